@@ -1,4 +1,6 @@
 use std::borrow::Borrow;
+use std::cell::RefCell;
+use std::env;
 use std::rc::Rc;
 use std::thread;
 use std::thread::{JoinHandle, Thread};
@@ -12,9 +14,15 @@ use crate::model::hittableObject::HittableObject;
 use crate::model::lambertian::Lambertian;
 use crate::model::metal::Metal;
 use crate::model::sphere::Sphere;
+use crate::render::aov::AOV_NAMES;
 use crate::render::camera::Camera;
+use crate::render::denoiser::Denoiser;
+use crate::render::filter::{createFilter, Filter};
+use crate::render::integrator::{createIntegrator, Integrator, INTEGRATOR_NAMES};
 use crate::render::renderer::Renderer;
+use crate::render::sampler::{createSampler, Sampler, SAMPLER_NAMES};
 use crate::render::scene::Scene;
+use crate::render::sceneLoader::SceneLoader;
 
 mod render;
 mod model;
@@ -38,13 +46,203 @@ fn main() {
 
     let cam: Camera = Camera::new(camPos,lookAt,resolution.x, resolution.y, 0.1, 10.0, 20.0);
 
-    let mut scene: Scene = Scene::randomScene();
-    scene.setCam(cam);
+    let args: Vec<String> = env::args().collect();
+
+    // Usage: [scene file] [--integrator <name>] [--sampler <name>] [--adaptive <max samples> <threshold>]
+    //        [--sample-heatmap <image file>] [--denoise] [--layers <name,name,...|all> <image file>]
+    //        [--filter <name> [radius]] [--samples <count>] [--crop <x> <y> <width> <height>]
+    //        [--resume <state file>] [--save-state <state file>] [--spectral]
+    //        [--terrain]
+    let mut scenePath: Option<String> = None;
+    let mut integrator: Option<Box<dyn Integrator>> = None;
+    let mut sampler: Option<Rc<RefCell<dyn Sampler>>> = None;
+    let mut adaptive: Option<(i32, f32)> = None;
+    let mut heatmapPath: Option<String> = None;
+    let mut denoise: bool = false;
+    let mut spectral: bool = false;
+    let mut terrain: bool = false;
+    let mut layers: Option<(Vec<String>, String)> = None;
+    let mut filter: Option<Rc<dyn Filter>> = None;
+    let mut sampling: Option<i32> = None;
+    let mut crop: Option<Vec<i32>> = None;
+    let mut resumePath: Option<String> = None;
+    let mut statePath: Option<String> = None;
+    let mut argIndex: usize = 1;
+    while argIndex < args.len() {
+        if args[argIndex] == "--integrator" {
+            let name: &str = args.get(argIndex + 1).map(|s| s.as_str()).unwrap_or("");
+            integrator = createIntegrator(name);
+            if integrator.is_none() {
+                println!("Unknown integrator '{}', expected one of {}", name, INTEGRATOR_NAMES.join(", "));
+                return;
+            }
+            argIndex += 2;
+        } else if args[argIndex] == "--sampler" {
+            let name: &str = args.get(argIndex + 1).map(|s| s.as_str()).unwrap_or("");
+            sampler = createSampler(name);
+            if sampler.is_none() {
+                println!("Unknown sampler '{}', expected one of {}", name, SAMPLER_NAMES.join(", "));
+                return;
+            }
+            argIndex += 2;
+        } else if args[argIndex] == "--adaptive" {
+            let maxSampling: Option<i32> = args.get(argIndex + 1).and_then(|s| s.parse().ok());
+            let threshold: Option<f32> = args.get(argIndex + 2).and_then(|s| s.parse().ok());
+            match (maxSampling, threshold) {
+                (Some(maxSampling), Some(threshold)) => adaptive = Some((maxSampling, threshold)),
+                _ => {
+                    println!("--adaptive expects the most samples per pixel and a relative error threshold");
+                    return;
+                }
+            }
+            argIndex += 3;
+        } else if args[argIndex] == "--sample-heatmap" {
+            heatmapPath = args.get(argIndex + 1).cloned();
+            if heatmapPath.is_none() {
+                println!("--sample-heatmap expects an image file");
+                return;
+            }
+            argIndex += 2;
+        } else if args[argIndex] == "--layers" {
+            let (names, path) = match (args.get(argIndex + 1), args.get(argIndex + 2)) {
+                (Some(names), Some(path)) => (names, path),
+                _ => {
+                    println!("--layers expects a comma separated list of layers and an image file");
+                    return;
+                }
+            };
+            let names: Vec<String> = if names == "all" {
+                AOV_NAMES.iter().map(|name| name.to_string()).collect()
+            } else {
+                names.split(',').map(|name| name.trim().to_string()).collect()
+            };
+            if let Some(unknown) = names.iter().find(|name| !AOV_NAMES.contains(&name.as_str())) {
+                println!("Unknown layer '{}', expected one of {}", unknown, AOV_NAMES.join(", "));
+                return;
+            }
+            layers = Some((names, path.clone()));
+            argIndex += 3;
+        } else if args[argIndex] == "--filter" {
+            let name: &str = args.get(argIndex + 1).map(|s| s.as_str()).unwrap_or("");
+            let radius: Option<f32> = args.get(argIndex + 2).and_then(|s| s.parse().ok());
+            match createFilter(name, radius) {
+                Ok(created) => filter = Some(created),
+                Err(message) => {
+                    println!("Failed to create the filter: {}", message);
+                    return;
+                }
+            }
+            argIndex += if radius.is_some() { 3 } else { 2 };
+        } else if args[argIndex] == "--samples" {
+            sampling = args.get(argIndex + 1).and_then(|s| s.parse().ok()).filter(|samples: &i32| *samples >= 1);
+            if sampling.is_none() {
+                println!("--samples expects a positive number of samples per pixel");
+                return;
+            }
+            argIndex += 2;
+        } else if args[argIndex] == "--crop" {
+            let values: Vec<i32> = args.iter().skip(argIndex + 1).take(4).filter_map(|s| s.parse().ok()).collect();
+            if values.len() != 4 {
+                println!("--crop expects the x, y, width and height of the region in pixels");
+                return;
+            }
+            crop = Some(values);
+            argIndex += 5;
+        } else if args[argIndex] == "--resume" || args[argIndex] == "--save-state" {
+            let path: Option<String> = args.get(argIndex + 1).cloned();
+            if path.is_none() {
+                println!("{} expects a state file", args[argIndex]);
+                return;
+            }
+            if args[argIndex] == "--resume" {
+                resumePath = path;
+            } else {
+                statePath = path;
+            }
+            argIndex += 2;
+        } else if args[argIndex] == "--denoise" {
+            denoise = true;
+            argIndex += 1;
+        } else if args[argIndex] == "--spectral" {
+            spectral = true;
+            argIndex += 1;
+        } else if args[argIndex] == "--terrain" {
+            terrain = true;
+            argIndex += 1;
+        } else {
+            scenePath = Some(args[argIndex].clone());
+            argIndex += 1;
+        }
+    }
+
+    let scene: Scene = if let Some(path) = scenePath {
+        match SceneLoader::load(&path, &resolution) {
+            Ok(loaded) => loaded,
+            Err(message) => {
+                println!("Failed to load the scene: {}", message);
+                return;
+            }
+        }
+    } else {
+        let mut randomScene: Scene = if terrain { Scene::randomSceneOnTerrain() } else { Scene::randomScene() };
+        randomScene.setCam(cam);
+        randomScene
+    };
 
     let mut renderer: Renderer = Renderer::new(scene, &resolution, img);
+    if let Some(chosen) = integrator {
+        renderer.setIntegrator(chosen);
+    }
+    if let Some(chosen) = sampler {
+        renderer.setSampler(chosen);
+    }
+    if let Some((maxSampling, threshold)) = adaptive {
+        renderer.setAdaptiveSampling(maxSampling, threshold);
+    }
+    if let Some(chosen) = filter {
+        renderer.setFilter(chosen);
+    }
+    if let Some(samples) = sampling {
+        renderer.setSampling(samples);
+    }
+    if let Some(region) = crop {
+        renderer.setCropWindow(region[0], region[1], region[2], region[3]);
+    }
+    renderer.setSpectral(spectral);
+    if denoise {
+        renderer.setDenoiser(Some(Denoiser::default()));
+    }
+    if let Some((names, _)) = &layers {
+        renderer.setLayers(names.clone());
+    }
+
+    // After everything the saved render has to match
+    if let Some(path) = resumePath {
+        if let Err(message) = renderer.resumeFrom(&path) {
+            println!("Failed to resume the render: {}", message);
+            return;
+        }
+    }
 
     renderer.render();
 
+    if let Some(path) = statePath {
+        if let Err(message) = renderer.saveState(&path) {
+            println!("Failed to save the render state: {}", message);
+        }
+    }
+
+    if let Some(path) = heatmapPath {
+        if !renderer.sampleCountHeatmap().save_to_file(&path) {
+            println!("Failed to save the sample heatmap to {}", path);
+        }
+    }
+    if let Some((_, path)) = layers {
+        if let Err(message) = renderer.saveLayers(&path) {
+            println!("Failed to save the layers: {}", message);
+        }
+    }
+
     loop {
         if window.is_open() {
             let event: Event = window.poll_event();
@@ -57,10 +255,10 @@ fn main() {
 
 
             let optTexture: Option<Texture> = Texture::new_from_image(&renderer.imageBuffer);
-            let mut tex: Texture;
+            let tex: Texture;
 
-            if optTexture.is_some() {
-                tex = optTexture.unwrap();
+            if let Some(texture) = optTexture {
+                tex = texture;
             } else {
                 println!("Texture is invalid!");
                 window.close();
@@ -0,0 +1,94 @@
+use glm::{max, min, Vec3, vec3};
+use crate::math::ray::Ray;
+
+/* Axis aligned bounding box */
+pub struct AABB {
+    pub minimum: Vec3,
+    pub maximum: Vec3,
+}
+
+impl AABB {
+    pub fn new(minimum: Vec3, maximum: Vec3) -> Self {
+        return Self {
+            minimum,
+            maximum,
+        };
+    }
+
+    /* Smallest box containing all of the given points */
+    pub fn new_from_points(points: &[Vec3]) -> Self {
+        let mut result: AABB = AABB::empty();
+        for point in points {
+            result.minimum = min(result.minimum, *point);
+            result.maximum = max(result.maximum, *point);
+        }
+        return result;
+    }
+
+    /* A box that contains nothing, so that growing it by any other box yields that box */
+    pub fn empty() -> Self {
+        return Self {
+            minimum: vec3(f32::MAX, f32::MAX, f32::MAX),
+            maximum: vec3(-f32::MAX, -f32::MAX, -f32::MAX),
+        };
+    }
+
+    pub fn surroundingBox(box0: &AABB, box1: &AABB) -> AABB {
+        return AABB {
+            minimum: min(box0.minimum, box1.minimum),
+            maximum: max(box0.maximum, box1.maximum),
+        };
+    }
+
+    /* Slab test, returns the entry and exit distances clipped to [tMin, tMax] */
+    pub fn intersect(&self, ray: &Ray, tMin: f32, tMax: f32) -> Option<(f32, f32)> {
+        let mut t0: f32 = tMin;
+        let mut t1: f32 = tMax;
+
+        for axis in 0..3 {
+            let invD: f32 = 1.0 / ray.direction[axis];
+            let mut tNear: f32 = (self.minimum[axis] - ray.origin[axis]) * invD;
+            let mut tFar: f32 = (self.maximum[axis] - ray.origin[axis]) * invD;
+
+            if invD < 0.0 {
+                std::mem::swap(&mut tNear, &mut tFar);
+            }
+
+            t0 = if tNear > t0 { tNear } else { t0 };
+            t1 = if tFar < t1 { tFar } else { t1 };
+
+            if t1 < t0 {
+                return None;
+            }
+        }
+
+        return Some((t0, t1));
+    }
+
+    pub fn hit(&self, ray: &Ray, tMin: f32, tMax: f32) -> bool {
+        return self.intersect(ray, tMin, tMax).is_some();
+    }
+
+    pub fn centroid(&self) -> Vec3 {
+        return (self.minimum + self.maximum) * 0.5;
+    }
+
+    pub fn extent(&self) -> Vec3 {
+        return self.maximum - self.minimum;
+    }
+}
+
+impl Clone for AABB {
+    fn clone(&self) -> Self {
+        return AABB {
+            minimum: self.minimum,
+            maximum: self.maximum,
+        };
+    }
+}
+
+impl Default for AABB {
+    fn default() -> Self {
+        return AABB::empty();
+    }
+}
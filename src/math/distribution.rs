@@ -0,0 +1,190 @@
+use glm::{Vec2, vec2};
+
+/* Piecewise-constant density over [0, 1) with one step per function value, sampled by inverting its CDF */
+pub struct Distribution1D {
+    function: Vec<f32>,
+    cdf: Vec<f32>,
+    integral: f32,
+}
+
+impl Distribution1D {
+    pub fn new(function: &[f32]) -> Self {
+        let count: usize = function.len();
+        let mut cdf: Vec<f32> = vec![0.0; count + 1];
+        for i in 1..count + 1 {
+            cdf[i] = cdf[i - 1] + f32::abs(function[i - 1]) / count as f32;
+        }
+
+        let integral: f32 = cdf[count];
+        for (i, value) in cdf.iter_mut().enumerate().skip(1) {
+            // An all zero function falls back to uniform sampling
+            *value = if integral == 0.0 { i as f32 / count as f32 } else { *value / integral };
+        }
+
+        return Self {
+            function: function.iter().map(|value| f32::abs(*value)).collect(),
+            cdf,
+            integral,
+        };
+    }
+
+    pub fn count(&self) -> usize {
+        return self.function.len();
+    }
+
+    pub fn getIntegral(&self) -> f32 {
+        return self.integral;
+    }
+
+    /* Returns the sampled position, its density and the index of the step it fell in */
+    pub fn sampleContinuous(&self, u: f32) -> (f32, f32, usize) {
+        // Last CDF entry that is not above u
+        let mut low: usize = 0;
+        let mut high: usize = self.count();
+        while low + 1 < high {
+            let middle: usize = (low + high) / 2;
+            if self.cdf[middle] <= u {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        let offset: usize = low;
+
+        let mut du: f32 = u - self.cdf[offset];
+        let width: f32 = self.cdf[offset + 1] - self.cdf[offset];
+        if width > 0.0 {
+            du /= width;
+        }
+
+        let pdf: f32 = if self.integral > 0.0 { self.function[offset] / self.integral } else { 1.0 };
+        return (f32::min((offset as f32 + du) / self.count() as f32, 1.0 - f32::EPSILON), pdf, offset);
+    }
+
+    /* Picks one of the steps, returns its index and probability */
+    pub fn sampleDiscrete(&self, u: f32) -> (usize, f32) {
+        let (_, _, offset) = self.sampleContinuous(u);
+        return (offset, self.probability(offset));
+    }
+
+    pub fn probability(&self, index: usize) -> f32 {
+        if self.integral <= 0.0 {
+            return 1.0 / self.count() as f32;
+        }
+        return self.function[index] / (self.integral * self.count() as f32);
+    }
+
+    pub fn pdf(&self, x: f32) -> f32 {
+        if self.integral <= 0.0 {
+            return 1.0;
+        }
+        let offset: usize = usize::min((f32::max(x, 0.0) * self.count() as f32) as usize, self.count() - 1);
+        return self.function[offset] / self.integral;
+    }
+}
+
+/* Density over the unit square from a grid of values: a marginal distribution picks the row, then the row's own
+   conditional distribution picks the column */
+pub struct Distribution2D {
+    conditional: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    /* `function` holds `rows` rows of `columns` values each */
+    pub fn new(function: &[f32], columns: usize, rows: usize) -> Self {
+        let conditional: Vec<Distribution1D> = (0..rows)
+            .map(|row| Distribution1D::new(&function[row * columns..(row + 1) * columns]))
+            .collect();
+        let rowIntegrals: Vec<f32> = conditional.iter().map(|distribution| distribution.getIntegral()).collect();
+
+        return Self {
+            conditional,
+            marginal: Distribution1D::new(&rowIntegrals),
+        };
+    }
+
+    /* Integral of the function over the unit square */
+    pub fn getIntegral(&self) -> f32 {
+        return self.marginal.getIntegral();
+    }
+
+    /* Returns the sampled point, x along the columns and y along the rows, with its density */
+    pub fn sampleContinuous(&self, u: &Vec2) -> (Vec2, f32) {
+        let (y, pdfRow, row) = self.marginal.sampleContinuous(u.y);
+        let (x, pdfColumn, _) = self.conditional[row].sampleContinuous(u.x);
+        return (vec2(x, y), pdfRow * pdfColumn);
+    }
+
+    pub fn pdf(&self, point: &Vec2) -> f32 {
+        let rows: usize = self.conditional.len();
+        let row: usize = usize::min((f32::max(point.y, 0.0) * rows as f32) as usize, rows - 1);
+        return self.marginal.pdf(point.y) * self.conditional[row].pdf(point.x);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn probabilitiesFollowTheFunction() {
+        let distribution: Distribution1D = Distribution1D::new(&[1.0, 3.0, 0.0, -4.0]);
+        assert_eq!(distribution.getIntegral(), 2.0);
+        let probabilities: Vec<f32> = (0..4).map(|index| distribution.probability(index)).collect();
+        assert_eq!(probabilities, vec![0.125, 0.375, 0.0, 0.5]);
+
+        // Evenly spread u pick the steps in proportion to their probability, and never the empty one
+        let mut counts: Vec<usize> = vec![0; 4];
+        let sampleCount: usize = 4000;
+        for i in 0..sampleCount {
+            let (index, probability) = distribution.sampleDiscrete((i as f32 + 0.5) / sampleCount as f32);
+            assert_eq!(probability, probabilities[index]);
+            counts[index] += 1;
+        }
+        for index in 0..4 {
+            assert!((counts[index] as f32 / sampleCount as f32 - probabilities[index]).abs() <= 1.0 / sampleCount as f32);
+        }
+    }
+
+    #[test]
+    fn continuousSamplesInvertTheCdf() {
+        let distribution: Distribution1D = Distribution1D::new(&[2.0, 0.5, 0.0, 1.5]);
+        let mut previous: f32 = 0.0;
+        for i in 0..1000 {
+            let u: f32 = i as f32 / 1000.0;
+            let (x, pdf, offset) = distribution.sampleContinuous(u);
+            assert!(x >= previous && x < 1.0);
+            assert_eq!(offset, (x * 4.0) as usize);
+            assert_eq!(pdf, distribution.pdf(x));
+            assert!(pdf > 0.0);
+            previous = x;
+        }
+        // The first step holds half of the integral
+        assert!((distribution.sampleContinuous(0.5).0 - 0.25).abs() < 1e-5);
+    }
+
+    #[test]
+    fn zeroFunctionSamplesUniformly() {
+        let distribution: Distribution1D = Distribution1D::new(&[0.0, 0.0, 0.0]);
+        for u in [0.0, 0.2, 0.5, 0.9] {
+            let (x, pdf, _) = distribution.sampleContinuous(u);
+            assert!((x - u).abs() < 1e-5);
+            assert_eq!(pdf, 1.0);
+        }
+        assert_eq!(distribution.probability(1), 1.0 / 3.0);
+    }
+
+    #[test]
+    fn twoDimensionalDensityMatchesItsSamples() {
+        let distribution: Distribution2D = Distribution2D::new(&[1.0, 2.0, 0.0, 3.0, 4.0, 2.0], 3, 2);
+        assert!((distribution.getIntegral() - 2.0).abs() < 1e-6);
+        for (ux, uy) in [(0.1, 0.1), (0.7, 0.2), (0.3, 0.8), (0.95, 0.6)] {
+            let (point, pdf) = distribution.sampleContinuous(&vec2(ux, uy));
+            assert!((pdf - distribution.pdf(&point)).abs() < 1e-5);
+            // Function value over the integral
+            let value: f32 = [1.0, 2.0, 0.0, 3.0, 4.0, 2.0][(point.y * 2.0) as usize * 3 + (point.x * 3.0) as usize];
+            assert!((pdf - value / 2.0).abs() < 1e-5);
+        }
+    }
+}
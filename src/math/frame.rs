@@ -0,0 +1,65 @@
+use glm::{cross, dot, normalize, Vec3, vec3};
+use crate::math::aabb::AABB;
+
+/* Orthonormal local coordinate system, with the y axis along the primitive's main axis */
+pub struct Frame {
+    pub origin: Vec3,
+    pub xAxis: Vec3,
+    pub yAxis: Vec3,
+    pub zAxis: Vec3,
+}
+
+impl Frame {
+    pub fn new(origin: Vec3, axis: Vec3) -> Self {
+        let yAxis: Vec3 = normalize(axis);
+        let helper: Vec3 = if f32::abs(yAxis.x) > 0.9 { vec3(0.0, 1.0, 0.0) } else { vec3(1.0, 0.0, 0.0) };
+        let zAxis: Vec3 = normalize(cross(helper, yAxis));
+        let xAxis: Vec3 = cross(yAxis, zAxis);
+
+        return Self {
+            origin,
+            xAxis,
+            yAxis,
+            zAxis,
+        };
+    }
+
+    pub fn vectorToLocal(&self, v: &Vec3) -> Vec3 {
+        return vec3(dot(*v, self.xAxis), dot(*v, self.yAxis), dot(*v, self.zAxis));
+    }
+
+    pub fn pointToLocal(&self, p: &Vec3) -> Vec3 {
+        return self.vectorToLocal(&(*p - self.origin));
+    }
+
+    pub fn vectorToWorld(&self, v: &Vec3) -> Vec3 {
+        return self.xAxis * v.x + self.yAxis * v.y + self.zAxis * v.z;
+    }
+
+    pub fn pointToWorld(&self, p: &Vec3) -> Vec3 {
+        return self.origin + self.vectorToWorld(p);
+    }
+
+    /* World space box around a box given in local coordinates */
+    pub fn boundingBox(&self, localMin: &Vec3, localMax: &Vec3) -> AABB {
+        let mut corners: Vec<Vec3> = Vec::with_capacity(8);
+        for i in 0..8 {
+            let corner: Vec3 = vec3(if i & 1 == 0 { localMin.x } else { localMax.x },
+                                    if i & 2 == 0 { localMin.y } else { localMax.y },
+                                    if i & 4 == 0 { localMin.z } else { localMax.z });
+            corners.push(self.pointToWorld(&corner));
+        }
+        return AABB::new_from_points(&corners);
+    }
+}
+
+impl Clone for Frame {
+    fn clone(&self) -> Self {
+        return Frame {
+            origin: self.origin,
+            xAxis: self.xAxis,
+            yAxis: self.yAxis,
+            zAxis: self.zAxis,
+        };
+    }
+}
@@ -0,0 +1,162 @@
+/* Largest f32 below one, sample values are kept in [0, 1) */
+pub const ONE_MINUS_EPSILON: f32 = 1.0 - f32::EPSILON / 2.0;
+
+/* Bases of the Halton sequence, one per dimension */
+pub const PRIMES: [u64; 64] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97, 101, 103, 107, 109,
+    113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193, 197, 199, 211, 223, 227, 229, 233, 239,
+    241, 251, 257, 263, 269, 271, 277, 281, 283, 293, 307, 311,
+];
+
+/* Scrambles the bits of `v` so that close inputs give unrelated outputs */
+pub fn mixBits(v: u64) -> u64 {
+    let mut v: u64 = v;
+    v ^= v >> 31;
+    v = v.wrapping_mul(0x7fb5d329728ea185);
+    v ^= v >> 27;
+    v = v.wrapping_mul(0x81dadef4bc2dd44d);
+    v ^= v >> 33;
+    return v;
+}
+
+/* Hash of a pixel, a dimension and a seed, what the scrambles of a sampler are keyed on */
+pub fn hashPixel(x: i32, y: i32, dimension: u32, seed: u64) -> u64 {
+    return mixBits(mixBits(mixBits(seed ^ x as u32 as u64) ^ y as u32 as u64) ^ dimension as u64);
+}
+
+/* Element `index` of a random permutation of [0, length) chosen by `seed`, Kensler's hash based permutation */
+pub fn permutationElement(index: u32, length: u32, seed: u32) -> u32 {
+    let mut mask: u32 = length.wrapping_sub(1);
+    mask |= mask >> 1;
+    mask |= mask >> 2;
+    mask |= mask >> 4;
+    mask |= mask >> 8;
+    mask |= mask >> 16;
+
+    // Cycle walking: the hash permutes the next power of two, values past the length go round again
+    let p: u32 = seed;
+    let mut i: u32 = index;
+    loop {
+        i ^= p;
+        i = i.wrapping_mul(0xe170893d);
+        i ^= p >> 16;
+        i ^= (i & mask) >> 4;
+        i ^= p >> 8;
+        i = i.wrapping_mul(0x0929eb3f);
+        i ^= p >> 23;
+        i ^= (i & mask) >> 1;
+        i = i.wrapping_mul(1 | p >> 27);
+        i = i.wrapping_mul(0x6935fa69);
+        i ^= (i & mask) >> 11;
+        i = i.wrapping_mul(0x74dcb303);
+        i ^= (i & mask) >> 2;
+        i = i.wrapping_mul(0x9e501cc3);
+        i ^= (i & mask) >> 2;
+        i = i.wrapping_mul(0xc860a3df);
+        i &= mask;
+        i ^= i >> 5;
+        if i < length {
+            break;
+        }
+    }
+    return i.wrapping_add(p) % length;
+}
+
+/* Radical inverse of `index` in `base` with the digits shuffled by a permutation that depends on the digits before
+   them, which is Owen's nested scrambling */
+pub fn owenScrambledRadicalInverse(base: u64, index: u64, seed: u64) -> f32 {
+    let inverseBase: f64 = 1.0 / base as f64;
+    let mut inverseBaseM: f64 = 1.0;
+    let mut reversedDigits: u64 = 0;
+    let mut index: u64 = index;
+
+    // Digits until they no longer change the value, past the index's own digits they are scrambled zeros
+    while 1.0 - (base - 1) as f64 * inverseBaseM < 1.0 {
+        let next: u64 = index / base;
+        let digitSeed: u64 = mixBits(seed ^ reversedDigits);
+        let digit: u64 = permutationElement((index - next * base) as u32, base as u32, digitSeed as u32) as u64;
+        reversedDigits = reversedDigits.wrapping_mul(base).wrapping_add(digit);
+        inverseBaseM *= inverseBase;
+        index = next;
+    }
+    return f32::min((reversedDigits as f64 * inverseBaseM) as f32, ONE_MINUS_EPSILON);
+}
+
+/* Owen scrambling of a 32 bit fixed point sample by Laine and Karras' hash, as refined by Burley */
+pub fn owenScramble(v: u32, seed: u32) -> u32 {
+    let mut v: u32 = v.reverse_bits();
+    v ^= v.wrapping_mul(0x3d20adea);
+    v = v.wrapping_add(seed);
+    v = v.wrapping_mul((seed >> 16) | 1);
+    v ^= v.wrapping_mul(0x05526c56);
+    v ^= v.wrapping_mul(0x53a22864);
+    return v.reverse_bits();
+}
+
+/* Point `index` of the first two dimensions of the Sobol sequence as 32 bit fixed point values: the van der Corput
+   sequence and the one made by the Pascal matrix */
+pub fn sobol2D(index: u32) -> (u32, u32) {
+    let mut second: u32 = 0;
+    let mut direction: u32 = 1 << 31;
+    let mut bits: u32 = index;
+    while bits != 0 {
+        if bits & 1 != 0 {
+            second ^= direction;
+        }
+        direction ^= direction >> 1;
+        bits >>= 1;
+    }
+    return (index.reverse_bits(), second);
+}
+
+/* 32 bit fixed point to [0, 1) */
+pub fn fixedToFloat(v: u32) -> f32 {
+    return f32::min(v as f32 * (1.0 / 4294967296.0), ONE_MINUS_EPSILON);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn permutationElementIsABijection() {
+        for length in [1, 2, 3, 5, 7, 10, 33, 100, 1000, 1023, 1025] {
+            for seed in [0, 1, 0x9e3779b9, 0xdeadbeef] {
+                let mut seen: Vec<bool> = vec![false; length as usize];
+                for index in 0..length {
+                    let element: u32 = permutationElement(index, length, seed);
+                    assert!(element < length);
+                    assert!(!seen[element as usize], "{} appears twice for length {} and seed {}", element, length, seed);
+                    seen[element as usize] = true;
+                }
+            }
+        }
+    }
+
+    /* Owen scrambling keeps the radical inverse a (0, 1)-sequence: the first base^k points fall one into each of the
+       base^k intervals of equal width */
+    #[test]
+    fn owenScrambledRadicalInverseStaysStratified() {
+        for (base, digits) in [(2u64, 10u32), (3, 6), (5, 4), (7, 3)] {
+            let count: u64 = base.pow(digits);
+            for seed in [0u64, 12345, mixBits(99)] {
+                let mut seen: Vec<bool> = vec![false; count as usize];
+                for index in 0..count {
+                    let value: f32 = owenScrambledRadicalInverse(base, index, seed);
+                    assert!((0.0..1.0).contains(&value));
+                    let interval: usize = (value as f64 * count as f64) as usize;
+                    assert!(!seen[interval], "two points in interval {} of base {} with seed {}", interval, base, seed);
+                    seen[interval] = true;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn owenScramblingDependsOnTheSeed() {
+        let first: Vec<f32> = (0..16).map(|index| owenScrambledRadicalInverse(3, index, 1)).collect();
+        let second: Vec<f32> = (0..16).map(|index| owenScrambledRadicalInverse(3, index, 2)).collect();
+        assert_ne!(first, second);
+        assert_eq!(first, (0..16).map(|index| owenScrambledRadicalInverse(3, index, 1)).collect::<Vec<f32>>());
+    }
+}
@@ -1,8 +1,44 @@
-﻿use std::ops::{Add, Mul};
-use glm::{dot, min, normalize, sqrt, to_vec3, Vec3, vec3, Vec4};
+﻿use std::cell::RefCell;
+use std::ops::{Add, Mul};
+use std::rc::Rc;
+use glm::{dot, min, sqrt, to_vec3, Vec2, vec2, Vec3, vec3, Vec4};
 use rand::{Rng, thread_rng};
+use crate::math::warp::{uniformDisk, uniformSphere};
 
 
+/* Where rendering takes its uniform random numbers from. A Metropolis chain replaces the generator with the sample
+   vector it mutates, so that the same numbers replay the same path, and the renderer with its pixel sampler */
+pub trait SampleSource {
+    /* Uniform in [0, 1), the next dimension of the sample */
+    fn next(&mut self) -> f32;
+
+    /* Two dimensions that are used together, like a point on the lens. Samplers that stratify pairs do it here */
+    fn next2D(&mut self) -> Vec2 {
+        return vec2(self.next(), self.next());
+    }
+}
+
+thread_local! {
+    static SAMPLE_SOURCE: RefCell<Option<Rc<RefCell<dyn SampleSource>>>> = RefCell::new(None);
+}
+
+/* Routes `random01` to `source` on this thread, none goes back to the thread's generator. Returns the source it
+   replaces, so that it can be put back */
+pub fn setSampleSource(source: Option<Rc<RefCell<dyn SampleSource>>>) -> Option<Rc<RefCell<dyn SampleSource>>> {
+    return SAMPLE_SOURCE.with(|current| std::mem::replace(&mut *current.borrow_mut(), source));
+}
+
+fn currentSampleSource() -> Option<Rc<RefCell<dyn SampleSource>>> {
+    return SAMPLE_SOURCE.with(|current| current.borrow().clone());
+}
+
+/* Uniform in [0, 1). Everything drawn while rendering goes through here, building a scene may use `thread_rng` */
+pub fn random01() -> f32 {
+    return match currentSampleSource() {
+        Some(source) => source.borrow_mut().next(),
+        None => thread_rng().gen_range(0.0..1.0),
+    };
+}
 
 pub fn reflect(v: &Vec3, n: &Vec3) -> Vec3 {
     return *v - *n * dot(*v,*n) * 2.0;
@@ -31,26 +67,28 @@ pub fn isNearlyZero_Vec3(vec: &Vec3, tolerance: Option<f32>) -> bool {
 
 pub fn randomVec3() -> Vec3 {
     return Vec3 {
-        x: thread_rng().gen(),
-        y: thread_rng().gen(),
-        z: thread_rng().gen(),
+        x: random01(),
+        y: random01(),
+        z: random01(),
+    };
+}
+
+/* Uniform in [0, 1)², for the warps */
+pub fn randomVec2() -> Vec2 {
+    return match currentSampleSource() {
+        Some(source) => source.borrow_mut().next2D(),
+        None => vec2(thread_rng().gen_range(0.0..1.0), thread_rng().gen_range(0.0..1.0)),
     };
 }
 
+/* Warped rather than rejected, so that every point takes the same sample dimensions */
 pub fn randomInUnitSphere() -> Vec3 {
-    loop {
-        let p: Vec3 = vec3(thread_rng().gen_range(-1.0..1.0),
-                           thread_rng().gen_range(-1.0..1.0),
-                           thread_rng().gen_range(-1.0..1.0));
-        if vectorLengthSquared(&p) >= 1.0 { continue; }
-        return p;
-    }
+    let direction: Vec3 = uniformSphere(&randomVec2());
+    return direction * random01().cbrt();
 }
 
 pub fn randomUnitVector3() -> Vec3 {
-    let vec: Vec3 = randomInUnitSphere();
-    let norm: Vec3 = normalize(vec);
-    return norm;
+    return uniformSphere(&randomVec2());
 }
 
 pub fn randomInHemisphere(n: &Vec3) -> Vec3 {
@@ -70,12 +108,17 @@ pub fn refract(uv: &Vec3, n: &Vec3, etaiOverEtat: &f32) -> Vec3 {
     return rOutParallel + rOutPersp;
 }
 
-pub fn randomInUnitDisk() -> Vec3 {
-    loop {
-        let p: Vec3 = vec3(thread_rng().gen_range(-1.0..1.0),thread_rng().gen_range(-1.0..1.0),0.0 );
-        if vectorLengthSquared(&p) >= 1.0 {
-            continue;
-        }
-        return p;
+/* Multiple importance sampling weight of a sample from strategy A, Veach's power heuristic with an exponent of 2 */
+pub fn powerHeuristic(pdfA: f32, pdfB: f32) -> f32 {
+    let a: f32 = pdfA * pdfA;
+    let b: f32 = pdfB * pdfB;
+    if a + b <= 0.0 {
+        return 0.0;
     }
+    return a / (a + b);
+}
+
+pub fn randomInUnitDisk() -> Vec3 {
+    let p: Vec2 = uniformDisk(&randomVec2());
+    return vec3(p.x, p.y, 0.0);
 }
@@ -1,3 +1,11 @@
 ﻿pub mod mathUtils;
 pub mod ray;
-pub mod vColor;
\ No newline at end of file
+pub mod vColor;
+pub mod spectrum;
+pub mod perlin;
+pub mod aabb;
+pub mod frame;
+pub mod polynomial;
+pub mod distribution;
+pub mod warp;
+pub mod lowDiscrepancy;
\ No newline at end of file
@@ -0,0 +1,87 @@
+use glm::{dot, floor, normalize, Vec3, vec3};
+use rand::{Rng, thread_rng};
+use rand::seq::SliceRandom;
+
+const POINT_COUNT: usize = 256;
+
+/* Gradient noise over a lattice of random unit vectors */
+pub struct Perlin {
+    randomVectors: Vec<Vec3>,
+    permX: Vec<usize>,
+    permY: Vec<usize>,
+    permZ: Vec<usize>,
+}
+
+impl Perlin {
+    pub fn new() -> Self {
+        let mut randomVectors: Vec<Vec3> = Vec::with_capacity(POINT_COUNT);
+        for _ in 0..POINT_COUNT {
+            randomVectors.push(normalize(vec3(thread_rng().gen_range(-1.0..1.0),
+                                              thread_rng().gen_range(-1.0..1.0),
+                                              thread_rng().gen_range(-1.0..1.0))));
+        }
+
+        return Self {
+            randomVectors,
+            permX: Perlin::generatePermutation(),
+            permY: Perlin::generatePermutation(),
+            permZ: Perlin::generatePermutation(),
+        };
+    }
+
+    fn generatePermutation() -> Vec<usize> {
+        let mut perm: Vec<usize> = (0..POINT_COUNT).collect();
+        perm.shuffle(&mut thread_rng());
+        return perm;
+    }
+
+    /* Noise value in roughly [-1, 1] */
+    pub fn noise(&self, p: &Vec3) -> f32 {
+        let cell: Vec3 = floor(*p);
+        let u: f32 = p.x - cell.x;
+        let v: f32 = p.y - cell.y;
+        let w: f32 = p.z - cell.z;
+
+        let i: i32 = cell.x as i32;
+        let j: i32 = cell.y as i32;
+        let k: i32 = cell.z as i32;
+
+        let uu: f32 = u * u * (3.0 - 2.0 * u);
+        let vv: f32 = v * v * (3.0 - 2.0 * v);
+        let ww: f32 = w * w * (3.0 - 2.0 * w);
+
+        let mut accum: f32 = 0.0;
+        for di in 0..2 {
+            for dj in 0..2 {
+                for dk in 0..2 {
+                    let index: usize = self.permX[((i + di) & 255) as usize]
+                        ^ self.permY[((j + dj) & 255) as usize]
+                        ^ self.permZ[((k + dk) & 255) as usize];
+                    let weight: Vec3 = vec3(u - di as f32, v - dj as f32, w - dk as f32);
+
+                    accum += (di as f32 * uu + (1 - di) as f32 * (1.0 - uu))
+                        * (dj as f32 * vv + (1 - dj) as f32 * (1.0 - vv))
+                        * (dk as f32 * ww + (1 - dk) as f32 * (1.0 - ww))
+                        * dot(self.randomVectors[index], weight);
+                }
+            }
+        }
+
+        return accum;
+    }
+
+    /* Sum of several octaves of noise, in roughly [0, 1] */
+    pub fn turbulence(&self, p: &Vec3, depth: i32) -> f32 {
+        let mut accum: f32 = 0.0;
+        let mut point: Vec3 = *p;
+        let mut weight: f32 = 1.0;
+
+        for _ in 0..depth {
+            accum += weight * self.noise(&point);
+            weight *= 0.5;
+            point = point * 2.0;
+        }
+
+        return f32::abs(accum);
+    }
+}
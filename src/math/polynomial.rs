@@ -0,0 +1,198 @@
+use std::f64::consts::PI;
+
+/* Closed form polynomial root finding after Jochen Schwarze, "Cubic and Quartic Roots" (Graphics Gems I).
+   Coefficients are given from the highest degree down, roots are returned in no particular order. */
+
+const EQN_EPS: f64 = 1e-9;
+
+fn isZero(x: f64) -> bool {
+    return x > -EQN_EPS && x < EQN_EPS;
+}
+
+/* Real roots of c[0] x² + c[1] x + c[2] */
+pub fn solveQuadratic(c: [f64; 3]) -> Vec<f64> {
+    let p: f64 = c[1] / (2.0 * c[0]);
+    let q: f64 = c[2] / c[0];
+    let d: f64 = p * p - q;
+
+    if isZero(d) {
+        return vec![-p];
+    }
+    if d < 0.0 {
+        return vec![];
+    }
+
+    let sqrtD: f64 = d.sqrt();
+    return vec![sqrtD - p, -sqrtD - p];
+}
+
+/* Real roots of c[0] x³ + c[1] x² + c[2] x + c[3] */
+pub fn solveCubic(c: [f64; 4]) -> Vec<f64> {
+    // Normal form x³ + Ax² + Bx + C = 0
+    let a: f64 = c[1] / c[0];
+    let b: f64 = c[2] / c[0];
+    let cc: f64 = c[3] / c[0];
+
+    // Substitute x = y - A/3 to eliminate the quadric term: y³ + 3py + 2q = 0
+    let sqA: f64 = a * a;
+    let p: f64 = 1.0 / 3.0 * (-1.0 / 3.0 * sqA + b);
+    let q: f64 = 1.0 / 2.0 * (2.0 / 27.0 * a * sqA - 1.0 / 3.0 * a * b + cc);
+
+    let cbP: f64 = p * p * p;
+    let d: f64 = q * q + cbP;
+
+    let mut roots: Vec<f64>;
+
+    if isZero(d) {
+        if isZero(q) {
+            roots = vec![0.0];
+        } else {
+            let u: f64 = (-q).cbrt();
+            roots = vec![2.0 * u, -u];
+        }
+    } else if d < 0.0 {
+        let phi: f64 = 1.0 / 3.0 * (-q / (-cbP).sqrt()).acos();
+        let t: f64 = 2.0 * (-p).sqrt();
+        roots = vec![t * phi.cos(), -t * (phi + PI / 3.0).cos(), -t * (phi - PI / 3.0).cos()];
+    } else {
+        let sqrtD: f64 = d.sqrt();
+        let u: f64 = (sqrtD - q).cbrt();
+        let v: f64 = -(sqrtD + q).cbrt();
+        roots = vec![u + v];
+    }
+
+    let sub: f64 = 1.0 / 3.0 * a;
+    for root in roots.iter_mut() {
+        *root -= sub;
+    }
+
+    return roots;
+}
+
+/* Real roots of c[0] x⁴ + c[1] x³ + c[2] x² + c[3] x + c[4] */
+pub fn solveQuartic(c: [f64; 5]) -> Vec<f64> {
+    // Normal form x⁴ + Ax³ + Bx² + Cx + D = 0
+    let a: f64 = c[1] / c[0];
+    let b: f64 = c[2] / c[0];
+    let cc: f64 = c[3] / c[0];
+    let dd: f64 = c[4] / c[0];
+
+    // Substitute x = y - A/4 to eliminate the cubic term: y⁴ + py² + qy + r = 0
+    let sqA: f64 = a * a;
+    let p: f64 = -3.0 / 8.0 * sqA + b;
+    let q: f64 = 1.0 / 8.0 * sqA * a - 1.0 / 2.0 * a * b + cc;
+    let r: f64 = -3.0 / 256.0 * sqA * sqA + 1.0 / 16.0 * sqA * b - 1.0 / 4.0 * a * cc + dd;
+
+    let mut roots: Vec<f64>;
+
+    if isZero(r) {
+        // No absolute term: y(y³ + py + q) = 0
+        roots = solveCubic([1.0, 0.0, p, q]);
+        roots.push(0.0);
+    } else {
+        // Solve the resolvent cubic and take one real root to build two quadratics
+        let cubic: Vec<f64> = solveCubic([1.0, -1.0 / 2.0 * p, -r, 1.0 / 2.0 * r * p - 1.0 / 8.0 * q * q]);
+        let z: f64 = cubic[0];
+
+        let mut u: f64 = z * z - r;
+        let mut v: f64 = 2.0 * z - p;
+
+        if isZero(u) {
+            u = 0.0;
+        } else if u > 0.0 {
+            u = u.sqrt();
+        } else {
+            return vec![];
+        }
+
+        if isZero(v) {
+            v = 0.0;
+        } else if v > 0.0 {
+            v = v.sqrt();
+        } else {
+            return vec![];
+        }
+
+        roots = solveQuadratic([1.0, if q < 0.0 { -v } else { v }, z - u]);
+        roots.extend(solveQuadratic([1.0, if q < 0.0 { v } else { -v }, z + u]));
+    }
+
+    let sub: f64 = 1.0 / 4.0 * a;
+    for root in roots.iter_mut() {
+        *root -= sub;
+    }
+
+    return roots;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /* Coefficients of the monic polynomial with the given roots, highest degree first */
+    fn fromRoots(roots: &[f64]) -> Vec<f64> {
+        let mut coefficients: Vec<f64> = vec![1.0];
+        for root in roots {
+            let mut next: Vec<f64> = coefficients.clone();
+            next.push(0.0);
+            for (i, c) in coefficients.iter().enumerate() {
+                next[i + 1] -= c * root;
+            }
+            coefficients = next;
+        }
+        return coefficients;
+    }
+
+    /* Every root is found and every root found is one of them, repeated roots may be reported once */
+    fn assertRoots(found: &[f64], expected: &[f64], tolerance: f64) {
+        for root in expected {
+            assert!(found.iter().any(|f| (f - root).abs() < tolerance), "missing {} in {:?}", root, found);
+        }
+        for f in found {
+            assert!(expected.iter().any(|root| (f - root).abs() < tolerance), "{} is no root, expected {:?}", f, expected);
+        }
+    }
+
+    fn cubic(roots: [f64; 3]) -> Vec<f64> {
+        let c: Vec<f64> = fromRoots(&roots);
+        return solveCubic([c[0], c[1], c[2], c[3]]);
+    }
+
+    fn quartic(roots: [f64; 4]) -> Vec<f64> {
+        let c: Vec<f64> = fromRoots(&roots);
+        return solveQuartic([c[0], c[1], c[2], c[3], c[4]]);
+    }
+
+    #[test]
+    fn quadraticRoots() {
+        assertRoots(&solveQuadratic([2.0, -2.0, -12.0]), &[3.0, -2.0], 1e-12);
+        assertRoots(&solveQuadratic([1.0, -4.0, 4.0]), &[2.0], 1e-12);
+        assert!(solveQuadratic([1.0, 0.0, 1.0]).is_empty());
+    }
+
+    #[test]
+    fn cubicRoots() {
+        assertRoots(&cubic([1.0, 2.0, 3.0]), &[1.0, 2.0, 3.0], 1e-9);
+        assertRoots(&cubic([-4.5, 0.25, 10.0]), &[-4.5, 0.25, 10.0], 1e-9);
+        assertRoots(&cubic([2.0, 2.0, -1.0]), &[2.0, -1.0], 1e-6);
+        assertRoots(&cubic([1.0, 1.0, 1.0]), &[1.0], 1e-6);
+        // x³ - x² + x - 1 = (x - 1)(x² + 1) has a single real root
+        assertRoots(&solveCubic([1.0, -1.0, 1.0, -1.0]), &[1.0], 1e-9);
+        // Scaled coefficients give the same roots
+        assertRoots(&solveCubic([3.0, -18.0, 33.0, -18.0]), &[1.0, 2.0, 3.0], 1e-9);
+    }
+
+    #[test]
+    fn quarticRoots() {
+        assertRoots(&quartic([-2.0, -1.0, 1.0, 3.0]), &[-2.0, -1.0, 1.0, 3.0], 1e-8);
+        assertRoots(&quartic([0.5, 1.5, 2.5, 7.0]), &[0.5, 1.5, 2.5, 7.0], 1e-8);
+        assertRoots(&quartic([0.0, 1.0, 2.0, 3.0]), &[0.0, 1.0, 2.0, 3.0], 1e-8);
+        assertRoots(&quartic([1.0, 1.0, 2.0, 2.0]), &[1.0, 2.0], 1e-4);
+        assertRoots(&quartic([-1.0, -1.0, -1.0, 4.0]), &[-1.0, 4.0], 1e-3);
+        // Close roots, as a ray grazing the inside of a torus gives
+        assertRoots(&quartic([1.0, 1.0 + 1e-3, 3.0, 4.0]), &[1.0, 1.0 + 1e-3, 3.0, 4.0], 1e-5);
+        // (x² + 1)(x² + 4) and (x² + 1)(x - 2)(x + 3)
+        assert!(solveQuartic([1.0, 0.0, 5.0, 0.0, 4.0]).is_empty());
+        assertRoots(&solveQuartic([1.0, 1.0, -5.0, 1.0, -6.0]), &[2.0, -3.0], 1e-8);
+    }
+}
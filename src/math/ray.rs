@@ -4,6 +4,8 @@ use glm::{Vec3, vec3};
 pub struct Ray {
     pub origin: Vec3,
     pub direction: Vec3,
+    /* Wavelength in nanometers carried by the ray in spectral mode, 0 when rendering in RGB */
+    pub wavelength: f32,
 }
 
 impl Ray {
@@ -11,6 +13,15 @@ impl Ray {
         return Self {
             origin: orig,
             direction: dir,
+            wavelength: 0.0,
+        };
+    }
+
+    pub fn new_spectral(orig: Vec3, dir: Vec3, wavelength: f32) -> Self {
+        return Self {
+            origin: orig,
+            direction: dir,
+            wavelength,
         };
     }
 
@@ -24,6 +35,17 @@ impl Default for Ray {
         return Self {
             origin: vec3(0.0,0.0,0.0),
             direction: vec3(0.0,0.0,0.0),
+            wavelength: 0.0,
+        }
+    }
+}
+
+impl Clone for Ray {
+    fn clone(&self) -> Self {
+        return Self {
+            origin: self.origin,
+            direction: self.direction,
+            wavelength: self.wavelength,
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,86 @@
+use glm::{exp, Vec3, vec3};
+use crate::math::mathUtils::random01;
+
+pub const WAVELENGTH_MIN: f32 = 380.0;
+pub const WAVELENGTH_MAX: f32 = 780.0;
+
+/* Mean RGB response of a constant spectrum over the sampled range, used to keep a flat spectrum white */
+const WHITE_BALANCE: [f32; 3] = [0.3209067, 0.2538716, 0.2426239];
+
+/* Relative luminance of a linear sRGB color */
+pub fn luminance(rgb: &Vec3) -> f32 {
+    return 0.2126 * rgb.x + 0.7152 * rgb.y + 0.0722 * rgb.z;
+}
+
+/* Picks a wavelength in nanometers uniformly from the visible range */
+pub fn sampleWavelength() -> f32 {
+    return WAVELENGTH_MIN + random01() * (WAVELENGTH_MAX - WAVELENGTH_MIN);
+}
+
+fn piecewiseGaussian(x: f32, mu: f32, sigmaLow: f32, sigmaHigh: f32) -> f32 {
+    let sigma: f32 = if x < mu { sigmaLow } else { sigmaHigh };
+    let t: f32 = (x - mu) / sigma;
+    return exp(-0.5 * t * t);
+}
+
+/* Analytic multi-lobe fit of the CIE 1931 color matching functions (Wyman, Sloan and Shirley 2013) */
+pub fn wavelengthToXYZ(wavelength: f32) -> Vec3 {
+    let x: f32 = 1.056 * piecewiseGaussian(wavelength, 599.8, 37.9, 31.0)
+        + 0.362 * piecewiseGaussian(wavelength, 442.0, 16.0, 26.7)
+        - 0.065 * piecewiseGaussian(wavelength, 501.1, 20.4, 26.2);
+    let y: f32 = 0.821 * piecewiseGaussian(wavelength, 568.8, 46.9, 40.5)
+        + 0.286 * piecewiseGaussian(wavelength, 530.9, 16.3, 31.1);
+    let z: f32 = 1.217 * piecewiseGaussian(wavelength, 437.0, 11.8, 36.0)
+        + 0.681 * piecewiseGaussian(wavelength, 459.0, 26.0, 13.8);
+
+    return vec3(x, y, z);
+}
+
+/* Converts CIE XYZ to linear sRGB */
+pub fn xyzToRGB(xyz: &Vec3) -> Vec3 {
+    return vec3(
+        3.2406 * xyz.x - 1.5372 * xyz.y - 0.4986 * xyz.z,
+        -0.9689 * xyz.x + 1.8758 * xyz.y + 0.0415 * xyz.z,
+        0.0557 * xyz.x - 0.2040 * xyz.y + 1.0570 * xyz.z,
+    );
+}
+
+/* RGB weight of a single uniformly sampled wavelength. Averaging it over many samples of a flat spectrum gives white */
+pub fn wavelengthToRGB(wavelength: f32) -> Vec3 {
+    let rgb: Vec3 = xyzToRGB(&wavelengthToXYZ(wavelength));
+    return vec3(rgb.x / WHITE_BALANCE[0], rgb.y / WHITE_BALANCE[1], rgb.z / WHITE_BALANCE[2]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flatSpectrumIntegratesToWhite() {
+        // Midpoint rule over the sampled range, the same average uniform wavelength samples converge to
+        let steps: usize = 4000;
+        let mut sum: Vec3 = vec3(0.0, 0.0, 0.0);
+        for i in 0..steps {
+            let wavelength: f32 = WAVELENGTH_MIN + (i as f32 + 0.5) / steps as f32 * (WAVELENGTH_MAX - WAVELENGTH_MIN);
+            sum = sum + wavelengthToRGB(wavelength);
+        }
+        let white: Vec3 = sum / steps as f32;
+        assert!((white.x - 1.0).abs() < 1e-3, "{:?}", white);
+        assert!((white.y - 1.0).abs() < 1e-3, "{:?}", white);
+        assert!((white.z - 1.0).abs() < 1e-3, "{:?}", white);
+    }
+
+    #[test]
+    fn sampledWavelengthsAverageToWhite() {
+        let samples: usize = 200000;
+        let mut sum: Vec3 = vec3(0.0, 0.0, 0.0);
+        for _ in 0..samples {
+            let wavelength: f32 = sampleWavelength();
+            assert!((WAVELENGTH_MIN..=WAVELENGTH_MAX).contains(&wavelength));
+            sum = sum + wavelengthToRGB(wavelength);
+        }
+        let white: Vec3 = sum / samples as f32;
+        assert!((luminance(&white) - 1.0).abs() < 0.02, "{:?}", white);
+        assert!(white.x > 0.95 && white.y > 0.95 && white.z > 0.95, "{:?}", white);
+    }
+}
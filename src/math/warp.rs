@@ -0,0 +1,45 @@
+use std::f32::consts::PI;
+use glm::{Vec2, vec2, Vec3, vec3};
+
+/* Maps uniform numbers in [0, 1)² onto other domains. Directions are local to a Frame, whose y axis is the pole */
+
+pub fn uniformSphere(u: &Vec2) -> Vec3 {
+    let cosTheta: f32 = 1.0 - 2.0 * u.x;
+    let sinTheta: f32 = f32::max(1.0 - cosTheta * cosTheta, 0.0).sqrt();
+    let phi: f32 = 2.0 * PI * u.y;
+    return vec3(sinTheta * phi.cos(), cosTheta, sinTheta * phi.sin());
+}
+
+pub fn uniformSpherePdf() -> f32 {
+    return 1.0 / (4.0 * PI);
+}
+
+/* Directions within the angle whose cosine is `cosMax` around the pole */
+pub fn uniformCone(u: &Vec2, cosMax: f32) -> Vec3 {
+    let cosTheta: f32 = 1.0 - u.x * (1.0 - cosMax);
+    let sinTheta: f32 = f32::max(1.0 - cosTheta * cosTheta, 0.0).sqrt();
+    let phi: f32 = 2.0 * PI * u.y;
+    return vec3(sinTheta * phi.cos(), cosTheta, sinTheta * phi.sin());
+}
+
+pub fn uniformConePdf(cosMax: f32) -> f32 {
+    return 1.0 / (2.0 * PI * (1.0 - cosMax));
+}
+
+/* Points on the unit disk, in x and y */
+pub fn uniformDisk(u: &Vec2) -> Vec2 {
+    let radius: f32 = u.x.sqrt();
+    let phi: f32 = 2.0 * PI * u.y;
+    return vec2(radius * phi.cos(), radius * phi.sin());
+}
+
+/* Hemisphere around the pole with a density proportional to the cosine, that is a disk point lifted up */
+pub fn cosineHemisphere(u: &Vec2) -> Vec3 {
+    let disk: Vec2 = uniformDisk(u);
+    let cosTheta: f32 = f32::max(1.0 - disk.x * disk.x - disk.y * disk.y, 0.0).sqrt();
+    return vec3(disk.x, cosTheta, disk.y);
+}
+
+pub fn cosineHemispherePdf(cosTheta: f32) -> f32 {
+    return f32::max(cosTheta, 0.0) / PI;
+}
@@ -0,0 +1,160 @@
+use std::f32::consts::PI;
+use std::rc::Rc;
+use glm::{cos, dot, length, normalize, sqrt, Vec3, vec3, vec4};
+use crate::math::mathUtils::{random01, reflect};
+use crate::math::ray::Ray;
+use crate::math::vColor::VColor;
+use crate::model::dielectric::Dielectric;
+use crate::model::hitRecord::HitRecord;
+use crate::model::material::Material;
+
+/* Wavelengths in nanometers standing in for the RGB channels when the film is evaluated without spectral rendering */
+const RGB_WAVELENGTHS: [f32; 3] = [650.0, 532.0, 450.0];
+
+/* A dielectric clearcoat over another material, with an optional thin film on top of the coat for iridescence */
+pub struct Coated {
+    base: Rc<dyn Material>,
+    coatIndex: f32,
+    /* Thickness of the thin film in nanometers, 0 disables the interference layer */
+    filmThickness: f32,
+    filmIndex: f32,
+}
+
+impl Coated {
+    pub fn new(base: Rc<dyn Material>, coatIndex: f32) -> Self {
+        return Self {
+            base,
+            coatIndex,
+            filmThickness: 0.0,
+            filmIndex: 1.0,
+        };
+    }
+
+    pub fn new_thin_film(base: Rc<dyn Material>, coatIndex: f32, filmThickness: f32, filmIndex: f32) -> Self {
+        return Self {
+            base,
+            coatIndex,
+            filmThickness,
+            filmIndex,
+        };
+    }
+
+    /* Reflectance of the air/film/coat stack for one wavelength, averaged over both polarizations */
+    fn thinFilmReflectance(&self, cosTheta: f32, wavelength: f32) -> f32 {
+        let n1: f32 = 1.0;
+        let n2: f32 = self.filmIndex;
+        let n3: f32 = self.coatIndex;
+
+        let sin1: f32 = sqrt(f32::max(0.0, 1.0 - cosTheta * cosTheta));
+        let sin2: f32 = n1 / n2 * sin1;
+        let sin3: f32 = n1 / n3 * sin1;
+
+        if sin2 >= 1.0 || sin3 >= 1.0 {
+            return 1.0;
+        }
+
+        let cos1: f32 = cosTheta;
+        let cos2: f32 = sqrt(1.0 - sin2 * sin2);
+        let cos3: f32 = sqrt(1.0 - sin3 * sin3);
+
+        let phase: f32 = 4.0 * PI * n2 * self.filmThickness * cos2 / wavelength;
+        let cosPhase: f32 = cos(phase);
+
+        let airy = |r12: f32, r23: f32| -> f32 {
+            let cross: f32 = 2.0 * r12 * r23 * cosPhase;
+            return (r12 * r12 + r23 * r23 + cross) / (1.0 + r12 * r12 * r23 * r23 + cross);
+        };
+
+        let rs: f32 = airy((n1 * cos1 - n2 * cos2) / (n1 * cos1 + n2 * cos2),
+                           (n2 * cos2 - n3 * cos3) / (n2 * cos2 + n3 * cos3));
+        let rp: f32 = airy((n2 * cos1 - n1 * cos2) / (n2 * cos1 + n1 * cos2),
+                           (n3 * cos2 - n2 * cos3) / (n3 * cos2 + n2 * cos3));
+
+        return 0.5 * (rs + rp);
+    }
+
+    /* Fraction of light reflected by the coat per channel, the rest reaches the base material */
+    pub fn coatReflectance(&self, cosTheta: f32, wavelength: f32) -> Vec3 {
+        if self.filmThickness <= 0.0 {
+            let r: f32 = Dielectric::reflectance(cosTheta, 1.0 / self.coatIndex);
+            return vec3(r, r, r);
+        }
+
+        if wavelength > 0.0 {
+            let r: f32 = self.thinFilmReflectance(cosTheta, wavelength);
+            return vec3(r, r, r);
+        }
+
+        return vec3(self.thinFilmReflectance(cosTheta, RGB_WAVELENGTHS[0]),
+                    self.thinFilmReflectance(cosTheta, RGB_WAVELENGTHS[1]),
+                    self.thinFilmReflectance(cosTheta, RGB_WAVELENGTHS[2]));
+    }
+}
+
+impl Material for Coated {
+    fn scatter(&self, ray: &Ray, hitRecord: &HitRecord, attenuation: &mut VColor, scattered: &mut Ray) -> bool {
+        if !hitRecord.frontFace {
+            return self.base.scatter(ray, hitRecord, attenuation, scattered);
+        }
+
+        let unitDirection: Vec3 = normalize(ray.direction);
+        let cosTheta: f32 = f32::min(dot(unitDirection * -1.0, hitRecord.shadingNormal), 1.0);
+
+        let reflectance: Vec3 = self.coatReflectance(cosTheta, ray.wavelength);
+        let reflectProbability: f32 = (reflectance.x + reflectance.y + reflectance.z) / 3.0;
+
+        if random01() < reflectProbability {
+            *scattered = Ray::new(hitRecord.point, reflect(&unitDirection, &hitRecord.shadingNormal));
+            let weight: Vec3 = reflectance / reflectProbability;
+            *attenuation = VColor::new_vec4(&vec4(weight.x, weight.y, weight.z, 1.0));
+            return true;
+        }
+
+        let mut baseAttenuation: VColor = VColor::default();
+        if !self.base.scatter(ray, hitRecord, &mut baseAttenuation, scattered) {
+            return false;
+        }
+
+        let transmitted: Vec3 = (vec3(1.0, 1.0, 1.0) - reflectance) / (1.0 - reflectProbability);
+        *attenuation = baseAttenuation * VColor::new_vec4(&vec4(transmitted.x, transmitted.y, transmitted.z, 1.0));
+        return true;
+    }
+
+    /* The coat itself is a mirror, direct light only reaches the base through it, once on the way in and once out */
+    fn eval(&self, ray: &Ray, hitRecord: &HitRecord, direction: &Vec3) -> VColor {
+        let baseValue: VColor = self.base.eval(ray, hitRecord, direction);
+        if !hitRecord.frontFace {
+            return baseValue;
+        }
+
+        let cosIn: f32 = f32::min(dot(*direction, hitRecord.shadingNormal), 1.0);
+        let cosOut: f32 = f32::min(dot(normalize(ray.direction) * -1.0, hitRecord.shadingNormal), 1.0);
+        let transmitted: Vec3 = (vec3(1.0, 1.0, 1.0) - self.coatReflectance(cosIn, ray.wavelength))
+            * (vec3(1.0, 1.0, 1.0) - self.coatReflectance(cosOut, ray.wavelength));
+        return baseValue * VColor::new_vec4(&vec4(transmitted.x, transmitted.y, transmitted.z, 1.0));
+    }
+
+    fn pdf(&self, ray: &Ray, hitRecord: &HitRecord, direction: &Vec3) -> f32 {
+        if !hitRecord.frontFace {
+            return self.base.pdf(ray, hitRecord, direction);
+        }
+
+        let cosTheta: f32 = f32::min(dot(normalize(ray.direction) * -1.0, hitRecord.shadingNormal), 1.0);
+        let reflectance: Vec3 = self.coatReflectance(cosTheta, ray.wavelength);
+        let reflectProbability: f32 = (reflectance.x + reflectance.y + reflectance.z) / 3.0;
+        return (1.0 - reflectProbability) * self.base.pdf(ray, hitRecord, direction);
+    }
+
+    /* The base lobe hits the exact mirror direction with probability zero, so that identifies the coat's reflection */
+    fn isSpecular(&self, ray: &Ray, hitRecord: &HitRecord, scattered: &Ray) -> bool {
+        if !hitRecord.frontFace {
+            return self.base.isSpecular(ray, hitRecord, scattered);
+        }
+
+        let mirrored: Vec3 = reflect(&normalize(ray.direction), &hitRecord.shadingNormal);
+        if length(normalize(scattered.direction) - normalize(mirrored)) < 1e-5 {
+            return true;
+        }
+        return self.base.isSpecular(ray, hitRecord, scattered);
+    }
+}
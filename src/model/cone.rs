@@ -0,0 +1,165 @@
+use std::f32::consts::PI;
+use std::rc::Rc;
+use glm::{atan2, cross, normalize, sqrt, Vec2, vec2, Vec3, vec3};
+use crate::math::aabb::AABB;
+use crate::math::frame::Frame;
+use crate::math::ray::Ray;
+use crate::model::hitRecord::HitRecord;
+use crate::model::hittableObject::HittableObject;
+use crate::model::material::Material;
+
+/* Cone with its base disk on a point and its apex a given height along the axis, the base is capped */
+pub struct Cone {
+    frame: Frame,
+    radius: f32,
+    height: f32,
+    material: Rc<dyn Material>,
+}
+
+impl Cone {
+    pub fn new(baseCenter: Vec3, axis: Vec3, radius: f32, height: f32, mat: Rc<dyn Material>) -> Self {
+        return Self {
+            frame: Frame::new(baseCenter, axis),
+            radius,
+            height,
+            material: mat,
+        };
+    }
+
+    /* Nearest hit in local coordinates as (t, local normal, uv, local tangent) */
+    fn intersectLocal(&self, origin: &Vec3, direction: &Vec3, tMin: f32, tMax: f32) -> Option<(f32, Vec3, Vec2, Vec3)> {
+        let mut closest: Option<(f32, Vec3, Vec2, Vec3)> = None;
+        let mut closestT: f32 = tMax;
+
+        // x² + z² = k² (h - y)²
+        let k: f32 = self.radius / self.height;
+        let k2: f32 = k * k;
+        let hy: f32 = self.height - origin.y;
+
+        let a: f32 = direction.x * direction.x + direction.z * direction.z - k2 * direction.y * direction.y;
+        let halfB: f32 = origin.x * direction.x + origin.z * direction.z + k2 * hy * direction.y;
+        let c: f32 = origin.x * origin.x + origin.z * origin.z - k2 * hy * hy;
+
+        let mut roots: Vec<f32> = vec![];
+        if f32::abs(a) > 1e-12 {
+            let discriminant: f32 = halfB * halfB - a * c;
+            if discriminant >= 0.0 {
+                let sqrtd: f32 = sqrt(discriminant);
+                let r0: f32 = (-halfB - sqrtd) / a;
+                let r1: f32 = (-halfB + sqrtd) / a;
+                roots = vec![f32::min(r0, r1), f32::max(r0, r1)];
+            }
+        } else if f32::abs(halfB) > 1e-12 {
+            roots = vec![-c / (2.0 * halfB)];
+        }
+
+        for root in roots {
+            if root < tMin || closestT < root {
+                continue;
+            }
+            let p: Vec3 = *origin + *direction * root;
+            if p.y < 0.0 || p.y > self.height {
+                continue;
+            }
+
+            let phi: f32 = atan2(p.z, p.x);
+            let normal: Vec3 = normalize(vec3(p.x, k2 * (self.height - p.y), p.z));
+            let tangent: Vec3 = if p.x * p.x + p.z * p.z > 1e-12 {
+                normalize(vec3(p.z, 0.0, -p.x))
+            } else {
+                vec3(0.0, 0.0, 1.0)
+            };
+            closestT = root;
+            closest = Some((root, normal, vec2(0.5 - phi / (2.0 * PI), p.y / self.height), tangent));
+            break;
+        }
+
+        if f32::abs(direction.y) > 1e-12 {
+            let root: f32 = -origin.y / direction.y;
+            if root >= tMin && root <= closestT {
+                let p: Vec3 = *origin + *direction * root;
+                if p.x * p.x + p.z * p.z <= self.radius * self.radius {
+                    let uv: Vec2 = vec2((p.x / self.radius + 1.0) * 0.5, (p.z / self.radius + 1.0) * 0.5);
+                    closest = Some((root, vec3(0.0, -1.0, 0.0), uv, vec3(1.0, 0.0, 0.0)));
+                }
+            }
+        }
+
+        return closest;
+    }
+}
+
+impl HittableObject for Cone {
+    fn testIntersection(&self, ray: &Ray, tMin: f32, tMax: f32, hitRecord: &mut HitRecord) -> bool {
+        let origin: Vec3 = self.frame.pointToLocal(&ray.origin);
+        let direction: Vec3 = self.frame.vectorToLocal(&ray.direction);
+
+        let (t, localNormal, uv, localTangent) = match self.intersectLocal(&origin, &direction, tMin, tMax) {
+            Some(hit) => hit,
+            None => return false,
+        };
+
+        let outwardNormal: Vec3 = self.frame.vectorToWorld(&localNormal);
+        let tangent: Vec3 = self.frame.vectorToWorld(&localTangent);
+
+        hitRecord.t = t;
+        hitRecord.point = ray.at(t);
+        hitRecord.material = Rc::clone(&self.material);
+        hitRecord.SetFaceNormal(ray, outwardNormal);
+        hitRecord.setSurfaceFrame(uv, tangent, cross(outwardNormal, tangent));
+
+        return true;
+    }
+
+    fn boundingBox(&self, outputBox: &mut AABB) -> bool {
+        *outputBox = self.frame.boundingBox(&vec3(-self.radius, 0.0, -self.radius), &vec3(self.radius, self.height, self.radius));
+        return true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glm::dot;
+    use crate::model::lambertian::Lambertian;
+    use super::*;
+
+    fn hit(cone: &Cone, origin: Vec3, direction: Vec3) -> Option<HitRecord> {
+        let mut hitRecord: HitRecord = HitRecord::default();
+        if !cone.testIntersection(&Ray::new(origin, direction), 0.001, f32::MAX, &mut hitRecord) {
+            return None;
+        }
+        return Some(hitRecord);
+    }
+
+    #[test]
+    fn sideAndBaseHits() {
+        // Base of radius 1 at the origin, apex at y = 2
+        let cone: Cone = Cone::new(vec3(0.0, 0.0, 0.0), vec3(0.0, 1.0, 0.0), 1.0, 2.0, Rc::new(Lambertian::default()));
+
+        // Halfway up the radius is 0.5 and the side leans back by atan(1/2)
+        let side: HitRecord = hit(&cone, vec3(-5.0, 1.0, 0.0), vec3(1.0, 0.0, 0.0)).unwrap();
+        assert!((side.t - 4.5).abs() < 1e-5);
+        assert!(side.frontFace);
+        assert!(dot(side.normal, normalize(vec3(-2.0, 1.0, 0.0))) > 0.9999);
+
+        let base: HitRecord = hit(&cone, vec3(0.3, -5.0, 0.0), vec3(0.0, 1.0, 0.0)).unwrap();
+        assert!((base.t - 5.0).abs() < 1e-5);
+        assert!(dot(base.normal, vec3(0.0, -1.0, 0.0)) > 0.9999);
+
+        // Straight down onto the side, near the apex
+        let top: HitRecord = hit(&cone, vec3(0.25, 5.0, 0.0), vec3(0.0, -1.0, 0.0)).unwrap();
+        assert!((top.t - 3.5).abs() < 1e-5);
+        assert!(dot(top.normal, normalize(vec3(2.0, 1.0, 0.0))) > 0.9999);
+    }
+
+    #[test]
+    fn raysOutsideTheConeMiss() {
+        let cone: Cone = Cone::new(vec3(0.0, 0.0, 0.0), vec3(0.0, 1.0, 0.0), 1.0, 2.0, Rc::new(Lambertian::default()));
+        assert!(hit(&cone, vec3(-5.0, 1.0, 0.6), vec3(1.0, 0.0, 0.0)).is_none());
+        assert!(hit(&cone, vec3(-5.0, 2.5, 0.0), vec3(1.0, 0.0, 0.0)).is_none());
+        assert!(hit(&cone, vec3(-5.0, -0.5, 0.0), vec3(1.0, 0.0, 0.0)).is_none());
+        // The mirrored nappe above the apex is not part of the cone
+        assert!(hit(&cone, vec3(-5.0, 3.0, 0.0), vec3(1.0, 0.0, 0.0)).is_none());
+        assert!(hit(&cone, vec3(1.2, -5.0, 0.0), vec3(0.0, 1.0, 0.0)).is_none());
+    }
+}
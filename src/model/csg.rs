@@ -0,0 +1,241 @@
+use glm::{max, min};
+use crate::math::aabb::AABB;
+use crate::math::ray::Ray;
+use crate::model::hitRecord::HitRecord;
+use crate::model::hittableObject::HittableObject;
+use crate::model::interval::Interval;
+
+pub enum CsgOperation {
+    Union,
+    Intersection,
+    /* Left minus right */
+    Difference,
+}
+
+impl CsgOperation {
+    fn isInside(&self, inLeft: bool, inRight: bool) -> bool {
+        return match self {
+            CsgOperation::Union => inLeft || inRight,
+            CsgOperation::Intersection => inLeft && inRight,
+            CsgOperation::Difference => inLeft && !inRight,
+        };
+    }
+}
+
+/* Boolean combination of two solids, which may be CSG nodes themselves */
+pub struct Csg {
+    operation: CsgOperation,
+    left: Box<dyn HittableObject>,
+    right: Box<dyn HittableObject>,
+    /* Box of the result, so rays that miss it skip the children */
+    bounds: AABB,
+    bounded: bool,
+}
+
+/* A boundary of one child's interval, as seen by the sweep along the ray */
+struct Event<'a> {
+    t: f32,
+    fromLeft: bool,
+    entering: bool,
+    record: &'a HitRecord,
+}
+
+impl Csg {
+    pub fn new(operation: CsgOperation, left: Box<dyn HittableObject>, right: Box<dyn HittableObject>) -> Self {
+        let mut bounds: AABB = AABB::default();
+        let bounded: bool = Csg::combinedBox(&operation, left.as_ref(), right.as_ref(), &mut bounds);
+        return Self {
+            operation,
+            left,
+            right,
+            bounds,
+            bounded,
+        };
+    }
+
+    fn combinedBox(operation: &CsgOperation, left: &dyn HittableObject, right: &dyn HittableObject, outputBox: &mut AABB) -> bool {
+        let mut leftBox: AABB = AABB::default();
+        let mut rightBox: AABB = AABB::default();
+        let leftBounded: bool = left.boundingBox(&mut leftBox);
+        let rightBounded: bool = right.boundingBox(&mut rightBox);
+
+        return match operation {
+            CsgOperation::Union => {
+                *outputBox = AABB::surroundingBox(&leftBox, &rightBox);
+                leftBounded && rightBounded
+            }
+            CsgOperation::Intersection => {
+                if leftBounded && rightBounded {
+                    *outputBox = AABB::new(max(leftBox.minimum, rightBox.minimum), min(leftBox.maximum, rightBox.maximum));
+                } else {
+                    *outputBox = if leftBounded { leftBox } else { rightBox };
+                }
+                leftBounded || rightBounded
+            }
+            CsgOperation::Difference => {
+                *outputBox = leftBox;
+                leftBounded
+            }
+        };
+    }
+
+    /* Takes a child's boundary over as a boundary of the result. Normals in hit records always face the ray,
+       so only the side has to be fixed up, which also flips the surfaces carved out by a difference */
+    fn boundary(record: &HitRecord, entering: bool) -> HitRecord {
+        let mut result: HitRecord = record.clone();
+        result.frontFace = entering;
+        return result;
+    }
+}
+
+impl HittableObject for Csg {
+    fn testIntersection(&self, ray: &Ray, tMin: f32, tMax: f32, hitRecord: &mut HitRecord) -> bool {
+        let mut intervals: Vec<Interval> = vec![];
+        if !self.intersectionIntervals(ray, &mut intervals) {
+            return false;
+        }
+
+        for interval in intervals.iter() {
+            for record in [&interval.enter, &interval.exit] {
+                if record.t >= tMin && record.t <= tMax && record.t.abs() < f32::MAX {
+                    *hitRecord = record.clone();
+                    return true;
+                }
+            }
+        }
+
+        return false;
+    }
+
+    fn boundingBox(&self, outputBox: &mut AABB) -> bool {
+        *outputBox = self.bounds.clone();
+        return self.bounded;
+    }
+
+    fn intersectionIntervals(&self, ray: &Ray, intervals: &mut Vec<Interval>) -> bool {
+        // Intervals cover the whole line, so the box is tested in both directions
+        if self.bounded && !self.bounds.hit(ray, -f32::MAX, f32::MAX) {
+            return false;
+        }
+
+        let mut leftIntervals: Vec<Interval> = vec![];
+        let mut rightIntervals: Vec<Interval> = vec![];
+        self.left.intersectionIntervals(ray, &mut leftIntervals);
+        self.right.intersectionIntervals(ray, &mut rightIntervals);
+
+        let mut events: Vec<Event> = Vec::with_capacity(2 * (leftIntervals.len() + rightIntervals.len()));
+        for (list, fromLeft) in [(&leftIntervals, true), (&rightIntervals, false)] {
+            for interval in list.iter() {
+                events.push(Event { t: interval.enter.t, fromLeft, entering: true, record: &interval.enter });
+                events.push(Event { t: interval.exit.t, fromLeft, entering: false, record: &interval.exit });
+            }
+        }
+        events.sort_by(|a, b| a.t.partial_cmp(&b.t).unwrap_or(std::cmp::Ordering::Equal));
+
+        let mut inLeft: bool = false;
+        let mut inRight: bool = false;
+        let mut enter: Option<HitRecord> = None;
+
+        for event in events.iter() {
+            let wasInside: bool = self.operation.isInside(inLeft, inRight);
+            if event.fromLeft { inLeft = event.entering; } else { inRight = event.entering; }
+            let isInside: bool = self.operation.isInside(inLeft, inRight);
+
+            if !wasInside && isInside {
+                enter = Some(Csg::boundary(event.record, true));
+            } else if wasInside && !isInside {
+                let entered: HitRecord = enter.take().unwrap_or(Interval::unbounded(-f32::MAX));
+                intervals.push(Interval::new(entered, Csg::boundary(event.record, false)));
+            }
+        }
+
+        if let Some(entered) = enter {
+            intervals.push(Interval::new(entered, Interval::unbounded(f32::MAX)));
+        }
+
+        return !intervals.is_empty();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use glm::{vec3, Vec3};
+    use crate::model::lambertian::Lambertian;
+    use crate::model::sphere::Sphere;
+    use super::*;
+
+    /* Unit spheres at the origin and one unit along x, crossed by a ray along x that starts at x = -5 */
+    fn overlappingSpheres(operation: CsgOperation) -> Csg {
+        let left: Sphere = Sphere::new(1.0, vec3(0.0, 0.0, 0.0), Rc::new(Lambertian::default()));
+        let right: Sphere = Sphere::new(1.0, vec3(1.0, 0.0, 0.0), Rc::new(Lambertian::default()));
+        return Csg::new(operation, Box::new(left), Box::new(right));
+    }
+
+    fn intervalsAlongX(csg: &Csg) -> Vec<Interval> {
+        let mut intervals: Vec<Interval> = vec![];
+        csg.intersectionIntervals(&Ray::new(vec3(-5.0, 0.0, 0.0), vec3(1.0, 0.0, 0.0)), &mut intervals);
+        return intervals;
+    }
+
+    fn outwardNormal(record: &HitRecord) -> Vec3 {
+        return if record.frontFace { record.normal } else { record.normal * -1.0 };
+    }
+
+    fn assertBoundary(record: &HitRecord, t: f32, outward: Vec3) {
+        assert!((record.t - t).abs() < 1e-3, "t {} instead of {}", record.t, t);
+        let normal: Vec3 = outwardNormal(record);
+        assert!((normal.x - outward.x).abs() < 1e-3 && (normal.y - outward.y).abs() < 1e-3 && (normal.z - outward.z).abs() < 1e-3);
+    }
+
+    #[test]
+    fn differenceEndsWhereTheRightSphereBegins() {
+        let intervals: Vec<Interval> = intervalsAlongX(&overlappingSpheres(CsgOperation::Difference));
+        assert_eq!(intervals.len(), 1);
+        assertBoundary(&intervals[0].enter, 4.0, vec3(-1.0, 0.0, 0.0));
+        // The carved out surface faces into the removed sphere
+        assertBoundary(&intervals[0].exit, 5.0, vec3(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn unionAndIntersectionIntervals() {
+        let union: Vec<Interval> = intervalsAlongX(&overlappingSpheres(CsgOperation::Union));
+        assert_eq!(union.len(), 1);
+        assertBoundary(&union[0].enter, 4.0, vec3(-1.0, 0.0, 0.0));
+        assertBoundary(&union[0].exit, 7.0, vec3(1.0, 0.0, 0.0));
+
+        let intersection: Vec<Interval> = intervalsAlongX(&overlappingSpheres(CsgOperation::Intersection));
+        assert_eq!(intersection.len(), 1);
+        assertBoundary(&intersection[0].enter, 5.0, vec3(-1.0, 0.0, 0.0));
+        assertBoundary(&intersection[0].exit, 6.0, vec3(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn rayInsideTheDifferenceHitsItsExit() {
+        let difference: Csg = overlappingSpheres(CsgOperation::Difference);
+        let ray: Ray = Ray::new(vec3(-5.0, 0.0, 0.0), vec3(1.0, 0.0, 0.0));
+        let mut hitRecord: HitRecord = HitRecord::default();
+        assert!(difference.testIntersection(&ray, 0.001, f32::MAX, &mut hitRecord));
+        assertBoundary(&hitRecord, 4.0, vec3(-1.0, 0.0, 0.0));
+        assert!(difference.testIntersection(&ray, 4.5, f32::MAX, &mut hitRecord));
+        assertBoundary(&hitRecord, 5.0, vec3(1.0, 0.0, 0.0));
+        assert!(!difference.testIntersection(&ray, 5.5, f32::MAX, &mut hitRecord));
+    }
+
+    #[test]
+    fn raysMissingTheBoxAreRejected() {
+        let intersection: Csg = overlappingSpheres(CsgOperation::Intersection);
+        let mut bounds: AABB = AABB::default();
+        assert!(intersection.boundingBox(&mut bounds));
+        assert!((bounds.minimum.x - 0.0).abs() < 1e-6 && (bounds.maximum.x - 1.0).abs() < 1e-6);
+
+        // Inside both children's boxes but outside the box of their intersection
+        let mut intervals: Vec<Interval> = vec![];
+        assert!(!intersection.intersectionIntervals(&Ray::new(vec3(-0.5, -5.0, 0.0), vec3(0.0, 1.0, 0.0)), &mut intervals));
+        assert!(intervals.is_empty());
+
+        // The box test covers the line behind the origin too
+        assert!(intersection.intersectionIntervals(&Ray::new(vec3(5.0, 0.0, 0.0), vec3(1.0, 0.0, 0.0)), &mut intervals));
+        assertBoundary(&intervals[0].enter, -5.0, vec3(-1.0, 0.0, 0.0));
+    }
+}
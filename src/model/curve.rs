@@ -0,0 +1,265 @@
+use std::f32::consts::SQRT_2;
+use std::rc::Rc;
+use glm::{clamp, cross, dot, length, log2, mix, normalize, sqrt, Vec2, vec2, Vec3, vec3};
+use crate::math::aabb::AABB;
+use crate::math::frame::Frame;
+use crate::math::ray::Ray;
+use crate::model::hitRecord::HitRecord;
+use crate::model::hittableObject::HittableObject;
+use crate::model::material::Material;
+
+pub enum CurveType {
+    /* Flat strip that always faces the incoming ray, cheap and fine for thin hair */
+    Ribbon,
+    /* Shaded as if it were a round tube, for thicker strands and grass blades */
+    Cylinder,
+}
+
+/* Cubic Bézier curve swept with a width varying linearly from the start to the end, intersected by recursive
+   subdivision in a coordinate system where the ray runs along +z (Nakamaru and Ohno, as done in pbrt) */
+pub struct Curve {
+    controlPoints: [Vec3; 4],
+    width0: f32,
+    width1: f32,
+    curveType: CurveType,
+    material: Rc<dyn Material>,
+}
+
+/* Nearest curve hit found so far, in curve parameters and ray space */
+struct CurveHit {
+    t: f32,
+    u: f32,
+    v: f32,
+}
+
+fn evalBezier(cp: &[Vec3; 4], u: f32) -> (Vec3, Vec3) {
+    let cp1: [Vec3; 3] = [mix(cp[0], cp[1], vec3(u, u, u)), mix(cp[1], cp[2], vec3(u, u, u)), mix(cp[2], cp[3], vec3(u, u, u))];
+    let cp2: [Vec3; 2] = [mix(cp1[0], cp1[1], vec3(u, u, u)), mix(cp1[1], cp1[2], vec3(u, u, u))];
+
+    let derivative: Vec3 = if length(cp2[1] - cp2[0]) > 0.0 {
+        (cp2[1] - cp2[0]) * 3.0
+    } else {
+        // Coincident control points, fall back to the chord
+        cp[3] - cp[0]
+    };
+
+    return (mix(cp2[0], cp2[1], vec3(u, u, u)), derivative);
+}
+
+/* de Casteljau split at the middle, the halves share the middle point */
+fn subdivideBezier(cp: &[Vec3; 4]) -> ([Vec3; 4], [Vec3; 4]) {
+    let half: f32 = 0.5;
+    let p01: Vec3 = (cp[0] + cp[1]) * half;
+    let p12: Vec3 = (cp[1] + cp[2]) * half;
+    let p23: Vec3 = (cp[2] + cp[3]) * half;
+    let p012: Vec3 = (p01 + p12) * half;
+    let p123: Vec3 = (p12 + p23) * half;
+    let middle: Vec3 = (p012 + p123) * half;
+
+    return ([cp[0], p01, p012, middle], [middle, p123, p23, cp[3]]);
+}
+
+impl Curve {
+    pub fn new(controlPoints: [Vec3; 4], width0: f32, width1: f32, curveType: CurveType, mat: Rc<dyn Material>) -> Self {
+        return Self {
+            controlPoints,
+            width0,
+            width1,
+            curveType,
+            material: mat,
+        };
+    }
+
+    fn width(&self, u: f32) -> f32 {
+        return mix(self.width0, self.width1, u);
+    }
+
+    /* `uRange` is the part of the curve `cp` covers, `zRange` the part of the ray that counts */
+    fn recursiveIntersect(&self, cp: &[Vec3; 4], uRange: (f32, f32), depth: i32, zRange: (f32, f32), closest: &mut Option<CurveHit>) {
+        let ((u0, u1), (zMin, zMax)) = (uRange, zRange);
+        let limit: f32 = match closest {
+            Some(hit) => f32::min(hit.t, zMax),
+            None => zMax,
+        };
+
+        let maxWidth: f32 = f32::max(self.width(u0), self.width(u1));
+        let curveBounds: AABB = AABB::new_from_points(cp);
+        let expand: f32 = 0.5 * maxWidth;
+        if curveBounds.minimum.x - expand > 0.0 || curveBounds.maximum.x + expand < 0.0
+            || curveBounds.minimum.y - expand > 0.0 || curveBounds.maximum.y + expand < 0.0
+            || curveBounds.minimum.z - expand > limit || curveBounds.maximum.z + expand < zMin {
+            return;
+        }
+
+        if depth > 0 {
+            let (first, second) = subdivideBezier(cp);
+            let uMiddle: f32 = (u0 + u1) * 0.5;
+            self.recursiveIntersect(&first, (u0, uMiddle), depth - 1, zRange, closest);
+            self.recursiveIntersect(&second, (uMiddle, u1), depth - 1, zRange, closest);
+            return;
+        }
+
+        // The ray passes through the origin, it must lie between the planes through the segment's end points
+        let edgeStart: f32 = (cp[1].y - cp[0].y) * -cp[0].y + cp[0].x * (cp[0].x - cp[1].x);
+        let edgeEnd: f32 = (cp[2].y - cp[3].y) * -cp[3].y + cp[3].x * (cp[3].x - cp[2].x);
+        if edgeStart < 0.0 || edgeEnd < 0.0 {
+            return;
+        }
+
+        let segment: Vec2 = vec2(cp[3].x - cp[0].x, cp[3].y - cp[0].y);
+        let denominator: f32 = dot(segment, segment);
+        if denominator == 0.0 {
+            return;
+        }
+
+        let w: f32 = dot(vec2(-cp[0].x, -cp[0].y), segment) / denominator;
+        let u: f32 = clamp(mix(u0, u1, w), u0, u1);
+        let hitWidth: f32 = self.width(u);
+
+        let (pc, dpcdw) = evalBezier(cp, clamp(w, 0.0, 1.0));
+        let distanceSquared: f32 = pc.x * pc.x + pc.y * pc.y;
+        if distanceSquared > hitWidth * hitWidth * 0.25 || pc.z < zMin || pc.z > limit {
+            return;
+        }
+
+        let distance: f32 = sqrt(distanceSquared);
+        let edge: f32 = dpcdw.x * -pc.y + pc.x * dpcdw.y;
+        let v: f32 = if edge > 0.0 { 0.5 + distance / hitWidth } else { 0.5 - distance / hitWidth };
+
+        *closest = Some(CurveHit { t: pc.z, u, v });
+    }
+}
+
+impl HittableObject for Curve {
+    fn testIntersection(&self, ray: &Ray, tMin: f32, tMax: f32, hitRecord: &mut HitRecord) -> bool {
+        let rayLength: f32 = length(ray.direction);
+        let direction: Vec3 = ray.direction / rayLength;
+
+        // Ray space: the frame's y axis follows the ray, swizzled so that it becomes z
+        let frame: Frame = Frame::new(ray.origin, direction);
+        let toRaySpace = |p: &Vec3| -> Vec3 {
+            let local: Vec3 = frame.pointToLocal(p);
+            return vec3(local.x, local.z, local.y);
+        };
+        let cp: [Vec3; 4] = [toRaySpace(&self.controlPoints[0]), toRaySpace(&self.controlPoints[1]),
+                             toRaySpace(&self.controlPoints[2]), toRaySpace(&self.controlPoints[3])];
+
+        // Refine until the segments are flat to within a fraction of the width
+        let mut l0: f32 = 0.0;
+        for i in 0..2 {
+            l0 = f32::max(l0, f32::max(f32::max(f32::abs(cp[i].x - 2.0 * cp[i + 1].x + cp[i + 2].x),
+                                                f32::abs(cp[i].y - 2.0 * cp[i + 1].y + cp[i + 2].y)),
+                                       f32::abs(cp[i].z - 2.0 * cp[i + 1].z + cp[i + 2].z)));
+        }
+        let epsilon: f32 = f32::max(self.width0, self.width1) * 0.05;
+        let depth: i32 = if l0 > 0.0 && epsilon > 0.0 {
+            clamp((log2(SQRT_2 * 6.0 * l0 / (8.0 * epsilon)) * 0.5).ceil(), 0.0, 10.0) as i32
+        } else {
+            0
+        };
+
+        let mut closest: Option<CurveHit> = None;
+        self.recursiveIntersect(&cp, (0.0, 1.0), depth, (tMin * rayLength, f32::min(tMax, f32::MAX / rayLength) * rayLength), &mut closest);
+
+        let hit: CurveHit = match closest {
+            Some(hit) => hit,
+            None => return false,
+        };
+
+        let t: f32 = hit.t / rayLength;
+        let (_, dpdu) = evalBezier(&self.controlPoints, hit.u);
+        let tangent: Vec3 = normalize(dpdu);
+
+        // Normal of a ribbon facing the ray, turned around the tangent across the width for the cylinder look
+        let facing: Vec3 = direction * -1.0 - tangent * dot(direction * -1.0, tangent);
+        let ribbonNormal: Vec3 = if length(facing) > 1e-8 { normalize(facing) } else { Frame::new(ray.origin, tangent).xAxis };
+        let outwardNormal: Vec3 = match self.curveType {
+            CurveType::Ribbon => ribbonNormal,
+            CurveType::Cylinder => {
+                let angle: f32 = (hit.v - 0.5) * std::f32::consts::PI;
+                let side: Vec3 = cross(tangent, ribbonNormal);
+                normalize(ribbonNormal * angle.cos() + side * angle.sin())
+            }
+        };
+
+        hitRecord.t = t;
+        hitRecord.point = ray.at(t);
+        hitRecord.material = Rc::clone(&self.material);
+        hitRecord.SetFaceNormal(ray, outwardNormal);
+        hitRecord.setSurfaceFrame(vec2(hit.u, hit.v), tangent, cross(outwardNormal, tangent));
+
+        return true;
+    }
+
+    fn boundingBox(&self, outputBox: &mut AABB) -> bool {
+        let controlBox: AABB = AABB::new_from_points(&self.controlPoints);
+        let expand: f32 = 0.5 * f32::max(self.width0, self.width1);
+        *outputBox = AABB::new(controlBox.minimum - vec3(expand, expand, expand), controlBox.maximum + vec3(expand, expand, expand));
+        return true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+    use crate::model::lambertian::Lambertian;
+    use super::*;
+
+    /* Straight curve along x from -1 to 1, tapering from a width of 0.2 to 0.1 */
+    fn straightCurve(curveType: CurveType) -> Curve {
+        let controlPoints: [Vec3; 4] = [vec3(-1.0, 0.0, 0.0), vec3(-1.0 / 3.0, 0.0, 0.0), vec3(1.0 / 3.0, 0.0, 0.0), vec3(1.0, 0.0, 0.0)];
+        return Curve::new(controlPoints, 0.2, 0.1, curveType, Rc::new(Lambertian::default()));
+    }
+
+    fn hitFromBelow(curve: &Curve, x: f32, y: f32) -> Option<HitRecord> {
+        let mut hitRecord: HitRecord = HitRecord::default();
+        if !curve.testIntersection(&Ray::new(vec3(x, y, -5.0), vec3(0.0, 0.0, 1.0)), 0.001, f32::MAX, &mut hitRecord) {
+            return None;
+        }
+        return Some(hitRecord);
+    }
+
+    #[test]
+    fn straightCurveIsHitAtItsCenter() {
+        let curve: Curve = straightCurve(CurveType::Ribbon);
+        let hit: HitRecord = hitFromBelow(&curve, 0.0, 0.0).unwrap();
+        assert!((hit.t - 5.0).abs() < 1e-3);
+        assert!((hit.uv.x - 0.5).abs() < 1e-3);
+        assert!((hit.uv.y - 0.5).abs() < 1e-3);
+        // A ribbon faces the ray, and the tangent follows the curve
+        assert!(dot(hit.normal, vec3(0.0, 0.0, -1.0)) > 0.999);
+        assert!(dot(hit.tangent, vec3(1.0, 0.0, 0.0)) > 0.999);
+
+        assert!(hitFromBelow(&curve, 0.0, 0.2).is_none());
+        assert!(hitFromBelow(&curve, 1.2, 0.0).is_none());
+        assert!(hitFromBelow(&curve, -1.2, 0.0).is_none());
+        // Hits behind the origin do not count
+        let mut hitRecord: HitRecord = HitRecord::default();
+        assert!(!curve.testIntersection(&Ray::new(vec3(0.0, 0.0, 5.0), vec3(0.0, 0.0, 1.0)), 0.001, f32::MAX, &mut hitRecord));
+    }
+
+    #[test]
+    fn widthTapersAlongTheCurve() {
+        let curve: Curve = straightCurve(CurveType::Ribbon);
+        // Half widths of 0.0875 a quarter along and 0.0625 three quarters along
+        assert!(hitFromBelow(&curve, -0.5, 0.08).is_some());
+        assert!(hitFromBelow(&curve, -0.5, 0.095).is_none());
+        assert!(hitFromBelow(&curve, 0.5, 0.055).is_some());
+        assert!(hitFromBelow(&curve, 0.5, 0.07).is_none());
+
+        let hit: HitRecord = hitFromBelow(&curve, 0.5, 0.05).unwrap();
+        assert!((hit.uv.x - 0.75).abs() < 1e-2);
+    }
+
+    #[test]
+    fn cylinderNormalsTurnAcrossTheWidth() {
+        let curve: Curve = straightCurve(CurveType::Cylinder);
+        let center: HitRecord = hitFromBelow(&curve, 0.0, 0.0).unwrap();
+        assert!(dot(center.normal, vec3(0.0, 0.0, -1.0)) > 0.999);
+
+        // Halfway to the edge the normal leans a quarter of the way around, towards the side of the hit
+        let side: HitRecord = hitFromBelow(&curve, 0.0, 0.0375).unwrap();
+        assert!(side.normal.y.abs() > 0.3);
+        assert!((side.normal.z + (PI / 4.0).cos()).abs() < 0.05);
+    }
+}
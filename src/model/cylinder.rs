@@ -0,0 +1,177 @@
+use std::f32::consts::PI;
+use std::rc::Rc;
+use glm::{atan2, cross, normalize, sqrt, Vec2, vec2, Vec3, vec3};
+use crate::math::aabb::AABB;
+use crate::math::frame::Frame;
+use crate::math::ray::Ray;
+use crate::model::hitRecord::HitRecord;
+use crate::model::hittableObject::HittableObject;
+use crate::model::material::Material;
+
+/* Cylinder standing on a base point along an axis, closed with flat caps */
+pub struct Cylinder {
+    frame: Frame,
+    radius: f32,
+    height: f32,
+    material: Rc<dyn Material>,
+}
+
+impl Cylinder {
+    pub fn new(baseCenter: Vec3, axis: Vec3, radius: f32, height: f32, mat: Rc<dyn Material>) -> Self {
+        return Self {
+            frame: Frame::new(baseCenter, axis),
+            radius,
+            height,
+            material: mat,
+        };
+    }
+
+    /* Nearest hit in local coordinates as (t, local normal, uv, local tangent) */
+    fn intersectLocal(&self, origin: &Vec3, direction: &Vec3, tMin: f32, tMax: f32) -> Option<(f32, Vec3, Vec2, Vec3)> {
+        let mut closest: Option<(f32, Vec3, Vec2, Vec3)> = None;
+        let mut closestT: f32 = tMax;
+
+        let a: f32 = direction.x * direction.x + direction.z * direction.z;
+        if a > 1e-12 {
+            let halfB: f32 = origin.x * direction.x + origin.z * direction.z;
+            let c: f32 = origin.x * origin.x + origin.z * origin.z - self.radius * self.radius;
+            let discriminant: f32 = halfB * halfB - a * c;
+
+            if discriminant >= 0.0 {
+                let sqrtd: f32 = sqrt(discriminant);
+                for root in [(-halfB - sqrtd) / a, (-halfB + sqrtd) / a] {
+                    if root < tMin || closestT < root {
+                        continue;
+                    }
+                    let p: Vec3 = *origin + *direction * root;
+                    if p.y < 0.0 || p.y > self.height {
+                        continue;
+                    }
+
+                    let phi: f32 = atan2(p.z, p.x);
+                    let normal: Vec3 = vec3(p.x, 0.0, p.z) / self.radius;
+                    let tangent: Vec3 = normalize(vec3(p.z, 0.0, -p.x));
+                    closestT = root;
+                    closest = Some((root, normal, vec2(0.5 - phi / (2.0 * PI), p.y / self.height), tangent));
+                    break;
+                }
+            }
+        }
+
+        if f32::abs(direction.y) > 1e-12 {
+            for (capY, normalY) in [(0.0, -1.0), (self.height, 1.0)] {
+                let root: f32 = (capY - origin.y) / direction.y;
+                if root < tMin || closestT < root {
+                    continue;
+                }
+                let p: Vec3 = *origin + *direction * root;
+                if p.x * p.x + p.z * p.z > self.radius * self.radius {
+                    continue;
+                }
+
+                let u: f32 = (p.x / self.radius + 1.0) * 0.5;
+                let v: f32 = (p.z / self.radius + 1.0) * 0.5;
+                closestT = root;
+                closest = if normalY > 0.0 {
+                    Some((root, vec3(0.0, 1.0, 0.0), vec2(v, u), vec3(0.0, 0.0, 1.0)))
+                } else {
+                    Some((root, vec3(0.0, -1.0, 0.0), vec2(u, v), vec3(1.0, 0.0, 0.0)))
+                };
+            }
+        }
+
+        return closest;
+    }
+}
+
+impl HittableObject for Cylinder {
+    fn testIntersection(&self, ray: &Ray, tMin: f32, tMax: f32, hitRecord: &mut HitRecord) -> bool {
+        let origin: Vec3 = self.frame.pointToLocal(&ray.origin);
+        let direction: Vec3 = self.frame.vectorToLocal(&ray.direction);
+
+        let (t, localNormal, uv, localTangent) = match self.intersectLocal(&origin, &direction, tMin, tMax) {
+            Some(hit) => hit,
+            None => return false,
+        };
+
+        let outwardNormal: Vec3 = self.frame.vectorToWorld(&localNormal);
+        let tangent: Vec3 = self.frame.vectorToWorld(&localTangent);
+
+        hitRecord.t = t;
+        hitRecord.point = ray.at(t);
+        hitRecord.material = Rc::clone(&self.material);
+        hitRecord.SetFaceNormal(ray, outwardNormal);
+        hitRecord.setSurfaceFrame(uv, tangent, cross(outwardNormal, tangent));
+
+        return true;
+    }
+
+    fn boundingBox(&self, outputBox: &mut AABB) -> bool {
+        *outputBox = self.frame.boundingBox(&vec3(-self.radius, 0.0, -self.radius), &vec3(self.radius, self.height, self.radius));
+        return true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glm::dot;
+    use crate::model::lambertian::Lambertian;
+    use super::*;
+
+    fn hit(object: &dyn HittableObject, origin: Vec3, direction: Vec3) -> Option<HitRecord> {
+        let mut hitRecord: HitRecord = HitRecord::default();
+        if !object.testIntersection(&Ray::new(origin, direction), 0.001, f32::MAX, &mut hitRecord) {
+            return None;
+        }
+        return Some(hitRecord);
+    }
+
+    #[test]
+    fn sideAndCapHits() {
+        let cylinder: Cylinder = Cylinder::new(vec3(0.0, 0.0, 0.0), vec3(0.0, 1.0, 0.0), 1.0, 2.0, Rc::new(Lambertian::default()));
+
+        let side: HitRecord = hit(&cylinder, vec3(-5.0, 1.0, 0.0), vec3(1.0, 0.0, 0.0)).unwrap();
+        assert!((side.t - 4.0).abs() < 1e-5);
+        assert!(dot(side.normal, vec3(-1.0, 0.0, 0.0)) > 0.9999);
+        assert!(side.frontFace);
+
+        let top: HitRecord = hit(&cylinder, vec3(0.5, 5.0, 0.0), vec3(0.0, -1.0, 0.0)).unwrap();
+        assert!((top.t - 3.0).abs() < 1e-5);
+        assert!(dot(top.normal, vec3(0.0, 1.0, 0.0)) > 0.9999);
+
+        let bottom: HitRecord = hit(&cylinder, vec3(0.0, -1.0, 0.5), vec3(0.0, 1.0, 0.0)).unwrap();
+        assert!((bottom.t - 1.0).abs() < 1e-5);
+        assert!(dot(bottom.normal, vec3(0.0, -1.0, 0.0)) > 0.9999);
+
+        // From inside the far wall is hit, with the normal turned towards the ray
+        let inside: HitRecord = hit(&cylinder, vec3(0.0, 1.0, 0.0), vec3(0.0, 0.0, 1.0)).unwrap();
+        assert!((inside.t - 1.0).abs() < 1e-5);
+        assert!(!inside.frontFace);
+        assert!(dot(inside.normal, vec3(0.0, 0.0, -1.0)) > 0.9999);
+    }
+
+    #[test]
+    fn raysPastTheEndsOrSideMiss() {
+        let cylinder: Cylinder = Cylinder::new(vec3(0.0, 0.0, 0.0), vec3(0.0, 1.0, 0.0), 1.0, 2.0, Rc::new(Lambertian::default()));
+        assert!(hit(&cylinder, vec3(-5.0, 2.5, 0.0), vec3(1.0, 0.0, 0.0)).is_none());
+        assert!(hit(&cylinder, vec3(-5.0, -0.5, 0.0), vec3(1.0, 0.0, 0.0)).is_none());
+        assert!(hit(&cylinder, vec3(-5.0, 1.0, 1.1), vec3(1.0, 0.0, 0.0)).is_none());
+        assert!(hit(&cylinder, vec3(1.5, 5.0, 0.0), vec3(0.0, -1.0, 0.0)).is_none());
+    }
+
+    #[test]
+    fn tiltedAxisIsFollowed() {
+        // Lying along +x, from x = 1 to x = 4
+        let cylinder: Cylinder = Cylinder::new(vec3(1.0, 0.0, 0.0), vec3(1.0, 0.0, 0.0), 0.5, 3.0, Rc::new(Lambertian::default()));
+
+        let side: HitRecord = hit(&cylinder, vec3(2.0, -5.0, 0.0), vec3(0.0, 1.0, 0.0)).unwrap();
+        assert!((side.t - 4.5).abs() < 1e-5);
+        assert!(dot(side.normal, vec3(0.0, -1.0, 0.0)) > 0.9999);
+
+        let cap: HitRecord = hit(&cylinder, vec3(-1.0, 0.2, 0.2), vec3(1.0, 0.0, 0.0)).unwrap();
+        assert!((cap.t - 2.0).abs() < 1e-5);
+        assert!(dot(cap.normal, vec3(-1.0, 0.0, 0.0)) > 0.9999);
+
+        assert!(hit(&cylinder, vec3(0.5, -5.0, 0.0), vec3(0.0, 1.0, 0.0)).is_none());
+    }
+}
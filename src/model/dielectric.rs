@@ -1,55 +1,196 @@
-use glm::{dot, normalize, pow, sqrt, Vec3};
-use rand::{Rng, thread_rng};
-use crate::math::mathUtils::{reflect, refract};
+use glm::{dot, exp, length, normalize, pow, sqrt, Vec3, vec3};
+use crate::math::mathUtils::{random01, reflect, refract};
 use crate::math::ray::Ray;
 use crate::math::vColor::VColor;
 use crate::model::hitRecord::HitRecord;
 use crate::model::material::Material;
 
+/* Wavelength dependence of the index of refraction, used when rays carry a wavelength */
+pub enum Dispersion {
+    None,
+    /* n(λ) = a + b / λ², with λ in micrometers */
+    Cauchy { a: f32, b: f32 },
+    /* n²(λ) = 1 + Σ b_i λ² / (λ² - c_i), with λ in micrometers and c_i in µm² */
+    Sellmeier { b: [f32; 3], c: [f32; 3] },
+}
+
+impl Dispersion {
+    pub fn index(&self, wavelength: f32) -> Option<f32> {
+        let micrometers: f32 = wavelength / 1000.0;
+        let l2: f32 = micrometers * micrometers;
+
+        return match self {
+            Dispersion::None => None,
+            Dispersion::Cauchy { a, b } => Some(a + b / l2),
+            Dispersion::Sellmeier { b, c } => {
+                let mut n2: f32 = 1.0;
+                for i in 0..3 {
+                    n2 += b[i] * l2 / (l2 - c[i]);
+                }
+                Some(sqrt(n2))
+            }
+        };
+    }
+}
+
+/* Wavelength of the sodium d-line, at which dispersive glasses report their RGB index */
+const D_LINE_WAVELENGTH: f32 = 587.6;
+
 pub struct Dielectric {
     indexOfRefraction: f32,
+    dispersion: Dispersion,
+    /* Absorption coefficient per unit distance travelled inside the medium, for each of the RGB channels */
+    absorption: Vec3,
 }
 
 impl Dielectric {
     pub fn new(index: f32) -> Self {
         return Self {
-            indexOfRefraction: index
+            indexOfRefraction: index,
+            dispersion: Dispersion::None,
+            absorption: vec3(0.0, 0.0, 0.0),
+        };
+    }
+
+    pub fn new_absorbing(index: f32, absorption: Vec3) -> Self {
+        return Self {
+            indexOfRefraction: index,
+            dispersion: Dispersion::None,
+            absorption,
+        };
+    }
+
+    pub fn new_dispersive(dispersion: Dispersion) -> Self {
+        return Self {
+            indexOfRefraction: dispersion.index(D_LINE_WAVELENGTH).unwrap_or(1.5),
+            dispersion,
+            absorption: vec3(0.0, 0.0, 0.0),
         };
     }
 
+    /* Schott N-BK7 crown glass */
+    pub fn new_crown_glass() -> Self {
+        return Self::new_dispersive(Dispersion::Sellmeier {
+            b: [1.039612, 0.2317923, 1.010469],
+            c: [0.006000699, 0.02001791, 103.5607],
+        });
+    }
+
+    /* Diamond, strongly dispersive */
+    pub fn new_diamond() -> Self {
+        return Self::new_dispersive(Dispersion::Sellmeier {
+            b: [0.3306, 4.3356, 0.0],
+            c: [0.030625, 0.011236, 0.0],
+        });
+    }
+
+    /* Index of refraction seen by the given ray, falling back to the constant index in RGB mode */
+    pub fn indexFor(&self, ray: &Ray) -> f32 {
+        if ray.wavelength <= 0.0 {
+            return self.indexOfRefraction;
+        }
+        return self.dispersion.index(ray.wavelength).unwrap_or(self.indexOfRefraction);
+    }
+
     pub fn reflectance(cos: f32, refIdx: f32) -> f32 {
         let mut r0: f32 = (1.0 - refIdx) / (1.0 + refIdx);
         r0 = r0 * r0;
         return r0 + (1.0 - r0) * pow(1.0 - cos, 5.0);
     }
+
+    /* Beer-Lambert transmittance over a given distance travelled inside the medium */
+    pub fn transmittance(&self, distance: f32) -> VColor {
+        let t: Vec3 = exp(self.absorption * -distance);
+        return VColor::new_rgb(t.x, t.y, t.z);
+    }
 }
 
 impl Material for Dielectric {
     fn scatter(&self, ray: &Ray, hitRecord: &HitRecord, attenuation: &mut VColor, scattered: &mut Ray) -> bool {
-        *attenuation = VColor::new_sc(1.0);
+        // Hitting the back face means the ray has just crossed the medium, so the hit distance is the path length inside it
+        *attenuation = if hitRecord.frontFace {
+            VColor::new_sc(1.0)
+        } else {
+            self.transmittance(hitRecord.t * length(ray.direction))
+        };
 
+        let index: f32 = self.indexFor(ray);
         let refractionRatio: f32 = if hitRecord.frontFace {
-            1.0 / self.indexOfRefraction
+            1.0 / index
         } else {
-            self.indexOfRefraction
+            index
         };
 
         let unitDirection: Vec3 = normalize(ray.direction);
 
-        let cosTheta: f32 = f32::min(dot(unitDirection * -1.0, hitRecord.normal), 1.0);
+        let cosTheta: f32 = f32::min(dot(unitDirection * -1.0, hitRecord.shadingNormal), 1.0);
         let sinTheta: f32 = sqrt(1.0 - cosTheta * cosTheta);
 
         let cannotRefract: bool = refractionRatio * sinTheta > 1.0;
-        let direction: Vec3;
-
-        if cannotRefract || Dielectric::reflectance(cosTheta,refractionRatio) > thread_rng().gen_range(0.0..1.0) {
-            direction = reflect(&unitDirection, &hitRecord.normal);
+        let direction: Vec3 = if cannotRefract || Dielectric::reflectance(cosTheta,refractionRatio) > random01() {
+            reflect(&unitDirection, &hitRecord.shadingNormal)
         } else {
-            direction = refract(&unitDirection,&hitRecord.normal,&refractionRatio);
-        }
+            refract(&unitDirection,&hitRecord.shadingNormal,&refractionRatio)
+        };
+
+        *scattered = Ray::new_spectral(hitRecord.point, direction, ray.wavelength);
+        return true;
+    }
 
-        *scattered = Ray::new(hitRecord.point, direction);
+    fn isSpecular(&self, _ray: &Ray, _hitRecord: &HitRecord, _scattered: &Ray) -> bool {
         return true;
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transmittanceFollowsBeerLambert() {
+        let glass: Dielectric = Dielectric::new_absorbing(1.5, vec3(0.5, 1.0, 2.0));
+        let t: Vec3 = glass.transmittance(2.0).getColorRGB();
+        assert!((t.x - f32::exp(-1.0)).abs() < 1e-6);
+        assert!((t.y - f32::exp(-2.0)).abs() < 1e-6);
+        assert!((t.z - f32::exp(-4.0)).abs() < 1e-6);
+        assert_eq!(glass.transmittance(0.0).getColorRGB(), vec3(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn absorptionIsAppliedWhenLeavingTheMedium() {
+        let glass: Dielectric = Dielectric::new_absorbing(1.5, vec3(0.5, 0.5, 0.5));
+        let ray: Ray = Ray::new(vec3(0.0, 0.0, 0.0), vec3(0.0, 0.0, 2.0));
+        let mut hitRecord: HitRecord = HitRecord::default();
+        hitRecord.SetFaceNormal(&ray, vec3(0.0, 0.0, 1.0));
+        hitRecord.t = 1.5;
+        hitRecord.point = ray.at(1.5);
+
+        // Leaving through the back face after 3 units inside
+        let mut attenuation: VColor = VColor::new_sc(0.0);
+        let mut scattered: Ray = Ray::new(vec3(0.0, 0.0, 0.0), vec3(0.0, 0.0, 1.0));
+        assert!(glass.scatter(&ray, &hitRecord, &mut attenuation, &mut scattered));
+        assert!((attenuation.getColorRGB().x - f32::exp(-1.5)).abs() < 1e-6);
+
+        // Entering through the front face nothing is absorbed yet
+        hitRecord.SetFaceNormal(&ray, vec3(0.0, 0.0, -1.0));
+        assert!(glass.scatter(&ray, &hitRecord, &mut attenuation, &mut scattered));
+        assert_eq!(attenuation.getColorRGB(), vec3(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn indexVariesWithWavelength() {
+        let glass: Dielectric = Dielectric::new_crown_glass();
+        let blue: f32 = glass.indexFor(&Ray::new_spectral(vec3(0.0, 0.0, 0.0), vec3(0.0, 0.0, 1.0), 450.0));
+        let red: f32 = glass.indexFor(&Ray::new_spectral(vec3(0.0, 0.0, 0.0), vec3(0.0, 0.0, 1.0), 650.0));
+        assert!(blue > red);
+        // N-BK7 is 1.5168 at the d-line, which is also its index for RGB rays
+        assert!((glass.indexFor(&Ray::new(vec3(0.0, 0.0, 0.0), vec3(0.0, 0.0, 1.0))) - 1.5168).abs() < 1e-3);
+        assert!((Dielectric::new_diamond().indexOfRefraction - 2.417).abs() < 2e-3);
+
+        let cauchy: Dispersion = Dispersion::Cauchy { a: 1.5, b: 0.01 };
+        assert!((cauchy.index(500.0).unwrap() - 1.54).abs() < 1e-5);
+        assert!(cauchy.index(400.0).unwrap() > cauchy.index(700.0).unwrap());
+        assert!(Dispersion::None.index(500.0).is_none());
+        assert_eq!(Dielectric::new(1.33).indexFor(&Ray::new_spectral(vec3(0.0, 0.0, 0.0), vec3(0.0, 0.0, 1.0), 450.0)), 1.33);
+    }
+}
@@ -0,0 +1,35 @@
+use glm::{Vec3, vec4};
+use crate::math::ray::Ray;
+use crate::math::vColor::VColor;
+use crate::model::hitRecord::HitRecord;
+use crate::model::material::Material;
+
+/* Emits the same radiance in every direction from the front side of a surface and reflects nothing */
+pub struct DiffuseLight {
+    radiance: Vec3,
+}
+
+impl DiffuseLight {
+    pub fn new(color: VColor, intensity: f32) -> Self {
+        return Self {
+            radiance: color.getColorRGB() * intensity,
+        };
+    }
+
+    pub fn getRadiance(&self) -> Vec3 {
+        return self.radiance;
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(&self, _ray: &Ray, _hitRecord: &HitRecord, _attenuation: &mut VColor, _scattered: &mut Ray) -> bool {
+        return false;
+    }
+
+    fn emitted(&self, _ray: &Ray, hitRecord: &HitRecord) -> VColor {
+        if !hitRecord.frontFace {
+            return VColor::default();
+        }
+        return VColor::new_vec4(&vec4(self.radiance.x, self.radiance.y, self.radiance.z, 1.0));
+    }
+}
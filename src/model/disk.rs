@@ -0,0 +1,87 @@
+use std::rc::Rc;
+use glm::{cross, dot, Vec2, vec2, Vec3, vec3};
+use crate::math::aabb::AABB;
+use crate::math::frame::Frame;
+use crate::math::ray::Ray;
+use crate::model::hitRecord::HitRecord;
+use crate::model::hittableObject::HittableObject;
+use crate::model::material::Material;
+
+/* Flat disk, UVs map the disk's bounding square to [0, 1] */
+pub struct Disk {
+    frame: Frame,
+    radius: f32,
+    material: Rc<dyn Material>,
+}
+
+impl Disk {
+    pub fn new(center: Vec3, normal: Vec3, radius: f32, mat: Rc<dyn Material>) -> Self {
+        return Self {
+            frame: Frame::new(center, normal),
+            radius,
+            material: mat,
+        };
+    }
+}
+
+impl HittableObject for Disk {
+    fn testIntersection(&self, ray: &Ray, tMin: f32, tMax: f32, hitRecord: &mut HitRecord) -> bool {
+        let denominator: f32 = dot(ray.direction, self.frame.yAxis);
+        if f32::abs(denominator) < 1e-8 {
+            return false;
+        }
+
+        let t: f32 = dot(self.frame.origin - ray.origin, self.frame.yAxis) / denominator;
+        if t < tMin || tMax < t {
+            return false;
+        }
+
+        let point: Vec3 = ray.at(t);
+        let local: Vec3 = self.frame.pointToLocal(&point);
+        if local.x * local.x + local.z * local.z > self.radius * self.radius {
+            return false;
+        }
+
+        hitRecord.t = t;
+        hitRecord.point = point;
+        hitRecord.material = Rc::clone(&self.material);
+        hitRecord.SetFaceNormal(ray, self.frame.yAxis);
+
+        let uv: Vec2 = vec2((local.z / self.radius + 1.0) * 0.5, (local.x / self.radius + 1.0) * 0.5);
+        hitRecord.setSurfaceFrame(uv, self.frame.zAxis, cross(self.frame.yAxis, self.frame.zAxis));
+
+        return true;
+    }
+
+    fn boundingBox(&self, outputBox: &mut AABB) -> bool {
+        // Slightly thickened so the box never degenerates to zero volume
+        *outputBox = self.frame.boundingBox(&vec3(-self.radius, -1e-4, -self.radius), &vec3(self.radius, 1e-4, self.radius));
+        return true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::lambertian::Lambertian;
+    use super::*;
+
+    #[test]
+    fn rayHitsInsideTheRadiusOnly() {
+        // Disk facing +x
+        let disk: Disk = Disk::new(vec3(1.0, 0.0, 0.0), vec3(1.0, 0.0, 0.0), 0.5, Rc::new(Lambertian::default()));
+
+        let mut hitRecord: HitRecord = HitRecord::default();
+        assert!(disk.testIntersection(&Ray::new(vec3(4.0, 0.3, -0.3), vec3(-1.0, 0.0, 0.0)), 0.001, f32::MAX, &mut hitRecord));
+        assert!((hitRecord.t - 3.0).abs() < 1e-5);
+        assert!(hitRecord.frontFace);
+        assert!(dot(hitRecord.normal, vec3(1.0, 0.0, 0.0)) > 0.9999);
+
+        assert!(disk.testIntersection(&Ray::new(vec3(-1.0, 0.0, 0.0), vec3(1.0, 0.0, 0.0)), 0.001, f32::MAX, &mut hitRecord));
+        assert!((hitRecord.t - 2.0).abs() < 1e-5);
+        assert!(dot(hitRecord.normal, vec3(-1.0, 0.0, 0.0)) > 0.9999);
+
+        assert!(!disk.testIntersection(&Ray::new(vec3(4.0, 0.4, 0.4), vec3(-1.0, 0.0, 0.0)), 0.001, f32::MAX, &mut hitRecord));
+        assert!(!disk.testIntersection(&Ray::new(vec3(4.0, 0.0, 0.0), vec3(0.0, 1.0, 0.0)), 0.001, f32::MAX, &mut hitRecord));
+        assert!(!disk.testIntersection(&Ray::new(vec3(4.0, 0.0, 0.0), vec3(1.0, 0.0, 0.0)), 0.001, f32::MAX, &mut hitRecord));
+    }
+}
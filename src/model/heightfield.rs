@@ -0,0 +1,325 @@
+use std::rc::Rc;
+use glm::{cross, dot, inverse, Mat3, normalize, transpose, Vec2, vec2, Vec3, vec3};
+use sfml::graphics::{Color, Image};
+use sfml::system::Vector2u;
+use crate::math::aabb::AABB;
+use crate::math::perlin::Perlin;
+use crate::math::ray::Ray;
+use crate::model::hitRecord::HitRecord;
+use crate::model::hittableObject::HittableObject;
+use crate::model::material::Material;
+
+/* Placement of a heightfield in the world: the grid covers the parallelogram spanned by uAxis and vAxis from
+   origin, and heightAxis is the offset of a sample of height 1 */
+pub struct GridAxes {
+    pub origin: Vec3,
+    pub uAxis: Vec3,
+    pub vAxis: Vec3,
+    pub heightAxis: Vec3,
+}
+
+/* Terrain made of a regular grid of height samples, two triangles per cell with interpolated normals.
+   Locally the grid spans x in [0, columns - 1] and z in [0, rows - 1] with heights in [0, 1] along y, and the
+   GridAxes place the whole grid in the world. */
+pub struct Heightfield {
+    heights: Vec<f32>,
+    columns: usize,
+    rows: usize,
+
+    origin: Vec3,
+    toWorld: Mat3,
+    toLocal: Mat3,
+    /* Transforms local normals to world normals, the inverse transpose of toWorld */
+    normalToWorld: Mat3,
+    tangent: Vec3,
+
+    material: Rc<dyn Material>,
+}
+
+impl Heightfield {
+    /* Heights are given row by row, each in [0, 1] */
+    pub fn new(heights: Vec<f32>, columns: usize, rows: usize, axes: GridAxes, mat: Rc<dyn Material>) -> Self {
+        let toWorld: Mat3 = Mat3::new(axes.uAxis / (columns - 1) as f32, axes.heightAxis, axes.vAxis / (rows - 1) as f32);
+        let toLocal: Mat3 = inverse(&toWorld);
+
+        return Self {
+            heights,
+            columns,
+            rows,
+            origin: axes.origin,
+            toWorld,
+            toLocal,
+            normalToWorld: transpose(&toLocal),
+            tangent: normalize(axes.uAxis),
+            material: mat,
+        };
+    }
+
+    /* Heights from the brightness of a grayscale image */
+    pub fn new_from_image(path: &str, axes: GridAxes, mat: Rc<dyn Material>) -> Option<Self> {
+        let image: Image = Image::new_from_file(path)?;
+        let size: Vector2u = image.get_size();
+        if size.x < 2 || size.y < 2 {
+            return None;
+        }
+
+        let mut heights: Vec<f32> = Vec::with_capacity((size.x * size.y) as usize);
+        for y in 0..size.y {
+            for x in 0..size.x {
+                let pixel: Color = image.get_pixel(x, y);
+                heights.push((pixel.0.red as f32 + pixel.0.green as f32 + pixel.0.blue as f32) / (3.0 * 255.0));
+            }
+        }
+
+        return Some(Heightfield::new(heights, size.x as usize, size.y as usize, axes, mat));
+    }
+
+    /* Heights from fractal Perlin noise, frequency is the number of noise features across the grid */
+    pub fn new_from_noise(columns: usize, rows: usize, frequency: f32, octaves: i32, axes: GridAxes, mat: Rc<dyn Material>) -> Self {
+        let noise: Perlin = Perlin::new();
+        let mut heights: Vec<f32> = Vec::with_capacity(columns * rows);
+
+        for z in 0..rows {
+            for x in 0..columns {
+                let p: Vec3 = vec3(x as f32 / (columns - 1) as f32, 0.0, z as f32 / (rows - 1) as f32) * frequency;
+                heights.push(f32::min(1.0, noise.turbulence(&p, octaves)));
+            }
+        }
+
+        return Heightfield::new(heights, columns, rows, axes, mat);
+    }
+
+    fn height(&self, x: usize, z: usize) -> f32 {
+        return self.heights[z * self.columns + x];
+    }
+
+    /* Vertex normal in local coordinates from central differences of the neighbouring samples */
+    fn vertexNormal(&self, x: usize, z: usize) -> Vec3 {
+        let left: f32 = self.height(if x > 0 { x - 1 } else { x }, z);
+        let right: f32 = self.height(usize::min(x + 1, self.columns - 1), z);
+        let back: f32 = self.height(x, if z > 0 { z - 1 } else { z });
+        let front: f32 = self.height(x, usize::min(z + 1, self.rows - 1));
+
+        let dx: f32 = (right - left) / (usize::min(x + 1, self.columns - 1) - if x > 0 { x - 1 } else { x }) as f32;
+        let dz: f32 = (front - back) / (usize::min(z + 1, self.rows - 1) - if z > 0 { z - 1 } else { z }) as f32;
+
+        return vec3(-dx, 1.0, -dz);
+    }
+
+    /* Möller-Trumbore, returns (t, b1, b2) for a hit within the range */
+    fn intersectTriangle(origin: &Vec3, direction: &Vec3, p0: &Vec3, p1: &Vec3, p2: &Vec3, tMin: f32, tMax: f32) -> Option<(f32, f32, f32)> {
+        let edge1: Vec3 = *p1 - *p0;
+        let edge2: Vec3 = *p2 - *p0;
+        let pvec: Vec3 = cross(*direction, edge2);
+        let det: f32 = dot(edge1, pvec);
+        if f32::abs(det) < 1e-12 {
+            return None;
+        }
+
+        let invDet: f32 = 1.0 / det;
+        let tvec: Vec3 = *origin - *p0;
+        let b1: f32 = dot(tvec, pvec) * invDet;
+        if !(0.0..=1.0).contains(&b1) {
+            return None;
+        }
+
+        let qvec: Vec3 = cross(tvec, edge1);
+        let b2: f32 = dot(*direction, qvec) * invDet;
+        if b2 < 0.0 || b1 + b2 > 1.0 {
+            return None;
+        }
+
+        let t: f32 = dot(edge2, qvec) * invDet;
+        if t < tMin || t > tMax {
+            return None;
+        }
+
+        return Some((t, b1, b2));
+    }
+
+    /* Tests both triangles of a cell against the ray in local coordinates, filling in the hit on success */
+    fn intersectCell(&self, ray: &Ray, localRay: &Ray, (x, z): (usize, usize), tMin: f32, tMax: f32, hitRecord: &mut HitRecord) -> bool {
+        let origin: &Vec3 = &localRay.origin;
+        let direction: &Vec3 = &localRay.direction;
+        let corners: [(usize, usize); 4] = [(x, z), (x + 1, z), (x + 1, z + 1), (x, z + 1)];
+        let positions: Vec<Vec3> = corners.iter().map(|&(cx, cz)| vec3(cx as f32, self.height(cx, cz), cz as f32)).collect();
+
+        let mut closest: Option<(f32, Vec3, Vec3)> = None;
+        let mut closestT: f32 = tMax;
+
+        for triangle in [[0, 1, 2], [0, 2, 3]] {
+            let (p0, p1, p2) = (&positions[triangle[0]], &positions[triangle[1]], &positions[triangle[2]]);
+            if let Some((t, b1, b2)) = Heightfield::intersectTriangle(origin, direction, p0, p1, p2, tMin, closestT) {
+                let n0: Vec3 = self.vertexNormal(corners[triangle[0]].0, corners[triangle[0]].1);
+                let n1: Vec3 = self.vertexNormal(corners[triangle[1]].0, corners[triangle[1]].1);
+                let n2: Vec3 = self.vertexNormal(corners[triangle[2]].0, corners[triangle[2]].1);
+
+                // Triangles are wound so that the face normal points up along +y
+                let faceNormal: Vec3 = cross(*p2 - *p0, *p1 - *p0);
+                let shadingNormal: Vec3 = n0 * (1.0 - b1 - b2) + n1 * b1 + n2 * b2;
+
+                closestT = t;
+                closest = Some((t, faceNormal, shadingNormal));
+            }
+        }
+
+        let (t, faceNormal, shadingNormal) = match closest {
+            Some(hit) => hit,
+            None => return false,
+        };
+
+        let localPoint: Vec3 = *origin + *direction * t;
+        let outwardNormal: Vec3 = normalize(self.normalToWorld * faceNormal);
+        let mut interpolatedNormal: Vec3 = normalize(self.normalToWorld * shadingNormal);
+
+        hitRecord.t = t;
+        hitRecord.point = ray.at(t);
+        hitRecord.material = Rc::clone(&self.material);
+        hitRecord.SetFaceNormal(ray, outwardNormal);
+
+        if !hitRecord.frontFace {
+            interpolatedNormal = -interpolatedNormal;
+        }
+        hitRecord.shadingNormal = interpolatedNormal;
+
+        let uv: Vec2 = vec2(localPoint.x / (self.columns - 1) as f32, localPoint.z / (self.rows - 1) as f32);
+        hitRecord.setSurfaceFrame(uv, self.tangent, cross(outwardNormal, self.tangent));
+
+        return true;
+    }
+}
+
+impl HittableObject for Heightfield {
+    fn testIntersection(&self, ray: &Ray, tMin: f32, tMax: f32, hitRecord: &mut HitRecord) -> bool {
+        // The linear map keeps the ray parameter, so t is the same in local and world space
+        let origin: Vec3 = self.toLocal * (ray.origin - self.origin);
+        let direction: Vec3 = self.toLocal * ray.direction;
+        let localRay: Ray = Ray::new(origin, direction);
+
+        let maxX: f32 = (self.columns - 1) as f32;
+        let maxZ: f32 = (self.rows - 1) as f32;
+        let bounds: AABB = AABB::new(vec3(0.0, 0.0, 0.0), vec3(maxX, 1.0, maxZ));
+
+        let (tStart, tEnd) = match bounds.intersect(&localRay, tMin, tMax) {
+            Some(range) => range,
+            None => return false,
+        };
+
+        // 2D DDA over the cells of the xz grid, starting at the cell where the ray enters the bounds
+        let entry: Vec3 = localRay.at(tStart);
+        let mut cellX: i64 = i64::min(i64::max(entry.x.floor() as i64, 0), self.columns as i64 - 2);
+        let mut cellZ: i64 = i64::min(i64::max(entry.z.floor() as i64, 0), self.rows as i64 - 2);
+
+        let stepX: i64 = if direction.x >= 0.0 { 1 } else { -1 };
+        let stepZ: i64 = if direction.z >= 0.0 { 1 } else { -1 };
+
+        let deltaX: f32 = if direction.x != 0.0 { f32::abs(1.0 / direction.x) } else { f32::MAX };
+        let deltaZ: f32 = if direction.z != 0.0 { f32::abs(1.0 / direction.z) } else { f32::MAX };
+
+        let nextBoundaryX: f32 = (cellX + if stepX > 0 { 1 } else { 0 }) as f32;
+        let nextBoundaryZ: f32 = (cellZ + if stepZ > 0 { 1 } else { 0 }) as f32;
+        let mut tNextX: f32 = if direction.x != 0.0 { (nextBoundaryX - origin.x) / direction.x } else { f32::MAX };
+        let mut tNextZ: f32 = if direction.z != 0.0 { (nextBoundaryZ - origin.z) / direction.z } else { f32::MAX };
+
+        loop {
+            if self.intersectCell(ray, &localRay, (cellX as usize, cellZ as usize), tMin, tMax, hitRecord) {
+                return true;
+            }
+
+            if f32::min(tNextX, tNextZ) > tEnd {
+                return false;
+            }
+
+            if tNextX < tNextZ {
+                cellX += stepX;
+                tNextX += deltaX;
+            } else {
+                cellZ += stepZ;
+                tNextZ += deltaZ;
+            }
+
+            if cellX < 0 || cellZ < 0 || cellX > self.columns as i64 - 2 || cellZ > self.rows as i64 - 2 {
+                return false;
+            }
+        }
+    }
+
+    fn boundingBox(&self, outputBox: &mut AABB) -> bool {
+        let maxX: f32 = (self.columns - 1) as f32;
+        let maxZ: f32 = (self.rows - 1) as f32;
+
+        let mut corners: Vec<Vec3> = Vec::with_capacity(8);
+        for i in 0..8 {
+            let local: Vec3 = vec3(if i & 1 == 0 { 0.0 } else { maxX },
+                                   if i & 2 == 0 { 0.0 } else { 1.0 },
+                                   if i & 4 == 0 { 0.0 } else { maxZ });
+            corners.push(self.origin + self.toWorld * local);
+        }
+
+        *outputBox = AABB::new_from_points(&corners);
+        return true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::lambertian::Lambertian;
+    use super::*;
+
+    /* Grid of columns x columns samples one unit apart, with heights in world units along +y */
+    fn grid(heights: Vec<f32>, columns: usize) -> Heightfield {
+        let size: f32 = (columns - 1) as f32;
+        let axes: GridAxes = GridAxes {
+            origin: vec3(0.0, 0.0, 0.0),
+            uAxis: vec3(size, 0.0, 0.0),
+            vAxis: vec3(0.0, 0.0, size),
+            heightAxis: vec3(0.0, 1.0, 0.0),
+        };
+        return Heightfield::new(heights, columns, columns, axes, Rc::new(Lambertian::default()));
+    }
+
+    fn hit(heightfield: &Heightfield, origin: Vec3, direction: Vec3) -> Option<HitRecord> {
+        let mut hitRecord: HitRecord = HitRecord::default();
+        if !heightfield.testIntersection(&Ray::new(origin, direction), 0.001, f32::MAX, &mut hitRecord) {
+            return None;
+        }
+        return Some(hitRecord);
+    }
+
+    #[test]
+    fn flatFieldIsHitLikeAPlane() {
+        let flat: Heightfield = grid(vec![0.5; 25], 5);
+
+        let straight: HitRecord = hit(&flat, vec3(1.3, 5.0, 2.7), vec3(0.0, -1.0, 0.0)).unwrap();
+        assert!((straight.t - 4.5).abs() < 1e-5);
+        assert!(dot(straight.normal, vec3(0.0, 1.0, 0.0)) > 0.9999);
+        assert!(dot(straight.shadingNormal, vec3(0.0, 1.0, 0.0)) > 0.9999);
+        assert!((straight.uv.x - 1.3 / 4.0).abs() < 1e-5 && (straight.uv.y - 2.7 / 4.0).abs() < 1e-5);
+
+        let oblique: HitRecord = hit(&flat, vec3(-1.0, 2.0, 0.5), vec3(1.0, -0.5, 0.3)).unwrap();
+        assert!((oblique.t - 3.0).abs() < 1e-4);
+
+        assert!(hit(&flat, vec3(5.0, 5.0, 2.0), vec3(0.0, -1.0, 0.0)).is_none());
+        assert!(hit(&flat, vec3(2.0, 1.0, 2.0), vec3(0.0, 1.0, 0.0)).is_none());
+    }
+
+    #[test]
+    fn grazingRaysWalkAcrossCells() {
+        // Flat at zero, the ray sinks by 0.01 over five units and crosses six cells on the way
+        let flat: Heightfield = grid(vec![0.0; 81], 9);
+        let grazing: HitRecord = hit(&flat, vec3(0.0, 0.01, 0.5), vec3(1.0, -0.002, 0.3)).unwrap();
+        assert!((grazing.t - 5.0).abs() < 1e-3);
+        assert!((grazing.point.x - 5.0).abs() < 1e-3 && (grazing.point.z - 2.0).abs() < 1e-3);
+
+        // A single raised sample at (6, 6) is found by a diagonal ray passing over the flat cells before it
+        let mut heights: Vec<f32> = vec![0.0; 81];
+        heights[6 * 9 + 6] = 1.0;
+        let peak: Heightfield = grid(heights, 9);
+        let slope: HitRecord = hit(&peak, vec3(0.1, 0.5, 0.3), vec3(1.0, 0.0, 1.0)).unwrap();
+        assert!((slope.t - 5.4).abs() < 1e-4);
+        assert!((slope.point.x - 5.5).abs() < 1e-4 && (slope.point.z - 5.7).abs() < 1e-4);
+
+        assert!(hit(&peak, vec3(0.1, 1.1, 0.3), vec3(1.0, 0.0, 1.0)).is_none());
+        assert!(hit(&peak, vec3(0.1, 0.5, 0.3), vec3(1.0, 0.0, 0.0)).is_none());
+    }
+}
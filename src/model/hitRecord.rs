@@ -1,16 +1,25 @@
 ﻿use std::rc::Rc;
-use glm::{dot, Vec3, vec3};
+use glm::{dot, Vec2, vec2, Vec3, vec3};
 use crate::Lambertian;
 use crate::math::ray::Ray;
 use crate::model::material::Material;
 
 pub struct HitRecord {
     pub point: Vec3,
+    /* Geometric normal, always facing against the incoming ray */
     pub normal: Vec3,
+    /* Normal used for shading, may be perturbed by normal or bump maps */
+    pub shadingNormal: Vec3,
+
+    pub uv: Vec2,
+    pub tangent: Vec3,
+    pub bitangent: Vec3,
     
     pub t: f32,
     pub material: Rc<dyn Material>,
-    pub frontFace: bool
+    pub frontFace: bool,
+    /* Index of the scene object that was hit, filled in by Scene::Hit */
+    pub objectId: usize,
 }
 
 impl HitRecord {
@@ -25,6 +34,13 @@ impl HitRecord {
         
         self.frontFace = dot(ray.direction, outwardNormal) < 0.0;
         if self.frontFace { self.normal = outwardNormal } else {self.normal = -outwardNormal};
+        self.shadingNormal = self.normal;
+    }
+
+    pub fn setSurfaceFrame(&mut self, uv: Vec2, tangent: Vec3, bitangent: Vec3) {
+        self.uv = uv;
+        self.tangent = tangent;
+        self.bitangent = bitangent;
     }
     
 }
@@ -34,9 +50,31 @@ impl Default for HitRecord {
         return Self {
             point:  vec3(0.0,0.0,0.0),
             normal: vec3(0.0,0.0,0.0),
+            shadingNormal: vec3(0.0,0.0,0.0),
+            uv: vec2(0.0,0.0),
+            tangent: vec3(0.0,0.0,0.0),
+            bitangent: vec3(0.0,0.0,0.0),
             t: 0.0,
             material: Rc::new(Lambertian::default()),
             frontFace: false,
+            objectId: 0,
         }
     }
-}
\ No newline at end of file
+}
+
+impl Clone for HitRecord {
+    fn clone(&self) -> Self {
+        return Self {
+            point: self.point,
+            normal: self.normal,
+            shadingNormal: self.shadingNormal,
+            uv: self.uv,
+            tangent: self.tangent,
+            bitangent: self.bitangent,
+            t: self.t,
+            material: Rc::clone(&self.material),
+            frontFace: self.frontFace,
+            objectId: self.objectId,
+        }
+    }
+}
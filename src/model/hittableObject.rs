@@ -1,9 +1,49 @@
-﻿use crate::math::ray::Ray;
+﻿use crate::math::aabb::AABB;
+use crate::math::ray::Ray;
 use crate::model::hitRecord::HitRecord;
+use crate::model::interval::Interval;
+
+/* Upper bound on the surface crossings collected along one ray */
+const MAX_CROSSINGS: i32 = 64;
 
 
 pub trait HittableObject {
     
     fn testIntersection(&self, ray: &Ray, tMin: f32, tMax: f32, hitRecord: &mut HitRecord) -> bool;
+
+    /* Writes the object's bounds into outputBox, returns false for unbounded objects such as infinite planes */
+    fn boundingBox(&self, outputBox: &mut AABB) -> bool;
+
+    /* Every interval the whole line of the ray spends inside the object, sorted along the ray.
+       The default steps through testIntersection from one crossing to the next, which is valid for any closed surface */
+    fn intersectionIntervals(&self, ray: &Ray, intervals: &mut Vec<Interval>) -> bool {
+        let mut tMin: f32 = -f32::MAX;
+        let mut enter: Option<HitRecord> = None;
+        let mut first: bool = true;
+
+        for _ in 0..MAX_CROSSINGS {
+            let mut crossing: HitRecord = HitRecord::default();
+            if !self.testIntersection(ray, tMin, f32::MAX, &mut crossing) {
+                break;
+            }
+            tMin = crossing.t + f32::max(1e-4, f32::abs(crossing.t) * 1e-5);
+
+            if crossing.frontFace {
+                enter = Some(crossing);
+            } else if let Some(entered) = enter.take() {
+                intervals.push(Interval::new(entered, crossing));
+            } else if first {
+                // The line starts inside the object
+                intervals.push(Interval::new(Interval::unbounded(-f32::MAX), crossing));
+            }
+            first = false;
+        }
+
+        if let Some(entered) = enter {
+            intervals.push(Interval::new(entered, Interval::unbounded(f32::MAX)));
+        }
+
+        return !intervals.is_empty();
+    }
 }
 
@@ -0,0 +1,34 @@
+use crate::model::hitRecord::HitRecord;
+
+/* Stretch of a ray spent inside a solid, bounded by the surface crossings where the ray enters and exits.
+   Intervals that are open towards either end of the ray have t set to -f32::MAX or f32::MAX on that side. */
+pub struct Interval {
+    pub enter: HitRecord,
+    pub exit: HitRecord,
+}
+
+impl Interval {
+    pub fn new(enter: HitRecord, exit: HitRecord) -> Self {
+        return Self {
+            enter,
+            exit,
+        };
+    }
+
+    /* Placeholder boundary for intervals reaching past the end of the ray */
+    pub fn unbounded(t: f32) -> HitRecord {
+        return HitRecord {
+            t,
+            ..Default::default()
+        };
+    }
+}
+
+impl Clone for Interval {
+    fn clone(&self) -> Self {
+        return Interval {
+            enter: self.enter.clone(),
+            exit: self.exit.clone(),
+        };
+    }
+}
@@ -0,0 +1,127 @@
+use std::f32::consts::PI;
+use glm::{cross, dot, length, normalize, Vec3, vec4};
+use crate::math::mathUtils::{isNearlyZero_Vec3, random01, randomInUnitSphere, randomUnitVector3};
+use crate::math::ray::Ray;
+use crate::math::spectrum::luminance;
+use crate::math::vColor::VColor;
+use crate::model::hitRecord::HitRecord;
+use crate::model::material::Material;
+
+/* Kajiya-Kay hair shading: fibers scatter light around the cone of directions that make the same angle with the
+   strand as the incoming ray, plus a diffuse term proportional to the sine of the angle to the strand.
+   Needs a surface with a tangent along the fiber, like Curve */
+pub struct KajiyaKay {
+    diffuse: VColor,
+    specular: VColor,
+    exponent: f32,
+}
+
+impl KajiyaKay {
+    pub fn new(diffuse: VColor, specular: VColor, exponent: f32) -> Self {
+        return Self {
+            diffuse,
+            specular,
+            exponent: f32::max(exponent, 1.0),
+        };
+    }
+
+    fn weighted(color: &VColor, weight: f32) -> VColor {
+        let rgb: Vec3 = color.getColorRGB() * weight;
+        return VColor::new_vec4(&vec4(rgb.x, rgb.y, rgb.z, 1.0));
+    }
+
+    /* Chance of taking the specular lobe, so that the weights stay close to the colors */
+    fn specularProbability(&self) -> f32 {
+        let specular: f32 = luminance(&self.specular.getColorRGB());
+        let total: f32 = specular + luminance(&self.diffuse.getColorRGB());
+        if total <= 0.0 {
+            return 0.5;
+        }
+        return (specular / total).clamp(0.05, 0.95);
+    }
+}
+
+impl Material for KajiyaKay {
+    fn scatter(&self, ray: &Ray, hitRecord: &HitRecord, attenuation: &mut VColor, scattered: &mut Ray) -> bool {
+        let tangent: Vec3 = hitRecord.tangent;
+        let specularProbability: f32 = self.specularProbability();
+        let direction: Vec3 = normalize(ray.direction);
+
+        if random01() < specularProbability {
+            // Keep the component along the fiber and flip the rest, that lands on the cone of the highlight
+            let along: f32 = dot(direction, tangent);
+            let mirrored: Vec3 = tangent * (2.0 * along) - direction;
+            let spread: f32 = (2.0 / (self.exponent + 2.0)).sqrt();
+            let mut scatterDir: Vec3 = mirrored + randomInUnitSphere() * spread;
+            if isNearlyZero_Vec3(&scatterDir, None) {
+                scatterDir = mirrored;
+            }
+
+            *scattered = Ray::new(hitRecord.point, scatterDir);
+            *attenuation = Self::weighted(&self.specular, 1.0 / specularProbability);
+            return true;
+        }
+
+        let mut scatterDir: Vec3 = hitRecord.shadingNormal + randomUnitVector3();
+        if isNearlyZero_Vec3(&scatterDir, None) {
+            scatterDir = hitRecord.shadingNormal;
+        }
+
+        // Light leaving along the fiber sees none of its cross section
+        let sine: f32 = length(cross(normalize(scatterDir), tangent));
+
+        *scattered = Ray::new(hitRecord.point, scatterDir);
+        *attenuation = Self::weighted(&self.diffuse, sine / (1.0 - specularProbability));
+        return true;
+    }
+
+    /* The fiber is treated as lit from all around, so only angles to the tangent matter, not the ribbon's normal */
+    fn eval(&self, ray: &Ray, hitRecord: &HitRecord, direction: &Vec3) -> VColor {
+        let tangent: Vec3 = hitRecord.tangent;
+        let toViewer: Vec3 = normalize(ray.direction) * -1.0;
+
+        let cosLight: f32 = dot(*direction, tangent);
+        let cosViewer: f32 = dot(toViewer, tangent);
+        let sinLight: f32 = f32::max(1.0 - cosLight * cosLight, 0.0).sqrt();
+        let sinViewer: f32 = f32::max(1.0 - cosViewer * cosViewer, 0.0).sqrt();
+
+        // Peaks when the light lies on the cone mirrored around the fiber
+        let highlight: f32 = f32::max(sinLight * sinViewer - cosLight * cosViewer, 0.0).powf(self.exponent);
+
+        let diffuse: Vec3 = self.diffuse.getColorRGB() * (sinLight / PI);
+        let specular: Vec3 = self.specular.getColorRGB() * (highlight * (self.exponent + 2.0) / (2.0 * PI));
+        let value: Vec3 = diffuse + specular;
+        return VColor::new_vec4(&vec4(value.x, value.y, value.z, 1.0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glm::vec3;
+    use super::*;
+
+    #[test]
+    fn highlightKeepsTheTangentComponent() {
+        // A black diffuse color leaves only the specular lobe with any weight, a high exponent a narrow spread
+        let material: KajiyaKay = KajiyaKay::new(VColor::new_rgb(0.0, 0.0, 0.0), VColor::new_rgb(1.0, 1.0, 1.0), 1e6);
+        let mut hitRecord: HitRecord = HitRecord::new();
+        hitRecord.tangent = vec3(1.0, 0.0, 0.0);
+        hitRecord.normal = vec3(0.0, 1.0, 0.0);
+        hitRecord.shadingNormal = hitRecord.normal;
+        let ray: Ray = Ray::new(vec3(-1.0, 2.0, 0.0), normalize(vec3(0.6, -0.8, 0.0)));
+
+        let mut specularSamples: i32 = 0;
+        for _ in 0..200 {
+            let mut attenuation: VColor = VColor::new_sc(0.0);
+            let mut scattered: Ray = Ray::new(vec3(0.0, 0.0, 0.0), vec3(0.0, 0.0, 1.0));
+            assert!(material.scatter(&ray, &hitRecord, &mut attenuation, &mut scattered));
+            if isNearlyZero_Vec3(&attenuation.getColorRGB(), None) {
+                continue;
+            }
+            specularSamples += 1;
+            let direction: Vec3 = normalize(scattered.direction);
+            assert!((dot(direction, hitRecord.tangent) - 0.6).abs() < 0.01);
+        }
+        assert!(specularSamples > 0);
+    }
+}
@@ -1,4 +1,5 @@
-﻿use glm::Vec3;
+﻿use std::f32::consts::PI;
+use glm::{dot, Vec3};
 use crate::math::mathUtils::{isNearlyZero_Vec3, isNearlyZero_Vec4, randomUnitVector3};
 use crate::math::ray::Ray;
 use crate::math::vColor::VColor;
@@ -12,16 +13,29 @@ pub struct Lambertian {
 impl Material for Lambertian {
     fn scatter(&self, ray: &Ray, hitRecord: &HitRecord, attenuation: &mut VColor, scattered: &mut Ray) -> bool {
         
-        let mut scatterDir: Vec3 = hitRecord.normal + randomUnitVector3();
+        let mut scatterDir: Vec3 = hitRecord.shadingNormal + randomUnitVector3();
         
         if isNearlyZero_Vec3(&scatterDir, None) {
-            scatterDir = hitRecord.normal;
+            scatterDir = hitRecord.shadingNormal;
         }
         
         *scattered = Ray::new(hitRecord.point,scatterDir);
         *attenuation = self.albedo.clone();
         return true;
     }
+
+    fn eval(&self, _ray: &Ray, hitRecord: &HitRecord, direction: &Vec3) -> VColor {
+        let cosTheta: f32 = dot(hitRecord.shadingNormal, *direction);
+        if cosTheta <= 0.0 || dot(hitRecord.normal, *direction) <= 0.0 {
+            return VColor::default();
+        }
+        return self.albedo.clone() * (cosTheta / PI);
+    }
+
+    /* The unit vector added to the normal in `scatter` gives a cosine weighted hemisphere */
+    fn pdf(&self, _ray: &Ray, hitRecord: &HitRecord, direction: &Vec3) -> f32 {
+        return f32::max(dot(hitRecord.shadingNormal, *direction), 0.0) / PI;
+    }
 }
 
 impl Lambertian {
@@ -0,0 +1,136 @@
+use std::rc::Rc;
+use crate::math::aabb::AABB;
+use crate::math::mathUtils::random01;
+use crate::math::ray::Ray;
+use crate::math::vColor::VColor;
+use crate::model::hitRecord::HitRecord;
+use crate::model::hittableObject::HittableObject;
+use crate::model::texture::Texture;
+
+/* How far past a rejected hit the search continues, so the same surface is not found again */
+const SKIP_EPSILON: f32 = 1e-4;
+/* Upper bound on rejected hits along a single ray */
+const MAX_SKIPPED_HITS: i32 = 64;
+
+pub enum MaskChannel {
+    /* Average of the RGB channels, for grayscale masks and procedural textures */
+    Luminance,
+    /* Alpha channel of the texture, for images with transparency */
+    Alpha,
+}
+
+pub enum MaskTest {
+    /* Hits with opacity below the threshold are ignored */
+    Threshold(f32),
+    /* Hits are kept with a probability equal to their opacity */
+    Stochastic,
+}
+
+/* Wraps any primitive with an opacity mask evaluated at the hit's surface coordinates */
+pub struct Masked {
+    object: Box<dyn HittableObject>,
+    mask: Rc<dyn Texture>,
+    channel: MaskChannel,
+    test: MaskTest,
+}
+
+impl Masked {
+    pub fn new(object: Box<dyn HittableObject>, mask: Rc<dyn Texture>, channel: MaskChannel, test: MaskTest) -> Self {
+        return Self {
+            object,
+            mask,
+            channel,
+            test,
+        };
+    }
+
+    pub fn opacity(&self, hitRecord: &HitRecord) -> f32 {
+        let value: VColor = self.mask.value(&hitRecord.uv, &hitRecord.point);
+        return match self.channel {
+            MaskChannel::Luminance => (value.color.x + value.color.y + value.color.z) / 3.0,
+            MaskChannel::Alpha => value.color.w,
+        };
+    }
+
+    fn isOpaque(&self, hitRecord: &HitRecord) -> bool {
+        let opacity: f32 = self.opacity(hitRecord);
+        return match self.test {
+            MaskTest::Threshold(threshold) => opacity >= threshold,
+            MaskTest::Stochastic => random01() < opacity,
+        };
+    }
+}
+
+impl HittableObject for Masked {
+    fn testIntersection(&self, ray: &Ray, tMin: f32, tMax: f32, hitRecord: &mut HitRecord) -> bool {
+        let mut searchMin: f32 = tMin;
+
+        for _ in 0..MAX_SKIPPED_HITS {
+            let mut candidate: HitRecord = HitRecord::default();
+            if !self.object.testIntersection(ray, searchMin, tMax, &mut candidate) {
+                return false;
+            }
+
+            if self.isOpaque(&candidate) {
+                *hitRecord = candidate;
+                return true;
+            }
+
+            searchMin = candidate.t + SKIP_EPSILON;
+        }
+
+        return false;
+    }
+
+    fn boundingBox(&self, outputBox: &mut AABB) -> bool {
+        return self.object.boundingBox(outputBox);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glm::{Vec2, vec3, Vec3};
+    use crate::model::lambertian::Lambertian;
+    use crate::model::sphere::Sphere;
+    use crate::render::scene::Scene;
+    use super::*;
+
+    /* Opaque where x is positive, see-through elsewhere */
+    struct HalfMask {}
+
+    impl Texture for HalfMask {
+        fn value(&self, _uv: &Vec2, point: &Vec3) -> VColor {
+            return VColor::new_sc(if point.x > 0.0 { 1.0 } else { 0.0 });
+        }
+    }
+
+    fn maskedSphere() -> Box<dyn HittableObject> {
+        let sphere: Sphere = Sphere::new(1.0, vec3(0.0, 0.0, 0.0), Rc::new(Lambertian::default()));
+        return Box::new(Masked::new(Box::new(sphere), Rc::new(HalfMask {}), MaskChannel::Luminance, MaskTest::Threshold(0.5)));
+    }
+
+    #[test]
+    fn maskedOutHitsLetTheRayThrough() {
+        let mut scene: Scene = Scene::default();
+        scene.addObject(maskedSphere());
+        scene.addObject(Box::new(Sphere::new(1.0, vec3(0.0, 0.0, 5.0), Rc::new(Lambertian::default()))));
+
+        let mut hitRecord: HitRecord = HitRecord::default();
+        assert!(scene.Hit(&Ray::new(vec3(-0.5, 0.0, -5.0), vec3(0.0, 0.0, 1.0)), &mut hitRecord));
+        assert_eq!(hitRecord.objectId, 1);
+        assert!((hitRecord.t - (10.0 - 0.75f32.sqrt())).abs() < 1e-3);
+
+        assert!(scene.Hit(&Ray::new(vec3(0.5, 0.0, -5.0), vec3(0.0, 0.0, 1.0)), &mut hitRecord));
+        assert_eq!(hitRecord.objectId, 0);
+        assert!((hitRecord.t - (5.0 - 0.75f32.sqrt())).abs() < 1e-3);
+    }
+
+    #[test]
+    fn maskedOutHitsCastNoShadow() {
+        let mut scene: Scene = Scene::default();
+        scene.addObject(maskedSphere());
+
+        assert!(scene.isVisible(&vec3(-0.5, 0.0, -5.0), &vec3(0.0, 0.0, 1.0), 10.0));
+        assert!(!scene.isVisible(&vec3(0.5, 0.0, -5.0), &vec3(0.0, 0.0, 1.0), 10.0));
+    }
+}
@@ -1,7 +1,32 @@
-﻿use crate::math::ray::Ray;
+﻿use glm::Vec3;
+use crate::math::ray::Ray;
 use crate::math::vColor::VColor;
 use crate::model::hitRecord::HitRecord;
 
 pub trait Material {
     fn scatter(&self, ray: &Ray, hitRecord: &HitRecord, attenuation: &mut VColor, scattered: &mut Ray) -> bool;
+
+    /* Fraction of the light arriving from `direction` (unit, pointing away from the surface) that leaves towards the
+       ray's origin, cosine included. Used for lights that can only be sampled directly, so mirror-like materials
+       can keep the default of reflecting none of it */
+    fn eval(&self, _ray: &Ray, _hitRecord: &HitRecord, _direction: &Vec3) -> VColor {
+        return VColor::default();
+    }
+
+    /* Light given off towards the ray's origin, nothing for anything but light sources */
+    fn emitted(&self, _ray: &Ray, _hitRecord: &HitRecord) -> VColor {
+        return VColor::default();
+    }
+
+    /* Density with which `scatter` picks `direction` (unit), zero when it is unknown or the lobes are mirror-like.
+       Lights are then counted through direct lighting only, without MIS */
+    fn pdf(&self, _ray: &Ray, _hitRecord: &HitRecord, _direction: &Vec3) -> f32 {
+        return 0.0;
+    }
+
+    /* Whether `scattered` came from a mirror-like lobe that `eval` leaves out. Lights that are sampled directly still
+       have to be counted when such a ray finds them, every other ray would count them twice */
+    fn isSpecular(&self, _ray: &Ray, _hitRecord: &HitRecord, _scattered: &Ray) -> bool {
+        return false;
+    }
 }
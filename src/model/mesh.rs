@@ -0,0 +1,300 @@
+use std::f32::consts::FRAC_1_SQRT_2;
+use std::rc::Rc;
+use glm::{cross, dot, length, normalize, Vec2, vec2, Vec3};
+use crate::math::aabb::AABB;
+use crate::math::frame::Frame;
+use crate::math::ray::Ray;
+use crate::model::hitRecord::HitRecord;
+use crate::model::hittableObject::HittableObject;
+use crate::model::material::Material;
+use crate::model::subdivision::{ControlCage, SubdivisionScheme};
+
+/* Faces meeting at a smaller angle than this share a smooth normal, sharper edges keep their facets */
+const SMOOTHING_ANGLE_COS: f32 = FRAC_1_SQRT_2;
+const LEAF_SIZE: usize = 4;
+
+struct Triangle {
+    vertices: [Vec3; 3],
+    uvs: [Vec2; 3],
+    normals: [Vec3; 3],
+}
+
+/* Node of the bounding volume hierarchy over the triangles, leaves have a non zero count */
+struct BvhNode {
+    bounds: AABB,
+    left: usize,
+    right: usize,
+    start: usize,
+    count: usize,
+}
+
+/* Triangle mesh, usually the result of subdividing a control cage, with a BVH for intersection */
+pub struct Mesh {
+    triangles: Vec<Triangle>,
+    nodes: Vec<BvhNode>,
+    material: Rc<dyn Material>,
+}
+
+impl Mesh {
+    pub fn new(cage: &ControlCage, mat: Rc<dyn Material>) -> Self {
+        let triangulated: ControlCage = cage.triangulated();
+
+        let faceNormals: Vec<Vec3> = triangulated.faces.iter().map(|face| {
+            let p0: Vec3 = triangulated.positions[face.vertices[0]];
+            return cross(triangulated.positions[face.vertices[1]] - p0, triangulated.positions[face.vertices[2]] - p0);
+        }).collect();
+
+        let mut facesOfVertex: Vec<Vec<usize>> = vec![vec![]; triangulated.positions.len()];
+        for (faceIndex, face) in triangulated.faces.iter().enumerate() {
+            for v in face.vertices.iter() {
+                facesOfVertex[*v].push(faceIndex);
+            }
+        }
+
+        let mut triangles: Vec<Triangle> = Vec::with_capacity(triangulated.faces.len());
+        for (faceIndex, face) in triangulated.faces.iter().enumerate() {
+            let ownNormal: Vec3 = faceNormals[faceIndex];
+            if length(ownNormal) == 0.0 {
+                continue;
+            }
+            let ownDirection: Vec3 = normalize(ownNormal);
+
+            let mut normals: [Vec3; 3] = [ownDirection; 3];
+            for corner in 0..3 {
+                // Area weighted average over the neighbouring faces on the same side of any sharp edge
+                let mut sum: Vec3 = ownNormal;
+                for other in facesOfVertex[face.vertices[corner]].iter() {
+                    if *other == faceIndex || length(faceNormals[*other]) == 0.0 {
+                        continue;
+                    }
+                    if dot(normalize(faceNormals[*other]), ownDirection) >= SMOOTHING_ANGLE_COS {
+                        sum = sum + faceNormals[*other];
+                    }
+                }
+                normals[corner] = normalize(sum);
+            }
+
+            let uvs: [Vec2; 3] = if face.uvs.is_empty() {
+                [vec2(0.0, 0.0), vec2(1.0, 0.0), vec2(0.0, 1.0)]
+            } else {
+                [triangulated.uvs[face.uvs[0]], triangulated.uvs[face.uvs[1]], triangulated.uvs[face.uvs[2]]]
+            };
+
+            triangles.push(Triangle {
+                vertices: [triangulated.positions[face.vertices[0]], triangulated.positions[face.vertices[1]], triangulated.positions[face.vertices[2]]],
+                uvs,
+                normals,
+            });
+        }
+
+        let mut mesh: Mesh = Mesh {
+            triangles,
+            nodes: vec![],
+            material: mat,
+        };
+        let count: usize = mesh.triangles.len();
+        if count > 0 {
+            mesh.build(0, count);
+        }
+        return mesh;
+    }
+
+    pub fn new_subdivided(cage: &ControlCage, scheme: SubdivisionScheme, levels: i32, mat: Rc<dyn Material>) -> Self {
+        return Mesh::new(&cage.subdivide(&scheme, levels), mat);
+    }
+
+    pub fn load_obj(path: &str, scheme: SubdivisionScheme, levels: i32, mat: Rc<dyn Material>) -> Result<Self, String> {
+        let cage: ControlCage = ControlCage::load_obj(path)?;
+        return Ok(Mesh::new_subdivided(&cage, scheme, levels, mat));
+    }
+
+    fn triangleBounds(triangle: &Triangle) -> AABB {
+        return AABB::new_from_points(&triangle.vertices);
+    }
+
+    /* Builds the subtree over triangles[start..end], splitting at the median along the widest axis of the centroids */
+    fn build(&mut self, start: usize, end: usize) -> usize {
+        let mut bounds: AABB = AABB::empty();
+        let mut centroidBounds: AABB = AABB::empty();
+        for triangle in self.triangles[start..end].iter() {
+            let triangleBox: AABB = Mesh::triangleBounds(triangle);
+            centroidBounds = AABB::surroundingBox(&centroidBounds, &AABB::new(triangleBox.centroid(), triangleBox.centroid()));
+            bounds = AABB::surroundingBox(&bounds, &triangleBox);
+        }
+
+        let nodeIndex: usize = self.nodes.len();
+        self.nodes.push(BvhNode { bounds, left: 0, right: 0, start, count: end - start });
+
+        if end - start <= LEAF_SIZE {
+            return nodeIndex;
+        }
+
+        let extent: Vec3 = centroidBounds.extent();
+        let axis: usize = if extent.x >= extent.y && extent.x >= extent.z { 0 } else if extent.y >= extent.z { 1 } else { 2 };
+
+        self.triangles[start..end].sort_by(|a, b| {
+            let ca: f32 = Mesh::triangleBounds(a).centroid()[axis];
+            let cb: f32 = Mesh::triangleBounds(b).centroid()[axis];
+            return ca.partial_cmp(&cb).unwrap_or(std::cmp::Ordering::Equal);
+        });
+
+        let middle: usize = (start + end) / 2;
+        let left: usize = self.build(start, middle);
+        let right: usize = self.build(middle, end);
+
+        let node: &mut BvhNode = &mut self.nodes[nodeIndex];
+        node.left = left;
+        node.right = right;
+        node.count = 0;
+
+        return nodeIndex;
+    }
+
+    /* Möller-Trumbore, returns (t, b1, b2) */
+    fn intersectTriangle(ray: &Ray, triangle: &Triangle, tMin: f32, tMax: f32) -> Option<(f32, f32, f32)> {
+        let edge1: Vec3 = triangle.vertices[1] - triangle.vertices[0];
+        let edge2: Vec3 = triangle.vertices[2] - triangle.vertices[0];
+        let pvec: Vec3 = cross(ray.direction, edge2);
+        let det: f32 = dot(edge1, pvec);
+        if f32::abs(det) < 1e-12 {
+            return None;
+        }
+
+        let invDet: f32 = 1.0 / det;
+        let tvec: Vec3 = ray.origin - triangle.vertices[0];
+        let b1: f32 = dot(tvec, pvec) * invDet;
+        if !(0.0..=1.0).contains(&b1) {
+            return None;
+        }
+
+        let qvec: Vec3 = cross(tvec, edge1);
+        let b2: f32 = dot(ray.direction, qvec) * invDet;
+        if b2 < 0.0 || b1 + b2 > 1.0 {
+            return None;
+        }
+
+        let t: f32 = dot(edge2, qvec) * invDet;
+        if t < tMin || t > tMax {
+            return None;
+        }
+
+        return Some((t, b1, b2));
+    }
+
+    /* dP/du of a triangle from its UV layout, or any vector in the plane when the layout is degenerate */
+    fn tangent(triangle: &Triangle, normal: &Vec3) -> Vec3 {
+        let edge1: Vec3 = triangle.vertices[1] - triangle.vertices[0];
+        let edge2: Vec3 = triangle.vertices[2] - triangle.vertices[0];
+        let duv1: Vec2 = triangle.uvs[1] - triangle.uvs[0];
+        let duv2: Vec2 = triangle.uvs[2] - triangle.uvs[0];
+
+        let det: f32 = duv1.x * duv2.y - duv2.x * duv1.y;
+        if f32::abs(det) > 1e-12 {
+            let tangent: Vec3 = (edge1 * duv2.y - edge2 * duv1.y) / det;
+            let projected: Vec3 = tangent - *normal * dot(tangent, *normal);
+            if length(projected) > 1e-12 {
+                return normalize(projected);
+            }
+        }
+
+        return Frame::new(triangle.vertices[0], *normal).zAxis;
+    }
+}
+
+impl HittableObject for Mesh {
+    fn testIntersection(&self, ray: &Ray, tMin: f32, tMax: f32, hitRecord: &mut HitRecord) -> bool {
+        if self.nodes.is_empty() {
+            return false;
+        }
+
+        let mut closest: Option<(usize, f32, f32, f32)> = None;
+        let mut closestT: f32 = tMax;
+        let mut stack: Vec<usize> = vec![0];
+
+        while let Some(nodeIndex) = stack.pop() {
+            let node: &BvhNode = &self.nodes[nodeIndex];
+            if !node.bounds.hit(ray, tMin, closestT) {
+                continue;
+            }
+
+            if node.count == 0 {
+                stack.push(node.left);
+                stack.push(node.right);
+                continue;
+            }
+
+            for index in node.start..node.start + node.count {
+                if let Some((t, b1, b2)) = Mesh::intersectTriangle(ray, &self.triangles[index], tMin, closestT) {
+                    closestT = t;
+                    closest = Some((index, t, b1, b2));
+                }
+            }
+        }
+
+        let (index, t, b1, b2) = match closest {
+            Some(hit) => hit,
+            None => return false,
+        };
+
+        let triangle: &Triangle = &self.triangles[index];
+        let b0: f32 = 1.0 - b1 - b2;
+        let outwardNormal: Vec3 = normalize(cross(triangle.vertices[1] - triangle.vertices[0], triangle.vertices[2] - triangle.vertices[0]));
+        let mut interpolatedNormal: Vec3 = normalize(triangle.normals[0] * b0 + triangle.normals[1] * b1 + triangle.normals[2] * b2);
+
+        hitRecord.t = t;
+        hitRecord.point = ray.at(t);
+        hitRecord.material = Rc::clone(&self.material);
+        hitRecord.SetFaceNormal(ray, outwardNormal);
+
+        if !hitRecord.frontFace {
+            interpolatedNormal = -interpolatedNormal;
+        }
+        hitRecord.shadingNormal = interpolatedNormal;
+
+        let uv: Vec2 = triangle.uvs[0] * b0 + triangle.uvs[1] * b1 + triangle.uvs[2] * b2;
+        let tangent: Vec3 = Mesh::tangent(triangle, &outwardNormal);
+        hitRecord.setSurfaceFrame(uv, tangent, cross(outwardNormal, tangent));
+
+        return true;
+    }
+
+    fn boundingBox(&self, outputBox: &mut AABB) -> bool {
+        if self.nodes.is_empty() {
+            return false;
+        }
+        *outputBox = self.nodes[0].bounds.clone();
+        return true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+    use glm::vec3;
+    use crate::model::lambertian::Lambertian;
+    use super::*;
+
+    const CUBE_OBJ: &str = "v -1 -1 -1\nv 1 -1 -1\nv -1 1 -1\nv 1 1 -1\nv -1 -1 1\nv 1 -1 1\nv -1 1 1\nv 1 1 1\n\
+                            f 1 3 4 2\nf 5 6 8 7\nf 1 2 6 5\nf 3 7 8 4\nf 1 5 7 3\nf 2 4 8 6\n";
+
+    #[test]
+    fn subdividedCubeIsHitOnItsRoundedSurface() {
+        let path: String = env::temp_dir().join("meshSubdividedCube.obj").to_string_lossy().to_string();
+        fs::write(&path, CUBE_OBJ).unwrap();
+        let mesh: Result<Mesh, String> = Mesh::load_obj(&path, SubdivisionScheme::CatmullClark, 3, Rc::new(Lambertian::default()));
+        let _ = fs::remove_file(&path);
+        let mesh: Mesh = mesh.unwrap();
+
+        let ray: Ray = Ray::new(vec3(-5.0, 0.1, 0.05), vec3(1.0, 0.0, 0.0));
+        let mut hitRecord: HitRecord = HitRecord::default();
+        assert!(mesh.testIntersection(&ray, 0.001, f32::MAX, &mut hitRecord));
+        // The limit surface pulls the face centers in from the cage to about 0.85
+        assert!(hitRecord.point.x > -0.9 && hitRecord.point.x < -0.8);
+        assert!(hitRecord.frontFace);
+        assert!(dot(hitRecord.normal, vec3(-1.0, 0.0, 0.0)) > 0.95);
+        assert!(dot(hitRecord.shadingNormal, vec3(-1.0, 0.0, 0.0)) > 0.95);
+
+        let miss: Ray = Ray::new(vec3(-5.0, 1.5, 0.0), vec3(1.0, 0.0, 0.0));
+        assert!(!mesh.testIntersection(&miss, 0.001, f32::MAX, &mut HitRecord::default()));
+    }
+}
@@ -20,11 +20,15 @@ impl Metal {
 
 impl Material for Metal {
     fn scatter(&self, ray: &Ray, hitRecord: &HitRecord, attenuation: &mut VColor, scattered: &mut Ray) -> bool {
-        let reflectedRay: Vec3 = reflect(normalize(ray.direction),hitRecord.normal);
+        let reflectedRay: Vec3 = reflect(normalize(ray.direction),hitRecord.shadingNormal);
 
-        *scattered = Ray::new(hitRecord.point, reflectedRay + hitRecord.normal * self.fuzz);
+        *scattered = Ray::new(hitRecord.point, reflectedRay + hitRecord.shadingNormal * self.fuzz);
 
         *attenuation = self.albedo.clone();
         return dot(scattered.direction,hitRecord.normal) > 0.0;
     }
+
+    fn isSpecular(&self, _ray: &Ray, _hitRecord: &HitRecord, _scattered: &Ray) -> bool {
+        return true;
+    }
 }
\ No newline at end of file
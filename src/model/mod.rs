@@ -4,4 +4,23 @@ pub mod sphere;
 pub mod hitRecord;
 pub mod lambertian;
 pub mod metal;
-pub mod dielectric;
\ No newline at end of file
+pub mod dielectric;
+pub mod coated;
+pub mod texture;
+pub mod normalMapped;
+pub mod masked;
+pub mod plane;
+pub mod disk;
+pub mod cylinder;
+pub mod cone;
+pub mod torus;
+pub mod interval;
+pub mod csg;
+pub mod sdf;
+pub mod sdfObject;
+pub mod heightfield;
+pub mod subdivision;
+pub mod mesh;
+pub mod curve;
+pub mod kajiyaKay;
+pub mod diffuseLight;
\ No newline at end of file
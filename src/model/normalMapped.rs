@@ -0,0 +1,185 @@
+use std::borrow::Cow;
+use std::rc::Rc;
+use glm::{dot, normalize, Vec2, vec2, Vec3};
+use crate::math::ray::Ray;
+use crate::math::vColor::VColor;
+use crate::model::hitRecord::HitRecord;
+use crate::model::material::Material;
+use crate::model::texture::Texture;
+
+/* Step of the finite differences of a height texture, taken the same in UV space and along the tangents */
+const BUMP_DELTA: f32 = 1.0 / 1024.0;
+
+/* Offset of scattered ray origins along the geometric normal */
+const RAY_OFFSET: f32 = 1e-4;
+
+pub enum NormalPerturbation {
+    /* Tangent-space normal map, with RGB in [0, 1] remapped to [-1, 1] */
+    NormalMap { texture: Rc<dyn Texture>, strength: f32 },
+    /* Height texture whose gradient tilts the normal, image or procedural */
+    Bump { texture: Rc<dyn Texture>, scale: f32 },
+}
+
+/* Wraps another material and perturbs its shading normal before scattering */
+pub struct NormalMapped {
+    base: Rc<dyn Material>,
+    perturbation: NormalPerturbation,
+}
+
+impl NormalMapped {
+    pub fn new_normal_map(base: Rc<dyn Material>, texture: Rc<dyn Texture>, strength: f32) -> Self {
+        return Self {
+            base,
+            perturbation: NormalPerturbation::NormalMap { texture, strength },
+        };
+    }
+
+    pub fn new_bump(base: Rc<dyn Material>, texture: Rc<dyn Texture>, scale: f32) -> Self {
+        return Self {
+            base,
+            perturbation: NormalPerturbation::Bump { texture, scale },
+        };
+    }
+
+    fn height(texture: &Rc<dyn Texture>, uv: &Vec2, point: &Vec3) -> f32 {
+        return texture.value(uv, point).getColorRGB().x;
+    }
+
+    pub fn perturbedNormal(&self, hitRecord: &HitRecord) -> Vec3 {
+        // The tangents follow the outward surface, on back faces they flip along with the normal so the
+        // perturbation stays the same surface seen from the other side
+        let side: f32 = if hitRecord.frontFace { 1.0 } else { -1.0 };
+        let n: Vec3 = hitRecord.shadingNormal;
+        let t: Vec3 = hitRecord.tangent * side;
+        let b: Vec3 = hitRecord.bitangent * side;
+
+        let perturbed: Vec3 = match &self.perturbation {
+            NormalPerturbation::NormalMap { texture, strength } => {
+                let rgb: Vec3 = texture.value(&hitRecord.uv, &hitRecord.point).getColorRGB();
+                let x: f32 = (rgb.x * 2.0 - 1.0) * strength;
+                let y: f32 = (rgb.y * 2.0 - 1.0) * strength;
+                let z: f32 = rgb.z * 2.0 - 1.0;
+                t * x + b * y + n * z
+            }
+            NormalPerturbation::Bump { texture, scale } => {
+                let h: f32 = NormalMapped::height(texture, &hitRecord.uv, &hitRecord.point);
+                let hu: f32 = NormalMapped::height(texture, &(hitRecord.uv + vec2(BUMP_DELTA, 0.0)), &(hitRecord.point + hitRecord.tangent * BUMP_DELTA));
+                let hv: f32 = NormalMapped::height(texture, &(hitRecord.uv + vec2(0.0, BUMP_DELTA)), &(hitRecord.point + hitRecord.bitangent * BUMP_DELTA));
+
+                n - (t * (hu - h) + b * (hv - h)) * (*scale / BUMP_DELTA)
+            }
+        };
+
+        let result: Vec3 = normalize(perturbed);
+
+        // Never let the shading normal fall behind the geometric surface
+        if dot(result, hitRecord.normal) <= 0.0 {
+            return n;
+        }
+
+        return result;
+    }
+
+    /* The hit with the perturbed shading normal. Surfaces without a tangent frame have nothing to perturb against
+       and are passed on as they are */
+    fn shaded<'a>(&self, hitRecord: &'a HitRecord) -> Cow<'a, HitRecord> {
+        if hitRecord.tangent.x == 0.0 && hitRecord.tangent.y == 0.0 && hitRecord.tangent.z == 0.0 {
+            return Cow::Borrowed(hitRecord);
+        }
+
+        let mut shaded: HitRecord = hitRecord.clone();
+        shaded.shadingNormal = self.perturbedNormal(hitRecord);
+        return Cow::Owned(shaded);
+    }
+}
+
+impl Material for NormalMapped {
+    fn scatter(&self, ray: &Ray, hitRecord: &HitRecord, attenuation: &mut VColor, scattered: &mut Ray) -> bool {
+        let shaded: Cow<HitRecord> = self.shaded(hitRecord);
+        if !self.base.scatter(ray, &shaded, attenuation, scattered) {
+            return false;
+        }
+        if let Cow::Borrowed(_) = shaded {
+            return true;
+        }
+
+        // Offsetting is done along the geometric normal, on the side the scattered ray leaves through
+        let side: f32 = if dot(scattered.direction, hitRecord.normal) >= 0.0 { 1.0 } else { -1.0 };
+        scattered.origin = hitRecord.point + hitRecord.normal * (RAY_OFFSET * side);
+        return true;
+    }
+
+    fn eval(&self, ray: &Ray, hitRecord: &HitRecord, direction: &Vec3) -> VColor {
+        return self.base.eval(ray, &self.shaded(hitRecord), direction);
+    }
+
+    fn emitted(&self, ray: &Ray, hitRecord: &HitRecord) -> VColor {
+        return self.base.emitted(ray, &self.shaded(hitRecord));
+    }
+
+    fn pdf(&self, ray: &Ray, hitRecord: &HitRecord, direction: &Vec3) -> f32 {
+        return self.base.pdf(ray, &self.shaded(hitRecord), direction);
+    }
+
+    fn isSpecular(&self, ray: &Ray, hitRecord: &HitRecord, scattered: &Ray) -> bool {
+        return self.base.isSpecular(ray, &self.shaded(hitRecord), scattered);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glm::vec3;
+    use crate::math::vColor::VColor;
+    use crate::model::lambertian::Lambertian;
+    use crate::model::texture::SolidColor;
+    use super::*;
+
+    /* Height rising linearly along u */
+    struct Ramp;
+
+    impl Texture for Ramp {
+        fn value(&self, uv: &Vec2, _point: &Vec3) -> VColor {
+            return VColor::new_rgb(uv.x, uv.x, uv.x);
+        }
+    }
+
+    /* Hit on the plane z = 0 with tangent +x and bitangent +y, from above or below */
+    fn planeHit(fromAbove: bool) -> HitRecord {
+        let direction: Vec3 = if fromAbove { vec3(0.0, 0.0, -1.0) } else { vec3(0.0, 0.0, 1.0) };
+        let mut hitRecord: HitRecord = HitRecord::default();
+        hitRecord.SetFaceNormal(&Ray::new(-direction, direction), vec3(0.0, 0.0, 1.0));
+        hitRecord.setSurfaceFrame(vec2(0.25, 0.5), vec3(1.0, 0.0, 0.0), vec3(0.0, 1.0, 0.0));
+        return hitRecord;
+    }
+
+    #[test]
+    fn normalMapTiltsTheShadingNormal() {
+        // Tangent space (0.6, 0, 0.8)
+        let texture: Rc<dyn Texture> = Rc::new(SolidColor::new(VColor::new_rgb(0.8, 0.5, 0.9)));
+        let mapped: NormalMapped = NormalMapped::new_normal_map(Rc::new(Lambertian::default()), texture, 1.0);
+
+        let front: Vec3 = mapped.perturbedNormal(&planeHit(true));
+        assert!(dot(front, normalize(vec3(0.6, 0.0, 0.8))) > 0.9999, "{:?}", front);
+
+        // From below it is the same surface, so the shading normal is the exact opposite
+        let back: Vec3 = mapped.perturbedNormal(&planeHit(false));
+        assert!(dot(back, -front) > 0.9999, "{:?}", back);
+    }
+
+    #[test]
+    fn bumpSlopeTiltsAgainstTheGradient() {
+        let mapped: NormalMapped = NormalMapped::new_bump(Rc::new(Lambertian::default()), Rc::new(Ramp), 0.5);
+
+        // The height rises by one per unit of u, so the normal leans back by half of that
+        let front: Vec3 = mapped.perturbedNormal(&planeHit(true));
+        assert!(dot(front, normalize(vec3(-0.5, 0.0, 1.0))) > 0.9999, "{:?}", front);
+
+        let back: Vec3 = mapped.perturbedNormal(&planeHit(false));
+        assert!(dot(back, -front) > 0.9999, "{:?}", back);
+
+        // Hits without a tangent frame are left alone
+        let mut flat: HitRecord = HitRecord::default();
+        flat.SetFaceNormal(&Ray::new(vec3(0.0, 0.0, 1.0), vec3(0.0, 0.0, -1.0)), vec3(0.0, 0.0, 1.0));
+        assert_eq!(mapped.shaded(&flat).shadingNormal, vec3(0.0, 0.0, 1.0));
+    }
+}
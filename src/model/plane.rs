@@ -0,0 +1,84 @@
+use std::rc::Rc;
+use glm::{cross, dot, Vec3, vec2};
+use crate::math::aabb::AABB;
+use crate::math::frame::Frame;
+use crate::math::ray::Ray;
+use crate::model::hitRecord::HitRecord;
+use crate::model::hittableObject::HittableObject;
+use crate::model::material::Material;
+
+/* Infinite plane through a point, UVs are the planar coordinates in world units */
+pub struct Plane {
+    frame: Frame,
+    material: Rc<dyn Material>,
+}
+
+impl Plane {
+    pub fn new(point: Vec3, normal: Vec3, mat: Rc<dyn Material>) -> Self {
+        return Self {
+            frame: Frame::new(point, normal),
+            material: mat,
+        };
+    }
+}
+
+impl HittableObject for Plane {
+    fn testIntersection(&self, ray: &Ray, tMin: f32, tMax: f32, hitRecord: &mut HitRecord) -> bool {
+        let denominator: f32 = dot(ray.direction, self.frame.yAxis);
+        if f32::abs(denominator) < 1e-8 {
+            return false;
+        }
+
+        let t: f32 = dot(self.frame.origin - ray.origin, self.frame.yAxis) / denominator;
+        if t < tMin || tMax < t {
+            return false;
+        }
+
+        hitRecord.t = t;
+        hitRecord.point = ray.at(t);
+        hitRecord.material = Rc::clone(&self.material);
+        hitRecord.SetFaceNormal(ray, self.frame.yAxis);
+
+        let local: Vec3 = self.frame.pointToLocal(&hitRecord.point);
+        hitRecord.setSurfaceFrame(vec2(local.z, local.x), self.frame.zAxis, cross(self.frame.yAxis, self.frame.zAxis));
+
+        return true;
+    }
+
+    fn boundingBox(&self, _outputBox: &mut AABB) -> bool {
+        return false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glm::vec3;
+    use crate::model::lambertian::Lambertian;
+    use super::*;
+
+    #[test]
+    fn rayHitsThePlaneFromEitherSide() {
+        let plane: Plane = Plane::new(vec3(0.0, 1.0, 0.0), vec3(0.0, 1.0, 0.0), Rc::new(Lambertian::default()));
+
+        let mut hitRecord: HitRecord = HitRecord::default();
+        assert!(plane.testIntersection(&Ray::new(vec3(3.0, 5.0, -2.0), vec3(0.0, -2.0, 0.0)), 0.001, f32::MAX, &mut hitRecord));
+        assert!((hitRecord.t - 2.0).abs() < 1e-5);
+        assert!((hitRecord.point.y - 1.0).abs() < 1e-5);
+        assert!(hitRecord.frontFace);
+        assert!(dot(hitRecord.normal, vec3(0.0, 1.0, 0.0)) > 0.9999);
+
+        assert!(plane.testIntersection(&Ray::new(vec3(0.0, -1.0, 0.0), vec3(0.6, 0.8, 0.0)), 0.001, f32::MAX, &mut hitRecord));
+        assert!((hitRecord.t - 2.5).abs() < 1e-5);
+        assert!(!hitRecord.frontFace);
+        assert!(dot(hitRecord.normal, vec3(0.0, -1.0, 0.0)) > 0.9999);
+    }
+
+    #[test]
+    fn parallelAndDistantRaysMiss() {
+        let plane: Plane = Plane::new(vec3(0.0, 1.0, 0.0), vec3(0.0, 1.0, 0.0), Rc::new(Lambertian::default()));
+        let mut hitRecord: HitRecord = HitRecord::default();
+        assert!(!plane.testIntersection(&Ray::new(vec3(0.0, 5.0, 0.0), vec3(1.0, 0.0, 0.0)), 0.001, f32::MAX, &mut hitRecord));
+        assert!(!plane.testIntersection(&Ray::new(vec3(0.0, 5.0, 0.0), vec3(0.0, 1.0, 0.0)), 0.001, f32::MAX, &mut hitRecord));
+        assert!(!plane.testIntersection(&Ray::new(vec3(0.0, 5.0, 0.0), vec3(0.0, -1.0, 0.0)), 0.001, 3.0, &mut hitRecord));
+    }
+}
@@ -0,0 +1,305 @@
+use glm::{abs, clamp, cos, dot, length, log, max, mix, normalize, pow, round, sin, Vec2, vec2, Vec3, vec3};
+
+/* Signed distance to a surface, negative inside. Distances must not overestimate for sphere tracing to be safe */
+pub trait SignedDistance {
+    fn distance(&self, p: &Vec3) -> f32;
+}
+
+//-----------Primitives--------------
+
+pub struct SdfSphere {
+    center: Vec3,
+    radius: f32,
+}
+
+impl SdfSphere {
+    pub fn new(center: Vec3, radius: f32) -> Self {
+        return Self { center, radius };
+    }
+}
+
+impl SignedDistance for SdfSphere {
+    fn distance(&self, p: &Vec3) -> f32 {
+        return length(*p - self.center) - self.radius;
+    }
+}
+
+/* Axis aligned box with rounded edges, a rounding of 0 gives a sharp box */
+pub struct SdfBox {
+    center: Vec3,
+    halfExtents: Vec3,
+    rounding: f32,
+}
+
+impl SdfBox {
+    pub fn new(center: Vec3, halfExtents: Vec3, rounding: f32) -> Self {
+        return Self { center, halfExtents, rounding };
+    }
+}
+
+impl SignedDistance for SdfBox {
+    fn distance(&self, p: &Vec3) -> f32 {
+        let q: Vec3 = abs(*p - self.center) - self.halfExtents + vec3(self.rounding, self.rounding, self.rounding);
+        let outside: f32 = length(max(q, vec3(0.0, 0.0, 0.0)));
+        let inside: f32 = f32::min(f32::max(q.x, f32::max(q.y, q.z)), 0.0);
+        return outside + inside - self.rounding;
+    }
+}
+
+/* Torus around the y axis through its center */
+pub struct SdfTorus {
+    center: Vec3,
+    majorRadius: f32,
+    minorRadius: f32,
+}
+
+impl SdfTorus {
+    pub fn new(center: Vec3, majorRadius: f32, minorRadius: f32) -> Self {
+        return Self { center, majorRadius, minorRadius };
+    }
+}
+
+impl SignedDistance for SdfTorus {
+    fn distance(&self, p: &Vec3) -> f32 {
+        let local: Vec3 = *p - self.center;
+        let q: Vec2 = vec2(length(vec2(local.x, local.z)) - self.majorRadius, local.y);
+        return length(q) - self.minorRadius;
+    }
+}
+
+/* Segment from a to b swept by a sphere */
+pub struct SdfCapsule {
+    a: Vec3,
+    b: Vec3,
+    radius: f32,
+}
+
+impl SdfCapsule {
+    pub fn new(a: Vec3, b: Vec3, radius: f32) -> Self {
+        return Self { a, b, radius };
+    }
+}
+
+impl SignedDistance for SdfCapsule {
+    fn distance(&self, p: &Vec3) -> f32 {
+        let pa: Vec3 = *p - self.a;
+        let ba: Vec3 = self.b - self.a;
+        let h: f32 = clamp(dot(pa, ba) / dot(ba, ba), 0.0, 1.0);
+        return length(pa - ba * h) - self.radius;
+    }
+}
+
+/* Half space below a plane with unit normal n, at the given offset from the origin along n */
+pub struct SdfPlane {
+    normal: Vec3,
+    offset: f32,
+}
+
+impl SdfPlane {
+    pub fn new(normal: Vec3, offset: f32) -> Self {
+        return Self { normal: normalize(normal), offset };
+    }
+}
+
+impl SignedDistance for SdfPlane {
+    fn distance(&self, p: &Vec3) -> f32 {
+        return dot(*p, self.normal) - self.offset;
+    }
+}
+
+/* Mandelbulb fractal through its distance estimator, fitting inside a sphere of about 1.2 times the scale */
+pub struct Mandelbulb {
+    center: Vec3,
+    scale: f32,
+    power: f32,
+    iterations: i32,
+}
+
+impl Mandelbulb {
+    pub fn new(center: Vec3, scale: f32, power: f32, iterations: i32) -> Self {
+        return Self { center, scale, power, iterations };
+    }
+}
+
+impl SignedDistance for Mandelbulb {
+    fn distance(&self, p: &Vec3) -> f32 {
+        let c: Vec3 = (*p - self.center) / self.scale;
+        let mut z: Vec3 = c;
+        let mut dr: f32 = 1.0;
+        let mut r: f32 = length(z);
+
+        for _ in 0..self.iterations {
+            if r > 2.0 {
+                break;
+            }
+
+            let theta: f32 = f32::acos(clamp(z.y / r, -1.0, 1.0)) * self.power;
+            let phi: f32 = f32::atan2(z.z, z.x) * self.power;
+            let zr: f32 = pow(r, self.power);
+            dr = pow(r, self.power - 1.0) * self.power * dr + 1.0;
+
+            z = vec3(sin(theta) * cos(phi), cos(theta), sin(theta) * sin(phi)) * zr + c;
+            r = length(z);
+        }
+
+        if r < 1e-6 {
+            return 0.0;
+        }
+        return 0.5 * log(r) * r / dr * self.scale;
+    }
+}
+
+//-----------Operators--------------
+
+pub struct SdfUnion {
+    a: Box<dyn SignedDistance>,
+    b: Box<dyn SignedDistance>,
+}
+
+impl SdfUnion {
+    pub fn new(a: Box<dyn SignedDistance>, b: Box<dyn SignedDistance>) -> Self {
+        return Self { a, b };
+    }
+}
+
+impl SignedDistance for SdfUnion {
+    fn distance(&self, p: &Vec3) -> f32 {
+        return f32::min(self.a.distance(p), self.b.distance(p));
+    }
+}
+
+pub struct SdfIntersection {
+    a: Box<dyn SignedDistance>,
+    b: Box<dyn SignedDistance>,
+}
+
+impl SdfIntersection {
+    pub fn new(a: Box<dyn SignedDistance>, b: Box<dyn SignedDistance>) -> Self {
+        return Self { a, b };
+    }
+}
+
+impl SignedDistance for SdfIntersection {
+    fn distance(&self, p: &Vec3) -> f32 {
+        return f32::max(self.a.distance(p), self.b.distance(p));
+    }
+}
+
+/* a minus b */
+pub struct SdfSubtraction {
+    a: Box<dyn SignedDistance>,
+    b: Box<dyn SignedDistance>,
+}
+
+impl SdfSubtraction {
+    pub fn new(a: Box<dyn SignedDistance>, b: Box<dyn SignedDistance>) -> Self {
+        return Self { a, b };
+    }
+}
+
+impl SignedDistance for SdfSubtraction {
+    fn distance(&self, p: &Vec3) -> f32 {
+        return f32::max(self.a.distance(p), -self.b.distance(p));
+    }
+}
+
+/* Polynomial smooth minimum, k is the size of the blended region */
+pub struct SdfSmoothUnion {
+    a: Box<dyn SignedDistance>,
+    b: Box<dyn SignedDistance>,
+    k: f32,
+}
+
+impl SdfSmoothUnion {
+    pub fn new(a: Box<dyn SignedDistance>, b: Box<dyn SignedDistance>, k: f32) -> Self {
+        return Self { a, b, k };
+    }
+}
+
+impl SignedDistance for SdfSmoothUnion {
+    fn distance(&self, p: &Vec3) -> f32 {
+        let d1: f32 = self.a.distance(p);
+        let d2: f32 = self.b.distance(p);
+        let h: f32 = clamp(0.5 + 0.5 * (d2 - d1) / self.k, 0.0, 1.0);
+        return mix(d2, d1, h) - self.k * h * (1.0 - h);
+    }
+}
+
+/* a minus b with a rounded seam */
+pub struct SdfSmoothSubtraction {
+    a: Box<dyn SignedDistance>,
+    b: Box<dyn SignedDistance>,
+    k: f32,
+}
+
+impl SdfSmoothSubtraction {
+    pub fn new(a: Box<dyn SignedDistance>, b: Box<dyn SignedDistance>, k: f32) -> Self {
+        return Self { a, b, k };
+    }
+}
+
+impl SignedDistance for SdfSmoothSubtraction {
+    fn distance(&self, p: &Vec3) -> f32 {
+        let d1: f32 = self.a.distance(p);
+        let d2: f32 = self.b.distance(p);
+        let h: f32 = clamp(0.5 - 0.5 * (d1 + d2) / self.k, 0.0, 1.0);
+        return mix(d1, -d2, h) + self.k * h * (1.0 - h);
+    }
+}
+
+/* Linear morph between two shapes, t = 0 gives a and t = 1 gives b */
+pub struct SdfBlend {
+    a: Box<dyn SignedDistance>,
+    b: Box<dyn SignedDistance>,
+    t: f32,
+}
+
+impl SdfBlend {
+    pub fn new(a: Box<dyn SignedDistance>, b: Box<dyn SignedDistance>, t: f32) -> Self {
+        return Self { a, b, t };
+    }
+}
+
+impl SignedDistance for SdfBlend {
+    fn distance(&self, p: &Vec3) -> f32 {
+        return mix(self.a.distance(p), self.b.distance(p), self.t);
+    }
+}
+
+/* Rounds off any shape by growing it outwards */
+pub struct SdfRound {
+    shape: Box<dyn SignedDistance>,
+    radius: f32,
+}
+
+impl SdfRound {
+    pub fn new(shape: Box<dyn SignedDistance>, radius: f32) -> Self {
+        return Self { shape, radius };
+    }
+}
+
+impl SignedDistance for SdfRound {
+    fn distance(&self, p: &Vec3) -> f32 {
+        return self.shape.distance(p) - self.radius;
+    }
+}
+
+/* Infinite repetition of a shape on a regular grid with the given cell size */
+pub struct SdfRepeat {
+    shape: Box<dyn SignedDistance>,
+    period: Vec3,
+}
+
+impl SdfRepeat {
+    pub fn new(shape: Box<dyn SignedDistance>, period: Vec3) -> Self {
+        return Self { shape, period };
+    }
+}
+
+impl SignedDistance for SdfRepeat {
+    fn distance(&self, p: &Vec3) -> f32 {
+        let cell: Vec3 = round(*p / self.period);
+        let local: Vec3 = *p - self.period * cell;
+        return self.shape.distance(&local);
+    }
+}
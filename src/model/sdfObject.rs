@@ -0,0 +1,141 @@
+use std::rc::Rc;
+use glm::{cross, length, normalize, Vec2, Vec3, vec3};
+use crate::math::aabb::AABB;
+use crate::math::ray::Ray;
+use crate::model::hitRecord::HitRecord;
+use crate::model::hittableObject::HittableObject;
+use crate::model::material::Material;
+use crate::model::sdf::SignedDistance;
+use crate::model::sphere::Sphere;
+
+/* Hittable surface of a signed distance function, found by sphere tracing inside the given bounds */
+pub struct SdfObject {
+    sdf: Box<dyn SignedDistance>,
+    bounds: AABB,
+    material: Rc<dyn Material>,
+    maxSteps: i32,
+    epsilon: f32,
+    /* Fraction of the distance taken per step, below 1 for estimators that may overshoot such as fractals */
+    stepScale: f32,
+}
+
+impl SdfObject {
+    pub fn new(sdf: Box<dyn SignedDistance>, bounds: AABB, mat: Rc<dyn Material>) -> Self {
+        return Self {
+            sdf,
+            bounds,
+            material: mat,
+            maxSteps: 256,
+            epsilon: 1e-4,
+            stepScale: 1.0,
+        };
+    }
+
+    pub fn setMarching(&mut self, maxSteps: i32, epsilon: f32, stepScale: f32) {
+        self.maxSteps = maxSteps;
+        self.epsilon = epsilon;
+        self.stepScale = stepScale;
+    }
+
+    /* Gradient of the distance field by the tetrahedron technique */
+    pub fn normal(&self, p: &Vec3) -> Vec3 {
+        let h: f32 = self.epsilon;
+        let k0: Vec3 = vec3(1.0, -1.0, -1.0);
+        let k1: Vec3 = vec3(-1.0, -1.0, 1.0);
+        let k2: Vec3 = vec3(-1.0, 1.0, -1.0);
+        let k3: Vec3 = vec3(1.0, 1.0, 1.0);
+
+        return normalize(k0 * self.sdf.distance(&(*p + k0 * h))
+            + k1 * self.sdf.distance(&(*p + k1 * h))
+            + k2 * self.sdf.distance(&(*p + k2 * h))
+            + k3 * self.sdf.distance(&(*p + k3 * h)));
+    }
+}
+
+impl HittableObject for SdfObject {
+    fn testIntersection(&self, ray: &Ray, tMin: f32, tMax: f32, hitRecord: &mut HitRecord) -> bool {
+        let (start, end) = match self.bounds.intersect(ray, tMin, tMax) {
+            Some(range) => range,
+            None => return false,
+        };
+
+        let directionLength: f32 = length(ray.direction);
+        let mut t: f32 = start;
+
+        // Skip the surface the ray is leaving, otherwise secondary rays would stop at their own origin
+        let startsOnSurface: bool = f32::abs(self.sdf.distance(&ray.at(t))) < self.epsilon;
+        if startsOnSurface {
+            t += 4.0 * self.epsilon / directionLength;
+        }
+
+        let mut converged: bool = false;
+        for _ in 0..self.maxSteps {
+            if t > end {
+                return false;
+            }
+
+            let distance: f32 = f32::abs(self.sdf.distance(&ray.at(t)));
+            if distance < self.epsilon {
+                converged = true;
+                break;
+            }
+
+            t += distance * self.stepScale / directionLength;
+        }
+
+        if !converged || t < tMin || t > end {
+            return false;
+        }
+
+        let point: Vec3 = ray.at(t);
+        let outwardNormal: Vec3 = self.normal(&point);
+
+        hitRecord.t = t;
+        hitRecord.point = point;
+        hitRecord.material = Rc::clone(&self.material);
+        hitRecord.SetFaceNormal(ray, outwardNormal);
+
+        let (uv, tangent): (Vec2, Vec3) = Sphere::surfaceCoordinates(&outwardNormal);
+        hitRecord.setSurfaceFrame(uv, tangent, cross(outwardNormal, tangent));
+
+        return true;
+    }
+
+    fn boundingBox(&self, outputBox: &mut AABB) -> bool {
+        *outputBox = self.bounds.clone();
+        return true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glm::{dot, vec3};
+    use crate::model::lambertian::Lambertian;
+    use crate::model::sdf::SdfSphere;
+    use super::*;
+
+    #[test]
+    fn sphereTracingMatchesTheAnalyticSphere() {
+        let (center, radius) = (vec3(0.5, -0.25, 1.0), 1.5);
+        let material: Rc<dyn Material> = Rc::new(Lambertian::default());
+        let sphere: Sphere = Sphere::new(radius, center, Rc::clone(&material));
+        let bounds: AABB = AABB::new(center - vec3(2.0, 2.0, 2.0), center + vec3(2.0, 2.0, 2.0));
+        let traced: SdfObject = SdfObject::new(Box::new(SdfSphere::new(center, radius)), bounds, material);
+
+        let origin: Vec3 = vec3(-4.0, 3.0, -5.0);
+        for (x, y) in [(0.0, 0.0), (0.6, -0.3), (-0.9, 1.1), (1.3, 0.2), (0.0, -1.4)] {
+            let ray: Ray = Ray::new(origin, center + vec3(x, y, 0.0) - origin);
+            let mut expected: HitRecord = HitRecord::default();
+            let mut found: HitRecord = HitRecord::default();
+            assert!(sphere.testIntersection(&ray, 0.001, f32::MAX, &mut expected));
+            assert!(traced.testIntersection(&ray, 0.001, f32::MAX, &mut found));
+            assert!((found.t - expected.t).abs() * length(ray.direction) < 1e-3);
+            assert!(dot(found.normal, expected.normal) > 0.999);
+            assert_eq!(found.frontFace, expected.frontFace);
+        }
+
+        // Passing by the sphere inside the bounds
+        let miss: Ray = Ray::new(origin, center + vec3(1.6, 1.6, 0.0) - origin);
+        assert!(!traced.testIntersection(&miss, 0.001, f32::MAX, &mut HitRecord::default()));
+    }
+}
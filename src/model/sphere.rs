@@ -1,7 +1,9 @@
 ﻿use std::ops::Deref;
 use std::rc::Rc;
-use glm::{dot, sqrt, Vec3, vec3};
+use std::f32::consts::PI;
+use glm::{acos, atan2, cross, dot, normalize, sqrt, Vec2, vec2, Vec3, vec3};
 use crate::Lambertian;
+use crate::math::aabb::AABB;
 use crate::math::mathUtils::vectorLengthSquared;
 use crate::math::ray::Ray;
 use crate::model::material::Material;
@@ -48,9 +50,18 @@ impl HittableObject for Sphere {
         let outwardNormal: Vec3 = (hitRecord.point - self.center) / self.radius;
         
         hitRecord.SetFaceNormal(ray,outwardNormal);
+
+        let (uv, tangent): (Vec2, Vec3) = Sphere::surfaceCoordinates(&outwardNormal);
+        hitRecord.setSurfaceFrame(uv, tangent, cross(outwardNormal, tangent));
         
         return true;
     }
+
+    fn boundingBox(&self, outputBox: &mut AABB) -> bool {
+        let extent: Vec3 = vec3(self.radius, self.radius, self.radius);
+        *outputBox = AABB::new(self.center - extent, self.center + extent);
+        return true;
+    }
 }
 
 impl Sphere {
@@ -64,6 +75,21 @@ impl Sphere {
         }
     }
     
+    /* Spherical UV coordinates and the dP/du tangent direction for a point given by its outward unit normal */
+    pub fn surfaceCoordinates(n: &Vec3) -> (Vec2, Vec3) {
+        let theta: f32 = acos((-n.y).clamp(-1.0, 1.0));
+        let phi: f32 = atan2(-n.z, n.x) + PI;
+
+        let tangentLength: f32 = sqrt(n.x * n.x + n.z * n.z);
+        let tangent: Vec3 = if tangentLength > 1e-6 {
+            normalize(vec3(n.z, 0.0, -n.x))
+        } else {
+            vec3(1.0, 0.0, 0.0)
+        };
+
+        return (vec2(phi / (2.0 * PI), theta / PI), tangent);
+    }
+    
     pub fn getRadius(&self) -> f32 {
         return self.radius;
     }
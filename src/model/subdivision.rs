@@ -0,0 +1,540 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use glm::{Vec2, vec2, Vec3, vec3};
+
+pub enum SubdivisionScheme {
+    /* Works on any polygons and produces quads */
+    CatmullClark,
+    /* Works on triangles, other polygons are fan triangulated first */
+    Loop,
+}
+
+/* Polygon of a control cage, with optional per corner indices into the UV list */
+pub struct Face {
+    pub vertices: Vec<usize>,
+    pub uvs: Vec<usize>,
+}
+
+/* Coarse polygon mesh that subdivision starts from. Crease edges stay sharp, as do boundary edges */
+pub struct ControlCage {
+    pub positions: Vec<Vec3>,
+    pub uvs: Vec<Vec2>,
+    pub faces: Vec<Face>,
+    pub creases: HashSet<(usize, usize)>,
+}
+
+/* Faces around an edge and the index of the point that splits it */
+struct Edge {
+    faces: Vec<usize>,
+    point: usize,
+}
+
+/* Edges by their vertices, smaller index first */
+type EdgeMap = HashMap<(usize, usize), Edge>;
+
+fn edgeKey(a: usize, b: usize) -> (usize, usize) {
+    return if a < b { (a, b) } else { (b, a) };
+}
+
+impl ControlCage {
+    pub fn new() -> Self {
+        return Self {
+            positions: vec![],
+            uvs: vec![],
+            faces: vec![],
+            creases: HashSet::new(),
+        };
+    }
+
+    /* Wavefront OBJ reader for positions, texture coordinates and faces. Sharp edges can be tagged with a
+       non-standard "crease <vertex> <vertex>" line, which other OBJ readers simply ignore */
+    pub fn load_obj(path: &str) -> Result<ControlCage, String> {
+        let source: String = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let mut cage: ControlCage = ControlCage::new();
+
+        let resolve = |index: &str, count: usize, lineNumber: usize| -> Result<usize, String> {
+            let value: i64 = index.parse::<i64>().map_err(|_| format!("{}:{}: bad index '{}'", path, lineNumber, index))?;
+            let resolved: i64 = if value < 0 { count as i64 + value } else { value - 1 };
+            if resolved < 0 || resolved >= count as i64 {
+                return Err(format!("{}:{}: index {} out of range", path, lineNumber, value));
+            }
+            return Ok(resolved as usize);
+        };
+
+        for (index, line) in source.lines().enumerate() {
+            let lineNumber: usize = index + 1;
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            if tokens.is_empty() {
+                continue;
+            }
+
+            let numbers = |from: usize| -> Result<Vec<f32>, String> {
+                return tokens[from..].iter()
+                    .map(|t| t.parse::<f32>().map_err(|_| format!("{}:{}: bad number '{}'", path, lineNumber, t)))
+                    .collect();
+            };
+
+            match tokens[0] {
+                "v" => {
+                    let values: Vec<f32> = numbers(1)?;
+                    if values.len() < 3 {
+                        return Err(format!("{}:{}: a vertex needs three coordinates", path, lineNumber));
+                    }
+                    cage.positions.push(vec3(values[0], values[1], values[2]));
+                }
+                "vt" => {
+                    let values: Vec<f32> = numbers(1)?;
+                    if values.len() < 2 {
+                        return Err(format!("{}:{}: a texture coordinate needs two values", path, lineNumber));
+                    }
+                    cage.uvs.push(vec2(values[0], values[1]));
+                }
+                "f" => {
+                    let mut face: Face = Face { vertices: vec![], uvs: vec![] };
+                    for corner in tokens[1..].iter() {
+                        let parts: Vec<&str> = corner.split('/').collect();
+                        face.vertices.push(resolve(parts[0], cage.positions.len(), lineNumber)?);
+                        if parts.len() > 1 && !parts[1].is_empty() {
+                            face.uvs.push(resolve(parts[1], cage.uvs.len(), lineNumber)?);
+                        }
+                    }
+                    if face.vertices.len() < 3 {
+                        return Err(format!("{}:{}: a face needs at least three vertices", path, lineNumber));
+                    }
+                    if face.uvs.len() != face.vertices.len() {
+                        face.uvs.clear();
+                    }
+                    cage.faces.push(face);
+                }
+                "crease" => {
+                    if tokens.len() != 3 {
+                        return Err(format!("{}:{}: a crease needs two vertices", path, lineNumber));
+                    }
+                    let a: usize = resolve(tokens[1], cage.positions.len(), lineNumber)?;
+                    let b: usize = resolve(tokens[2], cage.positions.len(), lineNumber)?;
+                    cage.creases.insert(edgeKey(a, b));
+                }
+                _ => {}
+            }
+        }
+
+        return Ok(cage);
+    }
+
+    pub fn subdivide(&self, scheme: &SubdivisionScheme, levels: i32) -> ControlCage {
+        let mut result: ControlCage = match scheme {
+            SubdivisionScheme::CatmullClark => self.clone(),
+            SubdivisionScheme::Loop => self.triangulated(),
+        };
+
+        for _ in 0..levels {
+            result = match scheme {
+                SubdivisionScheme::CatmullClark => result.catmullClark(),
+                SubdivisionScheme::Loop => result.loopSubdivision(),
+            };
+        }
+
+        return result;
+    }
+
+    /* Splits every polygon into a fan of triangles */
+    pub fn triangulated(&self) -> ControlCage {
+        let mut result: ControlCage = ControlCage {
+            positions: self.positions.clone(),
+            uvs: self.uvs.clone(),
+            faces: vec![],
+            creases: self.creases.clone(),
+        };
+
+        for face in self.faces.iter() {
+            for i in 1..face.vertices.len() - 1 {
+                result.faces.push(Face {
+                    vertices: vec![face.vertices[0], face.vertices[i], face.vertices[i + 1]],
+                    uvs: if face.uvs.is_empty() { vec![] } else { vec![face.uvs[0], face.uvs[i], face.uvs[i + 1]] },
+                });
+            }
+        }
+
+        return result;
+    }
+
+    /* Edges with their adjacent faces, and for every vertex the neighbours it shares an edge with */
+    fn topology(&self, firstEdgePoint: usize) -> (EdgeMap, Vec<Vec<usize>>) {
+        let mut edges: EdgeMap = HashMap::new();
+        let mut neighbours: Vec<Vec<usize>> = vec![vec![]; self.positions.len()];
+
+        for (faceIndex, face) in self.faces.iter().enumerate() {
+            let count: usize = face.vertices.len();
+            for i in 0..count {
+                let a: usize = face.vertices[i];
+                let b: usize = face.vertices[(i + 1) % count];
+                let key: (usize, usize) = edgeKey(a, b);
+
+                if !edges.contains_key(&key) {
+                    let point: usize = firstEdgePoint + edges.len();
+                    edges.insert(key, Edge { faces: vec![], point });
+                    neighbours[a].push(b);
+                    neighbours[b].push(a);
+                }
+                edges.get_mut(&key).unwrap().faces.push(faceIndex);
+            }
+        }
+
+        return (edges, neighbours);
+    }
+
+    fn isSharp(&self, key: &(usize, usize), edge: &Edge) -> bool {
+        return edge.faces.len() != 2 || self.creases.contains(key);
+    }
+
+    /* Sharp edges from a vertex, as the vertices at their other ends */
+    fn sharpNeighbours(&self, vertex: usize, neighbours: &[usize], edges: &EdgeMap) -> Vec<usize> {
+        return neighbours.iter()
+            .filter(|&&other| {
+                let key: (usize, usize) = edgeKey(vertex, other);
+                return self.isSharp(&key, &edges[&key]);
+            })
+            .cloned()
+            .collect();
+    }
+
+    fn faceUvCentroid(&self, face: &Face) -> Vec2 {
+        let mut sum: Vec2 = vec2(0.0, 0.0);
+        for uv in face.uvs.iter() {
+            sum = sum + self.uvs[*uv];
+        }
+        return sum / face.uvs.len() as f32;
+    }
+
+    fn subdividedCreases(&self, edges: &EdgeMap) -> HashSet<(usize, usize)> {
+        let mut creases: HashSet<(usize, usize)> = HashSet::new();
+        for key in self.creases.iter() {
+            if let Some(edge) = edges.get(key) {
+                creases.insert(edgeKey(key.0, edge.point));
+                creases.insert(edgeKey(edge.point, key.1));
+            }
+        }
+        return creases;
+    }
+
+    fn catmullClark(&self) -> ControlCage {
+        let vertexCount: usize = self.positions.len();
+        let faceCount: usize = self.faces.len();
+        let (edges, neighbours) = self.topology(vertexCount + faceCount);
+
+        let mut positions: Vec<Vec3> = vec![vec3(0.0, 0.0, 0.0); vertexCount + faceCount + edges.len()];
+
+        // Face points
+        let mut facesOfVertex: Vec<Vec<usize>> = vec![vec![]; vertexCount];
+        for (faceIndex, face) in self.faces.iter().enumerate() {
+            let mut sum: Vec3 = vec3(0.0, 0.0, 0.0);
+            for v in face.vertices.iter() {
+                sum = sum + self.positions[*v];
+                facesOfVertex[*v].push(faceIndex);
+            }
+            positions[vertexCount + faceIndex] = sum / face.vertices.len() as f32;
+        }
+
+        // Edge points
+        for (key, edge) in edges.iter() {
+            let midpoint: Vec3 = (self.positions[key.0] + self.positions[key.1]) * 0.5;
+            positions[edge.point] = if self.isSharp(key, edge) {
+                midpoint
+            } else {
+                (self.positions[key.0] + self.positions[key.1]
+                    + positions[vertexCount + edge.faces[0]] + positions[vertexCount + edge.faces[1]]) * 0.25
+            };
+        }
+
+        // Vertex points
+        for v in 0..vertexCount {
+            let p: Vec3 = self.positions[v];
+            let sharp: Vec<usize> = self.sharpNeighbours(v, &neighbours[v], &edges);
+            let valence: usize = neighbours[v].len();
+
+            positions[v] = if valence == 0 || sharp.len() > 2 {
+                p
+            } else if sharp.len() == 2 {
+                (p * 6.0 + self.positions[sharp[0]] + self.positions[sharp[1]]) / 8.0
+            } else {
+                let mut faceAverage: Vec3 = vec3(0.0, 0.0, 0.0);
+                for f in facesOfVertex[v].iter() {
+                    faceAverage = faceAverage + positions[vertexCount + *f];
+                }
+                faceAverage = faceAverage / facesOfVertex[v].len() as f32;
+
+                let mut edgeAverage: Vec3 = vec3(0.0, 0.0, 0.0);
+                for other in neighbours[v].iter() {
+                    edgeAverage = edgeAverage + (p + self.positions[*other]) * 0.5;
+                }
+                edgeAverage = edgeAverage / valence as f32;
+
+                let n: f32 = valence as f32;
+                (faceAverage + edgeAverage * 2.0 + p * (n - 3.0)) / n
+            };
+        }
+
+        let mut result: ControlCage = ControlCage {
+            positions,
+            uvs: self.uvs.clone(),
+            faces: Vec::with_capacity(faceCount * 4),
+            creases: self.subdividedCreases(&edges),
+        };
+
+        for (faceIndex, face) in self.faces.iter().enumerate() {
+            let count: usize = face.vertices.len();
+            let hasUvs: bool = !face.uvs.is_empty();
+
+            let centerUv: usize = result.uvs.len();
+            if hasUvs {
+                result.uvs.push(self.faceUvCentroid(face));
+            }
+
+            for i in 0..count {
+                let previous: usize = (i + count - 1) % count;
+                let next: usize = (i + 1) % count;
+
+                let v: usize = face.vertices[i];
+                let nextEdge: usize = edges[&edgeKey(v, face.vertices[next])].point;
+                let previousEdge: usize = edges[&edgeKey(face.vertices[previous], v)].point;
+
+                let mut uvs: Vec<usize> = vec![];
+                if hasUvs {
+                    let corner: Vec2 = self.uvs[face.uvs[i]];
+                    result.uvs.push((corner + self.uvs[face.uvs[next]]) * 0.5);
+                    result.uvs.push((corner + self.uvs[face.uvs[previous]]) * 0.5);
+                    let base: usize = result.uvs.len() - 2;
+                    uvs = vec![face.uvs[i], base, centerUv, base + 1];
+                }
+
+                result.faces.push(Face {
+                    vertices: vec![v, nextEdge, vertexCount + faceIndex, previousEdge],
+                    uvs,
+                });
+            }
+        }
+
+        return result;
+    }
+
+    fn loopSubdivision(&self) -> ControlCage {
+        let vertexCount: usize = self.positions.len();
+        let (edges, neighbours) = self.topology(vertexCount);
+
+        let mut positions: Vec<Vec3> = vec![vec3(0.0, 0.0, 0.0); vertexCount + edges.len()];
+
+        // Edge points, weighting in the vertices opposite the edge in both triangles
+        for (key, edge) in edges.iter() {
+            let a: Vec3 = self.positions[key.0];
+            let b: Vec3 = self.positions[key.1];
+
+            positions[edge.point] = if self.isSharp(key, edge) {
+                (a + b) * 0.5
+            } else {
+                let mut opposite: Vec3 = vec3(0.0, 0.0, 0.0);
+                for f in edge.faces.iter() {
+                    for v in self.faces[*f].vertices.iter() {
+                        if *v != key.0 && *v != key.1 {
+                            opposite = opposite + self.positions[*v];
+                        }
+                    }
+                }
+                (a + b) * (3.0 / 8.0) + opposite * (1.0 / 8.0)
+            };
+        }
+
+        // Vertex points
+        for v in 0..vertexCount {
+            let p: Vec3 = self.positions[v];
+            let sharp: Vec<usize> = self.sharpNeighbours(v, &neighbours[v], &edges);
+            let valence: usize = neighbours[v].len();
+
+            positions[v] = if valence == 0 || sharp.len() > 2 {
+                p
+            } else if sharp.len() == 2 {
+                p * 0.75 + (self.positions[sharp[0]] + self.positions[sharp[1]]) * 0.125
+            } else {
+                let n: f32 = valence as f32;
+                let beta: f32 = if valence == 3 { 3.0 / 16.0 } else { 3.0 / (8.0 * n) };
+                let mut sum: Vec3 = vec3(0.0, 0.0, 0.0);
+                for other in neighbours[v].iter() {
+                    sum = sum + self.positions[*other];
+                }
+                p * (1.0 - n * beta) + sum * beta
+            };
+        }
+
+        let mut result: ControlCage = ControlCage {
+            positions,
+            uvs: self.uvs.clone(),
+            faces: Vec::with_capacity(self.faces.len() * 4),
+            creases: self.subdividedCreases(&edges),
+        };
+
+        for face in self.faces.iter() {
+            let [a, b, c] = [face.vertices[0], face.vertices[1], face.vertices[2]];
+            let ab: usize = edges[&edgeKey(a, b)].point;
+            let bc: usize = edges[&edgeKey(b, c)].point;
+            let ca: usize = edges[&edgeKey(c, a)].point;
+
+            let mut uvAb: usize = 0;
+            let mut uvBc: usize = 0;
+            let mut uvCa: usize = 0;
+            let hasUvs: bool = !face.uvs.is_empty();
+            if hasUvs {
+                let (ua, ub, uc) = (self.uvs[face.uvs[0]], self.uvs[face.uvs[1]], self.uvs[face.uvs[2]]);
+                uvAb = result.uvs.len();
+                result.uvs.push((ua + ub) * 0.5);
+                uvBc = result.uvs.len();
+                result.uvs.push((ub + uc) * 0.5);
+                uvCa = result.uvs.len();
+                result.uvs.push((uc + ua) * 0.5);
+            }
+
+            let corners: [([usize; 3], [usize; 3]); 4] = [
+                ([a, ab, ca], [if hasUvs { face.uvs[0] } else { 0 }, uvAb, uvCa]),
+                ([ab, b, bc], [uvAb, if hasUvs { face.uvs[1] } else { 0 }, uvBc]),
+                ([ca, bc, c], [uvCa, uvBc, if hasUvs { face.uvs[2] } else { 0 }]),
+                ([ab, bc, ca], [uvAb, uvBc, uvCa]),
+            ];
+
+            for (vertices, uvs) in corners.iter() {
+                result.faces.push(Face {
+                    vertices: vertices.to_vec(),
+                    uvs: if hasUvs { uvs.to_vec() } else { vec![] },
+                });
+            }
+        }
+
+        return result;
+    }
+}
+
+impl Clone for Face {
+    fn clone(&self) -> Self {
+        return Face {
+            vertices: self.vertices.clone(),
+            uvs: self.uvs.clone(),
+        };
+    }
+}
+
+impl Clone for ControlCage {
+    fn clone(&self) -> Self {
+        return ControlCage {
+            positions: self.positions.clone(),
+            uvs: self.uvs.clone(),
+            faces: self.faces.clone(),
+            creases: self.creases.clone(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glm::length;
+    use super::*;
+
+    /* Cube from -1 to 1 around the origin, vertex i at the corner whose x, y and z are set by its bits */
+    fn cubeCage() -> ControlCage {
+        let mut cage: ControlCage = ControlCage::new();
+        for i in 0..8 {
+            let coordinate = |bit: i32| if i & bit != 0 { 1.0 } else { -1.0 };
+            cage.positions.push(vec3(coordinate(1), coordinate(2), coordinate(4)));
+        }
+        for vertices in [[0, 2, 3, 1], [4, 5, 7, 6], [0, 1, 5, 4], [2, 6, 7, 3], [0, 4, 6, 2], [1, 3, 7, 5]] {
+            cage.faces.push(Face { vertices: vertices.to_vec(), uvs: vec![] });
+        }
+        return cage;
+    }
+
+    #[test]
+    fn oneLevelOfACube() {
+        let cage: ControlCage = cubeCage();
+
+        // A vertex per corner, edge and face, four quads per face
+        let catmullClark: ControlCage = cage.subdivide(&SubdivisionScheme::CatmullClark, 1);
+        assert_eq!(catmullClark.positions.len(), 8 + 12 + 6);
+        assert_eq!(catmullClark.faces.len(), 24);
+        assert!(catmullClark.faces.iter().all(|face| face.vertices.len() == 4));
+
+        // The 12 triangles of the fan triangulated cube have 18 edges
+        let loopSubdivided: ControlCage = cage.subdivide(&SubdivisionScheme::Loop, 1);
+        assert_eq!(loopSubdivided.positions.len(), 8 + 18);
+        assert_eq!(loopSubdivided.faces.len(), 48);
+        assert!(loopSubdivided.faces.iter().all(|face| face.vertices.len() == 3));
+    }
+
+    #[test]
+    fn catmullClarkConvergesToARoundLimit() {
+        let cage: ControlCage = cubeCage();
+        let mut previousRatio: f32 = f32::MAX;
+        let mut previousCorner: Vec3 = cage.positions[0];
+        let mut previousStep: f32 = f32::MAX;
+
+        for levels in 1..5 {
+            let subdivided: ControlCage = cage.subdivide(&SubdivisionScheme::CatmullClark, levels);
+            let radii: Vec<f32> = subdivided.positions.iter().map(|p| length(*p)).collect();
+            let ratio: f32 = radii.iter().fold(0.0, |a: f32, b: &f32| f32::max(a, *b)) / radii.iter().fold(f32::MAX, |a: f32, b: &f32| f32::min(a, *b));
+            assert!(ratio <= previousRatio + 1e-3);
+            previousRatio = ratio;
+
+            // The corner keeps its index and moves less every level
+            let step: f32 = length(subdivided.positions[0] - previousCorner);
+            assert!(step < previousStep);
+            previousCorner = subdivided.positions[0];
+            previousStep = step;
+        }
+        assert!(previousRatio < 1.04);
+        assert!(previousStep < 0.01);
+    }
+
+    #[test]
+    fn creasesStayOnTheCageEdges() {
+        let mut cage: ControlCage = cubeCage();
+        for face in cage.faces.iter() {
+            for i in 0..4 {
+                cage.creases.insert(edgeKey(face.vertices[i], face.vertices[(i + 1) % 4]));
+            }
+        }
+
+        for scheme in [SubdivisionScheme::CatmullClark, SubdivisionScheme::Loop] {
+            let subdivided: ControlCage = cage.subdivide(&scheme, 2);
+            // Two levels split each of the 12 cube edges into four
+            assert_eq!(subdivided.creases.len(), 48);
+            for (a, b) in subdivided.creases.iter() {
+                for p in [subdivided.positions[*a], subdivided.positions[*b]] {
+                    let onFaces: usize = [p.x, p.y, p.z].iter().filter(|c| (c.abs() - 1.0).abs() < 1e-5).count();
+                    assert!(onFaces >= 2, "{:?} left the cube's edges", p);
+                }
+            }
+            // The corners have three creases and stay put
+            for i in 0..8 {
+                assert_eq!(subdivided.positions[i], cage.positions[i]);
+            }
+        }
+    }
+
+    #[test]
+    fn uvsAreInterpolated() {
+        let mut cage: ControlCage = ControlCage::new();
+        cage.positions = vec![vec3(0.0, 0.0, 0.0), vec3(1.0, 0.0, 0.0), vec3(1.0, 1.0, 0.0), vec3(0.0, 1.0, 0.0)];
+        cage.uvs = vec![vec2(0.0, 0.0), vec2(1.0, 0.0), vec2(1.0, 1.0), vec2(0.0, 1.0)];
+        cage.faces.push(Face { vertices: vec![0, 1, 2, 3], uvs: vec![0, 1, 2, 3] });
+
+        let subdivided: ControlCage = cage.subdivide(&SubdivisionScheme::CatmullClark, 1);
+        assert_eq!(subdivided.faces.len(), 4);
+        for face in subdivided.faces.iter() {
+            // The edge and face points of a flat unit square sit at their own uv, only the corners move along the
+            // boundary
+            for (vertex, uv) in face.vertices.iter().zip(face.uvs.iter()) {
+                let position: Vec3 = subdivided.positions[*vertex];
+                let uv: Vec2 = subdivided.uvs[*uv];
+                if face.vertices[0] != *vertex {
+                    assert!((uv.x - position.x).abs() < 1e-6 && (uv.y - position.y).abs() < 1e-6);
+                }
+            }
+            assert_eq!(subdivided.uvs[face.uvs[2]], vec2(0.5, 0.5));
+        }
+    }
+}
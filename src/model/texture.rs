@@ -0,0 +1,106 @@
+use glm::{Vec2, Vec3};
+use sfml::graphics::{Color, Image};
+use sfml::system::Vector2u;
+use crate::math::perlin::Perlin;
+use crate::math::vColor::VColor;
+
+pub trait Texture {
+    fn value(&self, uv: &Vec2, point: &Vec3) -> VColor;
+}
+
+pub struct SolidColor {
+    color: VColor,
+}
+
+impl SolidColor {
+    pub fn new(color: VColor) -> Self {
+        return Self {
+            color
+        };
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _uv: &Vec2, _point: &Vec3) -> VColor {
+        return self.color.clone();
+    }
+}
+
+/* Image looked up by the surface UV coordinates, repeating outside of [0, 1] */
+pub struct ImageTexture {
+    image: Image,
+    width: u32,
+    height: u32,
+}
+
+impl ImageTexture {
+    pub fn new(image: Image) -> Self {
+        let size: Vector2u = image.get_size();
+        return Self {
+            width: size.x,
+            height: size.y,
+            image,
+        };
+    }
+
+    pub fn new_from_file(path: &str) -> Option<Self> {
+        return Image::new_from_file(path).map(ImageTexture::new);
+    }
+
+    /* Texel with wrapped integer coordinates */
+    pub fn texel(&self, x: i64, y: i64) -> VColor {
+        let wx: u32 = x.rem_euclid(self.width as i64) as u32;
+        let wy: u32 = y.rem_euclid(self.height as i64) as u32;
+        let pixel: Color = self.image.get_pixel(wx, wy);
+
+        return VColor::new_rgba(pixel.0.red as f32 / 255.0,
+                                pixel.0.green as f32 / 255.0,
+                                pixel.0.blue as f32 / 255.0,
+                                pixel.0.alpha as f32 / 255.0);
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, uv: &Vec2, _point: &Vec3) -> VColor {
+        if self.width == 0 || self.height == 0 {
+            return VColor::new_rgb(1.0, 0.0, 1.0);
+        }
+
+        // Image rows go top to bottom while v goes bottom to top
+        let x: f32 = uv.x * self.width as f32 - 0.5;
+        let y: f32 = (1.0 - uv.y) * self.height as f32 - 0.5;
+
+        let x0: f32 = x.floor();
+        let y0: f32 = y.floor();
+        let fx: f32 = x - x0;
+        let fy: f32 = y - y0;
+
+        let top: VColor = self.texel(x0 as i64, y0 as i64) * (1.0 - fx) + self.texel(x0 as i64 + 1, y0 as i64) * fx;
+        let bottom: VColor = self.texel(x0 as i64, y0 as i64 + 1) * (1.0 - fx) + self.texel(x0 as i64 + 1, y0 as i64 + 1) * fx;
+
+        return top * (1.0 - fy) + bottom * fy;
+    }
+}
+
+/* Procedural turbulence evaluated at the hit point in object space */
+pub struct NoiseTexture {
+    noise: Perlin,
+    scale: f32,
+    octaves: i32,
+}
+
+impl NoiseTexture {
+    pub fn new(scale: f32, octaves: i32) -> Self {
+        return Self {
+            noise: Perlin::new(),
+            scale,
+            octaves,
+        };
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _uv: &Vec2, point: &Vec3) -> VColor {
+        return VColor::new_sc(self.noise.turbulence(&(*point * self.scale), self.octaves));
+    }
+}
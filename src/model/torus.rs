@@ -0,0 +1,194 @@
+use std::f32::consts::PI;
+use std::rc::Rc;
+use glm::{atan2, cross, length, normalize, sqrt, Vec2, vec2, Vec3, vec3};
+use crate::math::aabb::AABB;
+use crate::math::frame::Frame;
+use crate::math::polynomial::solveQuartic;
+use crate::math::ray::Ray;
+use crate::model::hitRecord::HitRecord;
+use crate::model::hittableObject::HittableObject;
+use crate::model::material::Material;
+
+/* Torus around an axis through its center, majorRadius to the middle of the tube and minorRadius of the tube */
+pub struct Torus {
+    frame: Frame,
+    majorRadius: f32,
+    minorRadius: f32,
+    material: Rc<dyn Material>,
+}
+
+impl Torus {
+    pub fn new(center: Vec3, axis: Vec3, majorRadius: f32, minorRadius: f32, mat: Rc<dyn Material>) -> Self {
+        return Self {
+            frame: Frame::new(center, axis),
+            majorRadius,
+            minorRadius,
+            material: mat,
+        };
+    }
+
+    /* Implicit function (|p|² + R² - r²)² - 4R²(x² + z²) for a local point at distance s along a unit direction */
+    fn implicit(&self, origin: &Vec3, direction: &Vec3, s: f64) -> (f64, f64) {
+        let r2: f64 = (self.majorRadius * self.majorRadius) as f64;
+        let px: f64 = origin.x as f64 + direction.x as f64 * s;
+        let py: f64 = origin.y as f64 + direction.y as f64 * s;
+        let pz: f64 = origin.z as f64 + direction.z as f64 * s;
+
+        let sum: f64 = px * px + py * py + pz * pz + r2 - (self.minorRadius * self.minorRadius) as f64;
+        let value: f64 = sum * sum - 4.0 * r2 * (px * px + pz * pz);
+
+        let dSum: f64 = 2.0 * (px * direction.x as f64 + py * direction.y as f64 + pz * direction.z as f64);
+        let derivative: f64 = 2.0 * sum * dSum - 8.0 * r2 * (px * direction.x as f64 + pz * direction.z as f64);
+
+        return (value, derivative);
+    }
+}
+
+impl HittableObject for Torus {
+    fn testIntersection(&self, ray: &Ray, tMin: f32, tMax: f32, hitRecord: &mut HitRecord) -> bool {
+        let mut bounds: AABB = AABB::default();
+        self.boundingBox(&mut bounds);
+        if !bounds.hit(ray, tMin, tMax) {
+            return false;
+        }
+
+        let localOrigin: Vec3 = self.frame.pointToLocal(&ray.origin);
+        let localDirection: Vec3 = self.frame.vectorToLocal(&ray.direction);
+        let directionLength: f32 = length(localDirection);
+        let d: Vec3 = localDirection / directionLength;
+
+        // Start the ray at the closest point to the center to keep the quartic well conditioned
+        let shift: f32 = -(localOrigin.x * d.x + localOrigin.y * d.y + localOrigin.z * d.z);
+        let o: Vec3 = localOrigin + d * shift;
+
+        let r2: f64 = (self.majorRadius * self.majorRadius) as f64;
+        let ox: f64 = o.x as f64;
+        let oy: f64 = o.y as f64;
+        let oz: f64 = o.z as f64;
+        let dy: f64 = d.y as f64;
+        let od: f64 = ox * d.x as f64 + oy * dy + oz * d.z as f64;
+        let e: f64 = ox * ox + oy * oy + oz * oz - r2 - (self.minorRadius * self.minorRadius) as f64;
+
+        let roots: Vec<f64> = solveQuartic([
+            1.0,
+            4.0 * od,
+            2.0 * e + 4.0 * od * od + 4.0 * r2 * dy * dy,
+            4.0 * od * e + 8.0 * r2 * oy * dy,
+            e * e - 4.0 * r2 * ((self.minorRadius * self.minorRadius) as f64 - oy * oy),
+        ]);
+
+        let mut closest: Option<f32> = None;
+        for root in roots {
+            // A couple of Newton steps clean up the closed form result
+            let mut s: f64 = root;
+            for _ in 0..2 {
+                let (value, derivative) = self.implicit(&o, &d, s);
+                if derivative.abs() > 1e-12 {
+                    s -= value / derivative;
+                }
+            }
+
+            let t: f32 = (s as f32 + shift) / directionLength;
+            if t < tMin || t > tMax {
+                continue;
+            }
+            if closest.is_none_or(|c| t < c) {
+                closest = Some(t);
+            }
+        }
+
+        let t: f32 = match closest {
+            Some(t) => t,
+            None => return false,
+        };
+
+        let p: Vec3 = localOrigin + localDirection * t;
+        let ring: f32 = sqrt(p.x * p.x + p.z * p.z);
+        let ringDirection: Vec3 = if ring > 1e-8 { vec3(p.x, 0.0, p.z) / ring } else { vec3(1.0, 0.0, 0.0) };
+        let localNormal: Vec3 = normalize(p - ringDirection * self.majorRadius);
+
+        let phi: f32 = atan2(p.z, p.x);
+        let theta: f32 = atan2(p.y, ring - self.majorRadius);
+        let uv: Vec2 = vec2(0.5 - phi / (2.0 * PI), 0.5 + theta / (2.0 * PI));
+
+        let outwardNormal: Vec3 = self.frame.vectorToWorld(&localNormal);
+        let tangent: Vec3 = self.frame.vectorToWorld(&vec3(ringDirection.z, 0.0, -ringDirection.x));
+
+        hitRecord.t = t;
+        hitRecord.point = ray.at(t);
+        hitRecord.material = Rc::clone(&self.material);
+        hitRecord.SetFaceNormal(ray, outwardNormal);
+        hitRecord.setSurfaceFrame(uv, tangent, cross(outwardNormal, tangent));
+
+        return true;
+    }
+
+    fn boundingBox(&self, outputBox: &mut AABB) -> bool {
+        let outer: f32 = self.majorRadius + self.minorRadius;
+        *outputBox = self.frame.boundingBox(&vec3(-outer, -self.minorRadius, -outer), &vec3(outer, self.minorRadius, outer));
+        return true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glm::dot;
+    use crate::model::lambertian::Lambertian;
+    use super::*;
+
+    fn hit(torus: &Torus, origin: Vec3, direction: Vec3) -> Option<HitRecord> {
+        let mut hitRecord: HitRecord = HitRecord::default();
+        if !torus.testIntersection(&Ray::new(origin, direction), 0.001, f32::MAX, &mut hitRecord) {
+            return None;
+        }
+        return Some(hitRecord);
+    }
+
+    #[test]
+    fn rayHitsTheTube() {
+        let torus: Torus = Torus::new(vec3(0.0, 0.0, 0.0), vec3(0.0, 1.0, 0.0), 2.0, 0.5, Rc::new(Lambertian::default()));
+
+        let outer: HitRecord = hit(&torus, vec3(-5.0, 0.0, 0.0), vec3(1.0, 0.0, 0.0)).unwrap();
+        assert!((outer.t - 2.5).abs() < 1e-4);
+        assert!(dot(outer.normal, vec3(-1.0, 0.0, 0.0)) > 0.9999);
+
+        // From the center the inner wall of the tube is hit first
+        let inner: HitRecord = hit(&torus, vec3(0.0, 0.0, 0.0), vec3(0.0, 0.0, 1.0)).unwrap();
+        assert!((inner.t - 1.5).abs() < 1e-4);
+        assert!(inner.frontFace);
+        assert!(dot(inner.normal, vec3(0.0, 0.0, -1.0)) > 0.9999);
+
+        let top: HitRecord = hit(&torus, vec3(2.0, 5.0, 0.0), vec3(0.0, -1.0, 0.0)).unwrap();
+        assert!((top.t - 4.5).abs() < 1e-4);
+        assert!(dot(top.normal, vec3(0.0, 1.0, 0.0)) > 0.9999);
+
+        // Off the tube center line, x² + (y - 0.3)² = 0.5² gives x = 2 ± 0.4
+        let offset: HitRecord = hit(&torus, vec3(0.0, 0.3, -5.0), vec3(0.0, 0.0, 1.0)).unwrap();
+        assert!((offset.t - 2.6).abs() < 1e-4);
+        assert!(dot(offset.normal, normalize(vec3(0.0, 0.3, -0.4))) > 0.9999);
+    }
+
+    #[test]
+    fn grazingAndTiltedHits() {
+        // Grazing the top of the tube, the two roots nearly coincide
+        let torus: Torus = Torus::new(vec3(0.0, 0.0, 0.0), vec3(0.0, 1.0, 0.0), 2.0, 0.5, Rc::new(Lambertian::default()));
+        let grazing: HitRecord = hit(&torus, vec3(-5.0, 0.499, 0.0), vec3(1.0, 0.0, 0.0)).unwrap();
+        let expected: f32 = 3.0 - f32::sqrt(0.5 * 0.5 - 0.499 * 0.499);
+        assert!((grazing.t - expected).abs() < 1e-3);
+
+        let tilted: Torus = Torus::new(vec3(1.0, 1.0, 1.0), vec3(0.0, 0.0, 1.0), 2.0, 0.5, Rc::new(Lambertian::default()));
+        let hitRecord: HitRecord = hit(&tilted, vec3(3.0, 1.0, 6.0), vec3(0.0, 0.0, -1.0)).unwrap();
+        assert!((hitRecord.t - 4.5).abs() < 1e-4);
+        assert!(dot(hitRecord.normal, vec3(0.0, 0.0, 1.0)) > 0.9999);
+    }
+
+    #[test]
+    fn raysThroughTheHoleOrPastTheRimMiss() {
+        let torus: Torus = Torus::new(vec3(0.0, 0.0, 0.0), vec3(0.0, 1.0, 0.0), 2.0, 0.5, Rc::new(Lambertian::default()));
+        assert!(hit(&torus, vec3(0.0, 5.0, 0.0), vec3(0.0, -1.0, 0.0)).is_none());
+        assert!(hit(&torus, vec3(1.0, 5.0, 0.5), vec3(0.0, -1.0, 0.0)).is_none());
+        assert!(hit(&torus, vec3(-5.0, 0.6, 0.0), vec3(1.0, 0.0, 0.0)).is_none());
+        assert!(hit(&torus, vec3(-5.0, 0.0, 2.6), vec3(1.0, 0.0, 0.0)).is_none());
+        assert!(hit(&torus, vec3(5.0, 0.0, 0.0), vec3(1.0, 0.0, 0.0)).is_none());
+    }
+}
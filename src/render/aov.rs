@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+use glm::{Vec3, vec3};
+use sfml::graphics::Image;
+use crate::math::vColor::VColor;
+use crate::model::hitRecord::HitRecord;
+use crate::render::debugIntegrators::idColor;
+
+/* Layers a render can be saved as. `beauty` is the image itself, the next five split its light by where it comes
+   from, the rest describe what the camera rays hit first */
+pub const AOV_NAMES: [&str; 11] = ["beauty", "direct", "indirect", "diffuse", "specular", "emission", "albedo", "normal", "depth", "objectid", "materialid"];
+
+const LIGHT_LAYER_NAMES: [&str; 5] = ["direct", "indirect", "diffuse", "specular", "emission"];
+
+/* Light of a camera sample split by where it comes from. Direct is the light that bounces once on its way to the
+   camera, indirect the light that bounces more often, diffuse and specular tell apart the lobe of the first bounce.
+   Direct, indirect and emission add up to the sample, so do diffuse, specular and emission */
+pub struct LightLayers {
+    pub direct: Vec3,
+    pub indirect: Vec3,
+    pub diffuse: Vec3,
+    pub specular: Vec3,
+    /* Light the camera sees straight from emitters and the environment */
+    pub emission: Vec3,
+}
+
+impl LightLayers {
+    pub fn scaled(&self, weight: &Vec3) -> LightLayers {
+        return LightLayers {
+            direct: self.direct * *weight,
+            indirect: self.indirect * *weight,
+            diffuse: self.diffuse * *weight,
+            specular: self.specular * *weight,
+            emission: self.emission * *weight,
+        };
+    }
+
+    fn asArray(&self) -> [Vec3; 5] {
+        return [self.direct, self.indirect, self.diffuse, self.specular, self.emission];
+    }
+}
+
+impl Default for LightLayers {
+    fn default() -> Self {
+        return Self {
+            direct: vec3(0.0, 0.0, 0.0),
+            indirect: vec3(0.0, 0.0, 0.0),
+            diffuse: vec3(0.0, 0.0, 0.0),
+            specular: vec3(0.0, 0.0, 0.0),
+            emission: vec3(0.0, 0.0, 0.0),
+        };
+    }
+}
+
+/* A layer ready to be saved, `channels.len()` values per pixel row by row */
+pub struct AovImage {
+    pub name: String,
+    pub channels: Vec<String>,
+    pub pixels: Vec<f32>,
+}
+
+impl AovImage {
+    /* The beauty layer gets the plain channel names that viewers show by default */
+    pub fn new_colors(name: &str, colors: &[Vec3]) -> Self {
+        let channels: Vec<String> = match name {
+            "beauty" => vec!["R".to_string(), "G".to_string(), "B".to_string()],
+            "normal" => vec!["normal.X".to_string(), "normal.Y".to_string(), "normal.Z".to_string()],
+            _ => vec![format!("{}.R", name), format!("{}.G", name), format!("{}.B", name)],
+        };
+        return Self {
+            name: name.to_string(),
+            channels,
+            pixels: colors.iter().flat_map(|color| [color.x, color.y, color.z]).collect(),
+        };
+    }
+
+    pub fn new_values(name: &str, channel: &str, values: &[f32]) -> Self {
+        return Self {
+            name: name.to_string(),
+            channels: vec![format!("{}.{}", name, channel)],
+            pixels: values.to_vec(),
+        };
+    }
+
+    /* 8 bit picture of the layer: light clamped to [0, 1], normals mapped from [-1, 1], depth white up close fading
+       to black at the farthest hit, ids as the colors of the objectid integrator. Misses are black */
+    pub fn toDisplayImage(&self, width: usize, height: usize) -> Image {
+        let mut image: Image = Image::new(width as u32, height as u32).unwrap();
+        let maxDepth: f32 = self.pixels.iter().fold(0.0, |a: f32, b: &f32| f32::max(a, *b));
+
+        for y in 0..height {
+            for x in 0..width {
+                let index: usize = y * width + x;
+                let color: VColor = match self.name.as_str() {
+                    "normal" => {
+                        let normal: Vec3 = vec3(self.pixels[index * 3], self.pixels[index * 3 + 1], self.pixels[index * 3 + 2]);
+                        if normal.x == 0.0 && normal.y == 0.0 && normal.z == 0.0 {
+                            VColor::new_rgb(0.0, 0.0, 0.0)
+                        } else {
+                            VColor::new_rgb(normal.x * 0.5 + 0.5, normal.y * 0.5 + 0.5, normal.z * 0.5 + 0.5)
+                        }
+                    }
+                    "depth" if self.pixels[index] > 0.0 => VColor::new_sc(1.0 - self.pixels[index] / maxDepth),
+                    "depth" => VColor::new_sc(0.0),
+                    "objectid" | "materialid" if self.pixels[index] > 0.0 => idColor(self.pixels[index] as usize - 1),
+                    "objectid" | "materialid" => VColor::new_sc(0.0),
+                    _ => VColor::new_rgb(self.pixels[index * 3], self.pixels[index * 3 + 1], self.pixels[index * 3 + 2]),
+                };
+                image.set_pixel(x as u32, y as u32, &color.asSFColor());
+            }
+        }
+        return image;
+    }
+}
+
+/* Per pixel sums of the light layers of the camera samples, and the object and material that the pixel's first
+   sample hit. Ids start at one, zero is left for rays that hit nothing */
+pub struct AovBuffers {
+    width: usize,
+    height: usize,
+    lightSums: Vec<[Vec3; 5]>,
+    sampleCounts: Vec<u32>,
+    objectIds: Vec<u32>,
+    materialIds: Vec<u32>,
+    hitsRecorded: Vec<bool>,
+    /* Materials are numbered in the order the render meets them, keyed by their address */
+    materialNumbers: HashMap<usize, u32>,
+}
+
+impl AovBuffers {
+    pub fn new(width: usize, height: usize) -> Self {
+        return Self {
+            width,
+            height,
+            lightSums: vec![[vec3(0.0, 0.0, 0.0); 5]; width * height],
+            sampleCounts: vec![0; width * height],
+            objectIds: vec![0; width * height],
+            materialIds: vec![0; width * height],
+            hitsRecorded: vec![false; width * height],
+            materialNumbers: HashMap::new(),
+        };
+    }
+
+    pub fn addSample(&mut self, x: usize, y: usize, layers: &LightLayers) {
+        let index: usize = y * self.width + x;
+        let values: [Vec3; 5] = layers.asArray();
+        for (sum, value) in self.lightSums[index].iter_mut().zip(values.iter()) {
+            *sum = *sum + *value;
+        }
+        self.sampleCounts[index] += 1;
+    }
+
+    /* Only the first call per pixel counts, the ids of different objects do not average */
+    pub fn addFirstHit(&mut self, x: usize, y: usize, hitRecord: Option<&HitRecord>) {
+        let index: usize = y * self.width + x;
+        if self.hitsRecorded[index] {
+            return;
+        }
+        self.hitsRecorded[index] = true;
+
+        if let Some(hitRecord) = hitRecord {
+            let nextNumber: u32 = self.materialNumbers.len() as u32 + 1;
+            let address: usize = Rc::as_ptr(&hitRecord.material) as *const () as usize;
+            self.objectIds[index] = hitRecord.objectId as u32 + 1;
+            self.materialIds[index] = *self.materialNumbers.entry(address).or_insert(nextNumber);
+        }
+    }
+
+    /* One of the light or id layers, none for the layers kept elsewhere */
+    pub fn layer(&self, name: &str) -> Option<AovImage> {
+        if let Some(layer) = LIGHT_LAYER_NAMES.iter().position(|n| *n == name) {
+            let colors: Vec<Vec3> = (0..self.width * self.height)
+                .map(|index| self.lightSums[index][layer] / f32::max(self.sampleCounts[index] as f32, 1.0))
+                .collect();
+            return Some(AovImage::new_colors(name, &colors));
+        }
+        let ids: &Vec<u32> = match name {
+            "objectid" => &self.objectIds,
+            "materialid" => &self.materialIds,
+            _ => return None,
+        };
+        let values: Vec<f32> = ids.iter().map(|id| *id as f32).collect();
+        return Some(AovImage::new_values(name, "id", &values));
+    }
+}
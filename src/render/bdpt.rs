@@ -0,0 +1,590 @@
+use glm::{dot, length, normalize, Vec3, vec3, vec4};
+use crate::math::mathUtils::random01;
+use crate::math::ray::Ray;
+use crate::math::spectrum::wavelengthToRGB;
+use crate::math::vColor::VColor;
+use crate::math::warp::cosineHemispherePdf;
+use crate::model::hitRecord::HitRecord;
+use crate::render::film::Film;
+use crate::render::integrator::Integrator;
+use crate::render::light::{EmissionSample, infiniteEmissionPdf, LightSample};
+use crate::render::scene::Scene;
+
+/* Bidirectional path tracer after Veach's thesis and pbrt: a path from the camera and one from a light are joined in
+   every possible way, and each of these strategies is weighted by the power heuristic against all the others that
+   could have built the same path. Paths that only a connection to the camera can find are splatted onto the film */
+pub struct BdptIntegrator {}
+
+impl BdptIntegrator {
+    pub fn new() -> Self {
+        return Self {};
+    }
+}
+
+enum VertexKind {
+    Camera,
+    /* Start of a light path, or a light sampled to connect to */
+    Light,
+    Surface,
+    /* Light arriving from infinity, like the sky. Paths from the camera that leave the scene end in one */
+    Infinite,
+}
+
+struct Vertex {
+    kind: VertexKind,
+    point: Vec3,
+    /* Geometric normal, zero for points without a surface */
+    normal: Vec3,
+    hit: Option<HitRecord>,
+    /* Ray the vertex was reached with, the material is evaluated against it */
+    incoming: Ray,
+    /* Throughput of the subpath up to and including this vertex */
+    beta: Vec3,
+    /* Set when the vertex scattered from a mirror-like lobe, it cannot be connected to */
+    delta: bool,
+    /* Area densities of reaching the vertex from its own subpath and from the other side */
+    pdfFwd: f32,
+    pdfRev: f32,
+    /* Scene light the vertex sits on */
+    light: Option<usize>,
+}
+
+impl Vertex {
+    fn new_camera(point: Vec3, beta: Vec3) -> Self {
+        return Self {
+            kind: VertexKind::Camera,
+            point,
+            beta,
+            ..Default::default()
+        };
+    }
+
+    fn new_light(light: usize, infinite: bool, point: Vec3, normal: Vec3, beta: Vec3, pdfFwd: f32) -> Self {
+        return Self {
+            kind: if infinite { VertexKind::Infinite } else { VertexKind::Light },
+            point,
+            normal,
+            beta,
+            pdfFwd,
+            light: Some(light),
+            ..Default::default()
+        };
+    }
+
+    fn new_surface(hitRecord: HitRecord, incoming: &Ray, beta: Vec3, light: Option<usize>) -> Self {
+        return Self {
+            kind: VertexKind::Surface,
+            point: hitRecord.point,
+            normal: hitRecord.normal,
+            hit: Some(hitRecord),
+            incoming: incoming.clone(),
+            beta,
+            light,
+            ..Default::default()
+        };
+    }
+
+    /* End of a camera path that left the scene, placed beyond everything along the ray */
+    fn new_escaped(ray: &Ray, beta: Vec3, pdfFwd: f32, sceneRadius: f32) -> Self {
+        return Self {
+            kind: VertexKind::Infinite,
+            point: ray.origin + normalize(ray.direction) * (2.0 * sceneRadius),
+            incoming: ray.clone(),
+            beta,
+            pdfFwd,
+            ..Default::default()
+        };
+    }
+
+    fn isInfinite(&self) -> bool {
+        return matches!(self.kind, VertexKind::Infinite);
+    }
+
+    fn isOnSurface(&self) -> bool {
+        return match self.kind {
+            VertexKind::Surface => true,
+            VertexKind::Light => self.normal.x != 0.0 || self.normal.y != 0.0 || self.normal.z != 0.0,
+            _ => false,
+        };
+    }
+
+    fn isDeltaLight(&self, scene: &Scene) -> bool {
+        return match self.kind {
+            VertexKind::Light | VertexKind::Infinite => match self.light {
+                Some(index) => scene.getLights()[index].isDeltaPosition() || scene.getLights()[index].isDeltaDirection(),
+                None => false,
+            },
+            _ => false,
+        };
+    }
+
+    fn isConnectible(&self, scene: &Scene) -> bool {
+        return match self.kind {
+            VertexKind::Camera => true,
+            VertexKind::Surface => !self.delta,
+            VertexKind::Light | VertexKind::Infinite => match self.light {
+                Some(index) => !scene.getLights()[index].isDeltaDirection(),
+                None => true,
+            },
+        };
+    }
+
+    /* Material value towards `next` with the cosine at this vertex, only surfaces scatter */
+    fn f(&self, next: &Vertex) -> Vec3 {
+        return match &self.hit {
+            Some(hitRecord) => hitRecord.material.eval(&self.incoming, hitRecord, &normalize(next.point - self.point)).getColorRGB(),
+            None => vec3(0.0, 0.0, 0.0),
+        };
+    }
+
+    /* Turns a density over directions leaving this vertex into one over the area around `next` */
+    fn convertDensity(&self, pdf: f32, next: &Vertex) -> f32 {
+        if next.isInfinite() {
+            return pdf;
+        }
+        let toNext: Vec3 = next.point - self.point;
+        let distanceSquared: f32 = dot(toNext, toNext);
+        if distanceSquared <= 0.0 {
+            return 0.0;
+        }
+        let mut density: f32 = pdf / distanceSquared;
+        if next.isOnSurface() {
+            density *= f32::abs(dot(next.normal, toNext / distanceSquared.sqrt()));
+        }
+        return density;
+    }
+
+    /* Area density of sampling `next` from this vertex, having arrived from `prev` */
+    fn pdf(&self, scene: &Scene, prev: Option<&Vertex>, next: &Vertex) -> f32 {
+        let toNext: Vec3 = normalize(next.point - self.point);
+        let pdf: f32 = match self.kind {
+            VertexKind::Light | VertexKind::Infinite => return self.pdfLight(scene, next),
+            VertexKind::Camera => scene.getCamera().pdfEmission(&toNext).1,
+            VertexKind::Surface => match (&self.hit, prev) {
+                (Some(hitRecord), Some(prev)) => scatterPdf(hitRecord, &Ray::new(prev.point, self.point - prev.point), &toNext),
+                _ => 0.0,
+            },
+        };
+        return self.convertDensity(pdf, next);
+    }
+
+    /* Area density of a light path starting at this vertex reaching `next` */
+    fn pdfLight(&self, scene: &Scene, next: &Vertex) -> f32 {
+        let toNext: Vec3 = next.point - self.point;
+        let distanceSquared: f32 = dot(toNext, toNext);
+        if distanceSquared <= 0.0 {
+            return 0.0;
+        }
+        let direction: Vec3 = toNext / distanceSquared.sqrt();
+
+        let mut pdf: f32 = if self.isInfinite() {
+            // Light from infinity starts on a disk as wide as the scene's bounds, unbounded objects stick out of it
+            let offset: Vec3 = next.point - scene.getSceneCenter();
+            let along: f32 = dot(offset, direction);
+            if dot(offset, offset) - along * along > scene.getSceneRadius() * scene.getSceneRadius() {
+                return 0.0;
+            }
+            infiniteEmissionPdf(scene.getSceneRadius())
+        } else {
+            match self.light {
+                Some(index) => scene.getLights()[index].emissionPdf(&self.point, &self.normal, &direction, scene.getSceneRadius()).1 / distanceSquared,
+                None => return 0.0,
+            }
+        };
+
+        if next.isOnSurface() {
+            pdf *= f32::abs(dot(next.normal, direction));
+        }
+        return pdf;
+    }
+
+    /* Density of a light path starting at this vertex, towards `next`, the choice of the light included */
+    fn pdfLightOrigin(&self, scene: &Scene, next: &Vertex) -> f32 {
+        let direction: Vec3 = normalize(next.point - self.point);
+        if self.isInfinite() {
+            return infiniteLightDensity(scene, &direction);
+        }
+
+        return match self.light {
+            Some(index) => {
+                let pmf: f32 = scene.getEmissionSampler().pmf(&scene.getSceneCenter(), index);
+                let (pdfPosition, _) = scene.getLights()[index].emissionPdf(&self.point, &self.normal, &direction, scene.getSceneRadius());
+                pdfPosition * pmf
+            }
+            None => 0.0,
+        };
+    }
+}
+
+impl Default for Vertex {
+    fn default() -> Self {
+        return Self {
+            kind: VertexKind::Surface,
+            point: vec3(0.0, 0.0, 0.0),
+            normal: vec3(0.0, 0.0, 0.0),
+            hit: None,
+            incoming: Ray::default(),
+            beta: vec3(0.0, 0.0, 0.0),
+            delta: false,
+            pdfFwd: 0.0,
+            pdfRev: 0.0,
+            light: None,
+        };
+    }
+}
+
+/* Unweighted contribution of a connection, the vertex sampled in place of a subpath end and the film coordinates
+   when that vertex is the camera */
+type Connection = (Vec3, Option<Vertex>, Option<(f32, f32)>);
+
+fn isBlack(value: &Vec3) -> bool {
+    return value.x <= 0.0 && value.y <= 0.0 && value.z <= 0.0;
+}
+
+/* Density of the material scattering along `direction`. Materials that cannot tell their density are taken as
+   diffuse, which only makes the weights less ideal */
+fn scatterPdf(hitRecord: &HitRecord, ray: &Ray, direction: &Vec3) -> f32 {
+    let pdf: f32 = hitRecord.material.pdf(ray, hitRecord, direction);
+    if pdf > 0.0 {
+        return pdf;
+    }
+    return cosineHemispherePdf(dot(hitRecord.shadingNormal, *direction));
+}
+
+/* Density over directions of all lights at infinity sending a light path along `direction`, the travel direction */
+fn infiniteLightDensity(scene: &Scene, direction: &Vec3) -> f32 {
+    let origin: Vec3 = vec3(0.0, 0.0, 0.0);
+    let mut pdf: f32 = 0.0;
+    for (index, light) in scene.getLights().iter().enumerate() {
+        if light.bounds().is_none() {
+            let (_, pdfDirection) = light.emissionPdf(&origin, &origin, direction, scene.getSceneRadius());
+            pdf += pdfDirection * scene.getEmissionSampler().pmf(&scene.getSceneCenter(), index);
+        }
+    }
+    return pdf;
+}
+
+/* Extends `path` by following `ray` through the scene, `pdf` being the density the ray was picked with.
+   Camera paths that leave the scene end with an infinite vertex */
+fn randomWalk(scene: &Scene, ray: Ray, beta: Vec3, pdf: f32, maxVertices: usize, fromCamera: bool, path: &mut Vec<Vertex>) {
+    let mut ray: Ray = ray;
+    let mut beta: Vec3 = beta;
+    let mut pdfFwd: f32 = pdf;
+    let mut count: usize = 0;
+
+    while count < maxVertices && !isBlack(&beta) {
+        let mut hitRecord: HitRecord = HitRecord::default();
+        if !scene.Hit(&ray, &mut hitRecord) {
+            if fromCamera {
+                path.push(Vertex::new_escaped(&ray, beta, pdfFwd, scene.getSceneRadius()));
+            }
+            return;
+        }
+
+        let light: Option<usize> = scene.getLightOfObject(hitRecord.objectId);
+        let mut vertex: Vertex = Vertex::new_surface(hitRecord, &ray, beta, light);
+        vertex.pdfFwd = path[path.len() - 1].convertDensity(pdfFwd, &vertex);
+        path.push(vertex);
+        count += 1;
+        if count >= maxVertices {
+            return;
+        }
+
+        let current: usize = path.len() - 1;
+        let mut scattered: Ray = Ray::default();
+        let mut attenuation: VColor = VColor::default();
+        let pdfRev: f32;
+        let specular: bool;
+        {
+            let hitRecord: &HitRecord = path[current].hit.as_ref().unwrap();
+            if !hitRecord.material.scatter(&ray, hitRecord, &mut attenuation, &mut scattered) {
+                return;
+            }
+            scattered.wavelength = ray.wavelength;
+
+            let direction: Vec3 = normalize(scattered.direction);
+            specular = hitRecord.material.isSpecular(&ray, hitRecord, &scattered);
+            if specular {
+                pdfFwd = 0.0;
+                pdfRev = 0.0;
+            } else {
+                pdfFwd = scatterPdf(hitRecord, &ray, &direction);
+                pdfRev = scatterPdf(hitRecord, &Ray::new(hitRecord.point + direction, direction * -1.0), &(normalize(ray.direction) * -1.0));
+                if pdfFwd <= 0.0 {
+                    return;
+                }
+            }
+        }
+
+        path[current].delta = specular;
+        beta = beta * attenuation.getColorRGB();
+        path[current - 1].pdfRev = path[current].convertDensity(pdfRev, &path[current - 1]);
+        ray = scattered;
+    }
+}
+
+impl BdptIntegrator {
+    fn cameraSubpath(scene: &Scene, ray: &Ray, maxVertices: usize) -> Vec<Vertex> {
+        let mut path: Vec<Vertex> = Vec::with_capacity(maxVertices);
+        let (_, pdfDirection) = scene.getCamera().pdfEmission(&ray.direction);
+        path.push(Vertex::new_camera(ray.origin, vec3(1.0, 1.0, 1.0)));
+        randomWalk(scene, ray.clone(), vec3(1.0, 1.0, 1.0), pdfDirection, maxVertices - 1, true, &mut path);
+        return path;
+    }
+
+    fn lightSubpath(scene: &Scene, wavelength: f32, maxVertices: usize) -> Vec<Vertex> {
+        let mut path: Vec<Vertex> = Vec::with_capacity(maxVertices);
+        let sceneCenter: Vec3 = scene.getSceneCenter();
+        let sceneRadius: f32 = scene.getSceneRadius();
+
+        let (index, pmf) = match scene.getEmissionSampler().sample(&sceneCenter, random01()) {
+            Some(choice) => choice,
+            None => return path,
+        };
+        let light = &scene.getLights()[index];
+        let mut sample: EmissionSample = EmissionSample::default();
+        if pmf <= 0.0 || !light.sampleEmission(&sceneCenter, sceneRadius, &mut sample) || sample.pdfPosition <= 0.0 || sample.pdfDirection <= 0.0 {
+            return path;
+        }
+
+        let infinite: bool = light.bounds().is_none();
+        let direction: Vec3 = normalize(sample.ray.direction);
+        let hasNormal: bool = sample.normal.x != 0.0 || sample.normal.y != 0.0 || sample.normal.z != 0.0;
+        let cosTheta: f32 = if hasNormal { f32::abs(dot(sample.normal, direction)) } else { 1.0 };
+        let beta: Vec3 = sample.radiance * (cosTheta / (pmf * sample.pdfPosition * sample.pdfDirection));
+
+        path.push(Vertex::new_light(index, infinite, sample.ray.origin, sample.normal, sample.radiance, sample.pdfPosition * pmf));
+        randomWalk(scene, Ray::new_spectral(sample.ray.origin, direction, wavelength), beta, sample.pdfDirection, maxVertices - 1, false, &mut path);
+
+        // Lights at infinity pick a point on the scene's cross section, which sets the density of the first hit
+        if infinite {
+            if path.len() > 1 {
+                path[1].pdfFwd = sample.pdfPosition;
+                if path[1].isOnSurface() {
+                    path[1].pdfFwd *= f32::abs(dot(direction, path[1].normal));
+                }
+            }
+            path[0].pdfFwd = infiniteLightDensity(scene, &direction);
+        }
+        return path;
+    }
+
+    /* Joins the first `s` vertices of the light path with the first `t` of the camera path */
+    fn connect(scene: &Scene, lightPath: &[Vertex], cameraPath: &[Vertex], s: usize, t: usize) -> Option<Connection> {
+        if t > 1 && s != 0 && cameraPath[t - 1].isInfinite() {
+            return None;
+        }
+
+        if s == 0 {
+            // The camera path found a light by itself
+            let pt: &Vertex = &cameraPath[t - 1];
+            let emitted: Vec3 = BdptIntegrator::emitted(scene, pt, true);
+            if isBlack(&emitted) {
+                return None;
+            }
+            return Some((pt.beta * emitted, None, None));
+        }
+
+        if t == 1 {
+            // Straight from a light path vertex to the lens
+            let qs: &Vertex = &lightPath[s - 1];
+            if !qs.isConnectible(scene) {
+                return None;
+            }
+            let (direction, weight, pdf, lensPoint, u, v) = scene.getCamera().sampleImportance(&qs.point)?;
+            if pdf <= 0.0 || weight <= 0.0 {
+                return None;
+            }
+
+            let camera: Vertex = Vertex::new_camera(lensPoint, vec3(weight, weight, weight));
+            let value: Vec3 = qs.beta * qs.f(&camera) * camera.beta;
+            if isBlack(&value) || !scene.isVisible(&qs.point, &direction, length(lensPoint - qs.point)) {
+                return None;
+            }
+            return Some((value, Some(camera), Some((u, v))));
+        }
+
+        if s == 1 {
+            // A light sampled from the camera path's end, like the path tracer's direct lighting
+            let pt: &Vertex = &cameraPath[t - 1];
+            if !pt.isConnectible(scene) {
+                return None;
+            }
+            let (index, pmf) = scene.getEmissionSampler().sample(&scene.getSceneCenter(), random01())?;
+            let light = &scene.getLights()[index];
+            let mut sample: LightSample = LightSample::default();
+            if pmf <= 0.0 || !light.illuminate(&pt.point, &mut sample) {
+                return None;
+            }
+
+            let infinite: bool = light.bounds().is_none();
+            let distance: f32 = if infinite { 2.0 * scene.getSceneRadius() } else { sample.distance };
+            let mut lightVertex: Vertex = Vertex::new_light(index, infinite, pt.point + sample.direction * distance, sample.normal,
+                                                            sample.radiance.getColorRGB() / pmf, 0.0);
+            lightVertex.pdfFwd = lightVertex.pdfLightOrigin(scene, pt);
+
+            let value: Vec3 = pt.beta * pt.f(&lightVertex) * lightVertex.beta;
+            if isBlack(&value) || !scene.isVisible(&pt.point, &sample.direction, sample.distance) {
+                return None;
+            }
+            return Some((value, Some(lightVertex), None));
+        }
+
+        let qs: &Vertex = &lightPath[s - 1];
+        let pt: &Vertex = &cameraPath[t - 1];
+        if !qs.isConnectible(scene) || !pt.isConnectible(scene) {
+            return None;
+        }
+
+        // Both material values carry the cosine at their end of the connection, leaving one over the squared distance
+        let toCamera: Vec3 = pt.point - qs.point;
+        let distance: f32 = length(toCamera);
+        let value: Vec3 = qs.beta * qs.f(pt) * pt.f(qs) * pt.beta / (distance * distance);
+        if isBlack(&value) || !scene.isVisible(&qs.point, &(toCamera / distance), distance) {
+            return None;
+        }
+        return Some((value, None, None));
+    }
+
+    /* Light given off by a camera path's last vertex towards the vertex before it. `sampled` picks the part the
+       lights also sample, otherwise the part only paths from the camera find */
+    fn emitted(scene: &Scene, vertex: &Vertex, sampled: bool) -> Vec3 {
+        return match vertex.kind {
+            VertexKind::Infinite => {
+                let environment = scene.getEnvironment();
+                let all: Vec3 = environment.radiance(&vertex.incoming.direction, true).getColorRGB();
+                let unsampled: Vec3 = environment.radiance(&vertex.incoming.direction, false).getColorRGB();
+                if sampled { all - unsampled } else { unsampled }
+            }
+            VertexKind::Surface => match &vertex.hit {
+                Some(hitRecord) => {
+                    let isSampled: bool = vertex.light.is_some();
+                    if isSampled != sampled {
+                        return vec3(0.0, 0.0, 0.0);
+                    }
+                    hitRecord.material.emitted(&vertex.incoming, hitRecord).getColorRGB()
+                }
+                None => vec3(0.0, 0.0, 0.0),
+            },
+            _ => vec3(0.0, 0.0, 0.0),
+        };
+    }
+
+    /* Power heuristic weight of strategy (s, t) against every other way of sampling the same path, from the ratios
+       of the densities of sampling each vertex from either side */
+    fn misWeight(scene: &Scene, lightPath: &[Vertex], cameraPath: &[Vertex], sampled: Option<&Vertex>, s: usize, t: usize) -> f32 {
+        if s + t == 2 {
+            return 1.0;
+        }
+
+        let qs: Option<&Vertex> = if s == 0 { None } else if s == 1 { sampled.or(lightPath.first()) } else { Some(&lightPath[s - 1]) };
+        let pt: &Vertex = if t == 1 { sampled.unwrap_or(&cameraPath[0]) } else { &cameraPath[t - 1] };
+        let qsMinus: Option<&Vertex> = if s > 1 { Some(&lightPath[s - 2]) } else { None };
+        let ptMinus: Option<&Vertex> = if t > 1 { Some(&cameraPath[t - 2]) } else { None };
+
+        // Forward density, reverse density and delta flag of each vertex, adjusted to this strategy
+        let mut cameraPdfs: Vec<(f32, f32, bool)> = cameraPath[..t].iter().map(|v| (v.pdfFwd, v.pdfRev, v.delta)).collect();
+        let mut lightPdfs: Vec<(f32, f32, bool)> = lightPath[..s].iter().map(|v| (v.pdfFwd, v.pdfRev, v.delta)).collect();
+        if let (1, Some(qs)) = (s, qs) {
+            lightPdfs[0] = (qs.pdfFwd, qs.pdfRev, false);
+        }
+
+        // The connected vertices are never degenerate
+        cameraPdfs[t - 1].2 = false;
+        if s > 0 {
+            lightPdfs[s - 1].2 = false;
+        }
+
+        cameraPdfs[t - 1].1 = match (qs, ptMinus) {
+            (Some(qs), _) => qs.pdf(scene, qsMinus, pt),
+            (None, Some(ptMinus)) => pt.pdfLightOrigin(scene, ptMinus),
+            (None, None) => 0.0,
+        };
+        if let Some(ptMinus) = ptMinus {
+            cameraPdfs[t - 2].1 = match qs {
+                Some(qs) => pt.pdf(scene, Some(qs), ptMinus),
+                None => pt.pdfLight(scene, ptMinus),
+            };
+        }
+        if let Some(qs) = qs {
+            lightPdfs[s - 1].1 = pt.pdf(scene, ptMinus, qs);
+            if let Some(qsMinus) = qsMinus {
+                lightPdfs[s - 2].1 = qs.pdf(scene, Some(pt), qsMinus);
+            }
+        }
+
+        // Next to a mirror-like vertex a density of zero stands for its delta distribution, which cancels out. Anywhere
+        // else it means the other side cannot sample the vertex at all, and neither the strategies beyond it
+        let ratio = |pdfRev: f32, pdfFwd: f32, nextDelta: bool| -> f32 {
+            let numerator: f32 = if pdfRev == 0.0 && nextDelta { 1.0 } else { pdfRev };
+            let denominator: f32 = if pdfFwd != 0.0 { pdfFwd } else { 1.0 };
+            return numerator / denominator;
+        };
+
+        let mut sumRi: f32 = 0.0;
+        let mut ri: f32 = 1.0;
+        for i in (1..t).rev() {
+            ri *= ratio(cameraPdfs[i].1, cameraPdfs[i].0, i + 1 < t && cameraPdfs[i + 1].2);
+            if !cameraPdfs[i].2 && !cameraPdfs[i - 1].2 {
+                sumRi += ri * ri;
+            }
+        }
+
+        ri = 1.0;
+        let deltaLight: bool = match qs {
+            Some(qs) if s == 1 => qs.isDeltaLight(scene),
+            _ => lightPath.first().map(|v| v.isDeltaLight(scene)).unwrap_or(false),
+        };
+        for i in (0..s).rev() {
+            ri *= ratio(lightPdfs[i].1, lightPdfs[i].0, i + 1 < s && lightPdfs[i + 1].2);
+            let previousDelta: bool = if i > 0 { lightPdfs[i - 1].2 } else { deltaLight };
+            if !lightPdfs[i].2 && !previousDelta {
+                sumRi += ri * ri;
+            }
+        }
+
+        return 1.0 / (1.0 + sumRi);
+    }
+}
+
+impl Integrator for BdptIntegrator {
+    fn name(&self) -> &'static str {
+        return "bdpt";
+    }
+
+    fn radiance(&self, scene: &Scene, ray: &Ray, maxDepth: i16, film: &mut Film) -> VColor {
+        let maxDepth: usize = usize::max(maxDepth as usize, 1);
+        let cameraPath: Vec<Vertex> = BdptIntegrator::cameraSubpath(scene, ray, maxDepth + 2);
+        let lightPath: Vec<Vertex> = BdptIntegrator::lightSubpath(scene, ray.wavelength, maxDepth + 1);
+
+        // Splats skip the renderer's conversion of the wavelength
+        let spectralWeight: Vec3 = if ray.wavelength > 0.0 { wavelengthToRGB(ray.wavelength) } else { vec3(1.0, 1.0, 1.0) };
+
+        let mut radiance: Vec3 = vec3(0.0, 0.0, 0.0);
+        for t in 1..=cameraPath.len() {
+            // Emitters only the camera path can find take no part in the weighting
+            if t > 1 {
+                radiance = radiance + cameraPath[t - 1].beta * BdptIntegrator::emitted(scene, &cameraPath[t - 1], false);
+            }
+
+            for s in 0..=lightPath.len() {
+                if (s == 1 && t == 1) || s + t < 2 || s + t - 2 > maxDepth {
+                    continue;
+                }
+
+                let (value, sampled, filmPosition) = match BdptIntegrator::connect(scene, &lightPath, &cameraPath, s, t) {
+                    Some(connection) => connection,
+                    None => continue,
+                };
+                let weighted: Vec3 = value * BdptIntegrator::misWeight(scene, &lightPath, &cameraPath, sampled.as_ref(), s, t);
+
+                match filmPosition {
+                    Some((u, v)) => film.addSplat(u, v, &(weighted * spectralWeight)),
+                    None => radiance = radiance + weighted,
+                }
+            }
+        }
+
+        return VColor::new_vec4(&vec4(radiance.x, radiance.y, radiance.z, 1.0));
+    }
+}
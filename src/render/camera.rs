@@ -1,8 +1,9 @@
 ﻿use std::f32::consts::PI;
-use glm::{cos, cross, IVec4, ivec4, mat4, Mat4, normalize, radians, sin, tan, vec3, Vec3, Vec4, vec4};
+use glm::{cos, cross, dot, IVec4, ivec4, length, mat4, Mat4, normalize, radians, sin, tan, Vec2, vec3, Vec3, Vec4, vec4};
 use glm::ext::{look_at, perspective};
-use crate::math::mathUtils::randomInUnitDisk;
+use crate::math::mathUtils::{randomInUnitDisk, randomVec2};
 use crate::math::ray::Ray;
+use crate::math::warp::uniformDisk;
 
 pub struct Camera {
     eyePosition: Vec3,
@@ -56,7 +57,84 @@ impl Camera {
 
         return Ray::new(self.eyePosition + offset,  self.lowerLeftCorner + self.horizontal * x + self.vertical * y - self.eyePosition - offset);
     }
-    
+
+    pub fn getPosition(&self) -> Vec3 {
+        return self.eyePosition;
+    }
+
+    /* Direction the camera looks in, the normal of the lens */
+    pub fn getForward(&self) -> Vec3 {
+        return self.viewDirection * -1.0;
+    }
+
+    fn focusDistance(&self) -> f32 {
+        let center: Vec3 = self.lowerLeftCorner + self.horizontal / 2.0 + self.vertical / 2.0;
+        return dot(center - self.eyePosition, self.getForward());
+    }
+
+    /* A pinhole counts as a lens of unit area */
+    fn lensArea(&self) -> f32 {
+        if self.lensRadius <= 0.0 {
+            return 1.0;
+        }
+        return PI * self.lensRadius * self.lensRadius;
+    }
+
+    /* Area of the image seen at distance one in front of the lens */
+    fn filmArea(&self) -> f32 {
+        let focusDistance: f32 = self.focusDistance();
+        return length(self.horizontal) * length(self.vertical) / (focusDistance * focusDistance);
+    }
+
+    /* Importance of a ray leaving the lens, with the film coordinates of `createARay` it lands on.
+       None for rays that miss the image */
+    pub fn importance(&self, ray: &Ray) -> Option<(f32, f32, f32)> {
+        let direction: Vec3 = normalize(ray.direction);
+        let cosTheta: f32 = dot(direction, self.getForward());
+        if cosTheta <= 0.0 {
+            return None;
+        }
+
+        let focusPoint: Vec3 = ray.origin + direction * (self.focusDistance() / cosTheta);
+        let x: f32 = dot(focusPoint - self.lowerLeftCorner, self.horizontal) / dot(self.horizontal, self.horizontal);
+        let y: f32 = dot(focusPoint - self.lowerLeftCorner, self.vertical) / dot(self.vertical, self.vertical);
+        if !(0.0..=1.0).contains(&x) || !(0.0..=1.0).contains(&y) {
+            return None;
+        }
+
+        let cos2Theta: f32 = cosTheta * cosTheta;
+        return Some((1.0 / (self.filmArea() * self.lensArea() * cos2Theta * cos2Theta), x, y));
+    }
+
+    /* Densities of `createARay` for a ray along `direction`, over the lens area and over solid angle */
+    pub fn pdfEmission(&self, direction: &Vec3) -> (f32, f32) {
+        let cosTheta: f32 = dot(normalize(*direction), self.getForward());
+        if cosTheta <= 0.0 {
+            return (0.0, 0.0);
+        }
+        return (1.0 / self.lensArea(), 1.0 / (self.filmArea() * cosTheta * cosTheta * cosTheta));
+    }
+
+    /* Connects `point` to a random point on the lens. Gives the unit direction towards the lens, the importance
+       arriving along it divided by the density of the choice in solid angle, that density, the lens point and
+       the film coordinates */
+    pub fn sampleImportance(&self, point: &Vec3) -> Option<(Vec3, f32, f32, Vec3, f32, f32)> {
+        let disk: Vec2 = uniformDisk(&randomVec2()) * self.lensRadius;
+        let lensPoint: Vec3 = self.eyePosition + self.upVector * disk.x + self.sideVector * disk.y;
+
+        let toLens: Vec3 = lensPoint - *point;
+        let distance: f32 = length(toLens);
+        if distance <= 0.0 {
+            return None;
+        }
+        let direction: Vec3 = toLens / distance;
+
+        let (importance, x, y) = self.importance(&Ray::new(lensPoint, direction * -1.0))?;
+        let cosLens: f32 = f32::abs(dot(direction, self.getForward()));
+        let pdf: f32 = distance * distance / (cosLens * self.lensArea());
+        return Some((direction, importance / pdf, pdf, lensPoint, x, y));
+    }
+
 }
 
 impl Default for Camera {
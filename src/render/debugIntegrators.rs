@@ -0,0 +1,235 @@
+use glm::{length, normalize, Vec3, vec4};
+use crate::math::mathUtils::{isNearlyZero_Vec3, randomUnitVector3};
+use crate::math::ray::Ray;
+use crate::math::vColor::VColor;
+use crate::model::hitRecord::HitRecord;
+use crate::render::film::Film;
+use crate::render::integrator::{heatmap, Integrator};
+use crate::render::scene::Scene;
+
+/* Integrators that show one property of the first hit, for finding out what is wrong with a scene. Misses are black
+   unless noted otherwise */
+
+fn rgb(value: &Vec3) -> VColor {
+    return VColor::new_vec4(&vec4(value.x, value.y, value.z, 1.0));
+}
+
+/* Shading normal, each component mapped from [-1, 1] to [0, 1] */
+pub struct NormalIntegrator {}
+
+impl NormalIntegrator {
+    pub fn new() -> Self {
+        return Self {};
+    }
+}
+
+impl Integrator for NormalIntegrator {
+    fn name(&self) -> &'static str {
+        return "normals";
+    }
+
+    fn radiance(&self, scene: &Scene, ray: &Ray, _maxDepth: i16, _film: &mut Film) -> VColor {
+        let mut hitRecord: HitRecord = HitRecord::default();
+        if !scene.Hit(ray, &mut hitRecord) {
+            return VColor::default();
+        }
+        return rgb(&(hitRecord.shadingNormal * 0.5 + 0.5));
+    }
+}
+
+/* Distance to the first hit, white up close fading to black at `maxDistance` */
+pub struct DepthIntegrator {
+    maxDistance: f32,
+}
+
+impl DepthIntegrator {
+    pub fn new(maxDistance: f32) -> Self {
+        return Self { maxDistance };
+    }
+}
+
+impl Integrator for DepthIntegrator {
+    fn name(&self) -> &'static str {
+        return "depth";
+    }
+
+    fn radiance(&self, scene: &Scene, ray: &Ray, _maxDepth: i16, _film: &mut Film) -> VColor {
+        let mut hitRecord: HitRecord = HitRecord::default();
+        if !scene.Hit(ray, &mut hitRecord) {
+            return VColor::default();
+        }
+        let distance: f32 = hitRecord.t * length(ray.direction);
+        return VColor::new_sc((1.0 - distance / self.maxDistance).clamp(0.0, 1.0));
+    }
+}
+
+/* Color a material gives to light in one bounce plus what it emits, the environment where nothing is hit */
+pub struct AlbedoIntegrator {}
+
+impl AlbedoIntegrator {
+    pub fn new() -> Self {
+        return Self {};
+    }
+
+    /* What the integrator shows for a ray that hit something */
+    pub fn surfaceAlbedo(ray: &Ray, hitRecord: &HitRecord) -> Vec3 {
+        let mut attenuation: VColor = VColor::default();
+        let mut scattered: Ray = Ray::default();
+        let mut value: Vec3 = hitRecord.material.emitted(ray, hitRecord).getColorRGB();
+        if hitRecord.material.scatter(ray, hitRecord, &mut attenuation, &mut scattered) {
+            value = value + attenuation.getColorRGB();
+        }
+        return value;
+    }
+}
+
+impl Integrator for AlbedoIntegrator {
+    fn name(&self) -> &'static str {
+        return "albedo";
+    }
+
+    fn radiance(&self, scene: &Scene, ray: &Ray, _maxDepth: i16, _film: &mut Film) -> VColor {
+        let mut hitRecord: HitRecord = HitRecord::default();
+        if !scene.Hit(ray, &mut hitRecord) {
+            return scene.getEnvironment().radiance(&ray.direction, true);
+        }
+        return rgb(&AlbedoIntegrator::surfaceAlbedo(ray, &hitRecord));
+    }
+}
+
+/* Surface coordinates as red and green */
+pub struct UvIntegrator {}
+
+impl UvIntegrator {
+    pub fn new() -> Self {
+        return Self {};
+    }
+}
+
+impl Integrator for UvIntegrator {
+    fn name(&self) -> &'static str {
+        return "uv";
+    }
+
+    fn radiance(&self, scene: &Scene, ray: &Ray, _maxDepth: i16, _film: &mut Film) -> VColor {
+        let mut hitRecord: HitRecord = HitRecord::default();
+        if !scene.Hit(ray, &mut hitRecord) {
+            return VColor::default();
+        }
+        return VColor::new_rgb(hitRecord.uv.x.fract(), hitRecord.uv.y.fract(), 0.0);
+    }
+}
+
+/* A random looking but fixed color for every scene object */
+pub struct ObjectIdIntegrator {}
+
+impl ObjectIdIntegrator {
+    pub fn new() -> Self {
+        return Self {};
+    }
+}
+
+impl Integrator for ObjectIdIntegrator {
+    fn name(&self) -> &'static str {
+        return "objectid";
+    }
+
+    fn radiance(&self, scene: &Scene, ray: &Ray, _maxDepth: i16, _film: &mut Film) -> VColor {
+        let mut hitRecord: HitRecord = HitRecord::default();
+        if !scene.Hit(ray, &mut hitRecord) {
+            return VColor::default();
+        }
+
+        return idColor(hitRecord.objectId);
+    }
+}
+
+/* A random looking but fixed color for an id */
+pub fn idColor(id: usize) -> VColor {
+    // Integer hash, so that neighbouring ids end up far apart
+    let mut hash: u32 = (id as u32).wrapping_add(1);
+    hash = (hash ^ (hash >> 16)).wrapping_mul(0x45d9f3b);
+    hash = (hash ^ (hash >> 16)).wrapping_mul(0x45d9f3b);
+    hash ^= hash >> 16;
+
+    return VColor::new_rgb((hash & 0xff) as f32 / 255.0, ((hash >> 8) & 0xff) as f32 / 255.0, ((hash >> 16) & 0xff) as f32 / 255.0);
+}
+
+/* How many times the paths bounce before they leave the scene, get absorbed or are cut off, as a heatmap from none
+   (blue) to the maximum depth (red) */
+pub struct BounceCountIntegrator {}
+
+impl BounceCountIntegrator {
+    pub fn new() -> Self {
+        return Self {};
+    }
+}
+
+impl Integrator for BounceCountIntegrator {
+    fn name(&self) -> &'static str {
+        return "bounces";
+    }
+
+    fn radiance(&self, scene: &Scene, ray: &Ray, maxDepth: i16, _film: &mut Film) -> VColor {
+        let mut current: Ray = ray.clone();
+        let mut bounces: i16 = 0;
+
+        while bounces < maxDepth {
+            let mut hitRecord: HitRecord = HitRecord::default();
+            if !scene.Hit(&current, &mut hitRecord) {
+                break;
+            }
+
+            let mut attenuation: VColor = VColor::default();
+            let mut scattered: Ray = Ray::default();
+            if !hitRecord.material.scatter(&current, &hitRecord, &mut attenuation, &mut scattered) {
+                break;
+            }
+            bounces += 1;
+            current = scattered;
+        }
+
+        return heatmap(bounces as f32 / f32::max(maxDepth as f32, 1.0));
+    }
+}
+
+/* Fraction of the hemisphere above the first hit that is open within `radius`, cosine weighted */
+pub struct AmbientOcclusionIntegrator {
+    samples: i32,
+    radius: f32,
+}
+
+impl AmbientOcclusionIntegrator {
+    pub fn new(samples: i32, radius: f32) -> Self {
+        return Self {
+            samples: i32::max(samples, 1),
+            radius,
+        };
+    }
+}
+
+impl Integrator for AmbientOcclusionIntegrator {
+    fn name(&self) -> &'static str {
+        return "ao";
+    }
+
+    fn radiance(&self, scene: &Scene, ray: &Ray, _maxDepth: i16, _film: &mut Film) -> VColor {
+        let mut hitRecord: HitRecord = HitRecord::default();
+        if !scene.Hit(ray, &mut hitRecord) {
+            return VColor::new_sc(1.0);
+        }
+
+        let mut open: i32 = 0;
+        for _ in 0..self.samples {
+            let mut direction: Vec3 = hitRecord.normal + randomUnitVector3();
+            if isNearlyZero_Vec3(&direction, None) {
+                direction = hitRecord.normal;
+            }
+            if scene.isVisible(&hitRecord.point, &normalize(direction), self.radius) {
+                open += 1;
+            }
+        }
+
+        return VColor::new_sc(open as f32 / self.samples as f32);
+    }
+}
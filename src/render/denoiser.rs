@@ -0,0 +1,223 @@
+use glm::{dot, Vec3, vec3};
+use crate::render::film::Film;
+
+pub const DEFAULT_SEARCH_RADIUS: i32 = 7;
+pub const DEFAULT_PATCH_RADIUS: i32 = 1;
+pub const DEFAULT_STRENGTH: f32 = 0.45;
+
+/* How far apart features may be and still be filtered together. The depth one is relative to the depth */
+const ALBEDO_SIGMA: f32 = 0.1;
+const NORMAL_SIGMA: f32 = 0.3;
+const DEPTH_SIGMA: f32 = 0.05;
+/* Share of the variance taken off the color differences, which would otherwise count the noise as a difference */
+const VARIANCE_CANCELLATION: f32 = 1.0;
+
+/* Non-local means filter guided by the film's feature buffers, after Rousselle et al.'s "Robust denoising using
+   feature and color information". A pixel is averaged with the pixels in its search window whose neighbourhoods
+   have similar colors, measured against the estimated variance of the pixels, and whose albedo, normal and depth
+   match its own. The features keep the texture and geometry edges that the noisy colors alone would blur.
+
+   The variance only knows about the samples of the pixels, light that integrators splat onto the film is filtered
+   by the features alone */
+pub struct Denoiser {
+    searchRadius: i32,
+    patchRadius: i32,
+    /* Higher values let pixels with more differing colors in, which smooths more and blurs more */
+    strength: f32,
+}
+
+impl Denoiser {
+    pub fn new(searchRadius: i32, patchRadius: i32, strength: f32) -> Self {
+        return Self {
+            searchRadius,
+            patchRadius,
+            strength,
+        };
+    }
+
+    /* Filters `colors`, the film resolved row by row */
+    pub fn denoise(&self, film: &Film, colors: &[Vec3]) -> Vec<Vec3> {
+        let width: i32 = film.getWidth() as i32;
+        let height: i32 = film.getHeight() as i32;
+        let pixelCount: usize = (width * height) as usize;
+
+        let mut variances: Vec<f32> = Vec::with_capacity(pixelCount);
+        let mut albedos: Vec<Vec3> = Vec::with_capacity(pixelCount);
+        let mut normals: Vec<Vec3> = Vec::with_capacity(pixelCount);
+        let mut depths: Vec<f32> = Vec::with_capacity(pixelCount);
+        for y in 0..height as usize {
+            for x in 0..width as usize {
+                variances.push(film.varianceOfMean(x, y));
+                albedos.push(film.getAlbedo(x, y));
+                normals.push(film.getNormal(x, y));
+                depths.push(film.getDepth(x, y));
+            }
+        }
+
+        let mut sums: Vec<Vec3> = vec![vec3(0.0, 0.0, 0.0); pixelCount];
+        let mut weightSums: Vec<f32> = vec![0.0; pixelCount];
+        let mut distances: Vec<f32> = vec![0.0; pixelCount];
+        let mut valid: Vec<f32> = vec![0.0; pixelCount];
+
+        // One offset at a time, so that the patch distances are a box filter over the pixel distances
+        for dy in -self.searchRadius..=self.searchRadius {
+            for dx in -self.searchRadius..=self.searchRadius {
+                for y in 0..height {
+                    for x in 0..width {
+                        let p: usize = (y * width + x) as usize;
+                        let (qx, qy) = (x + dx, y + dy);
+                        if qx < 0 || qy < 0 || qx >= width || qy >= height {
+                            distances[p] = 0.0;
+                            valid[p] = 0.0;
+                            continue;
+                        }
+                        let q: usize = (qy * width + qx) as usize;
+                        distances[p] = self.colorDistance(&colors[p], &colors[q], variances[p], variances[q]);
+                        valid[p] = 1.0;
+                    }
+                }
+                let patchDistances: Vec<f32> = boxFilter(&distances, width, height, self.patchRadius);
+                let patchSizes: Vec<f32> = boxFilter(&valid, width, height, self.patchRadius);
+
+                for y in 0..height {
+                    for x in 0..width {
+                        let p: usize = (y * width + x) as usize;
+                        if valid[p] == 0.0 {
+                            continue;
+                        }
+                        let q: usize = ((y + dy) * width + x + dx) as usize;
+                        let colorWeight: f32 = (-f32::max(patchDistances[p] / patchSizes[p], 0.0)).exp();
+                        let weight: f32 = colorWeight * featureWeight(&albedos, &normals, &depths, p, q);
+                        sums[p] = sums[p] + colors[q] * weight;
+                        weightSums[p] += weight;
+                    }
+                }
+            }
+        }
+
+        // Every pixel is its own neighbour with a weight of one, so the sums are never empty
+        return sums.iter().zip(weightSums.iter()).map(|(sum, weight)| *sum / *weight).collect();
+    }
+
+    /* Squared color difference of two pixels in units of their variance, averaged over the channels. Pixels without
+       an estimate of their variance are told apart by the features only */
+    fn colorDistance(&self, p: &Vec3, q: &Vec3, varianceP: f32, varianceQ: f32) -> f32 {
+        if varianceP == f32::MAX || varianceQ == f32::MAX {
+            return 0.0;
+        }
+        let difference: Vec3 = *p - *q;
+        let cancellation: f32 = VARIANCE_CANCELLATION * (varianceP + f32::min(varianceP, varianceQ));
+        let normalization: f32 = 1e-10 + self.strength * self.strength * (varianceP + varianceQ);
+        return (dot(difference, difference) / 3.0 - cancellation) / normalization;
+    }
+}
+
+impl Default for Denoiser {
+    fn default() -> Self {
+        return Denoiser::new(DEFAULT_SEARCH_RADIUS, DEFAULT_PATCH_RADIUS, DEFAULT_STRENGTH);
+    }
+}
+
+fn featureWeight(albedos: &[Vec3], normals: &[Vec3], depths: &[f32], p: usize, q: usize) -> f32 {
+    let albedo: Vec3 = albedos[p] - albedos[q];
+    let normal: Vec3 = normals[p] - normals[q];
+    let depth: f32 = (depths[p] - depths[q]) / f32::max(f32::max(depths[p], depths[q]), 1e-4);
+    let distance: f32 = dot(albedo, albedo) / (ALBEDO_SIGMA * ALBEDO_SIGMA)
+        + dot(normal, normal) / (NORMAL_SIGMA * NORMAL_SIGMA)
+        + depth * depth / (DEPTH_SIGMA * DEPTH_SIGMA);
+    return (-distance).exp();
+}
+
+/* Sums of `values` over the square of `radius` around each pixel, the parts of the square outside the image left out */
+fn boxFilter(values: &[f32], width: i32, height: i32, radius: i32) -> Vec<f32> {
+    let mut rows: Vec<f32> = vec![0.0; values.len()];
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum: f32 = 0.0;
+            for sx in i32::max(x - radius, 0)..=i32::min(x + radius, width - 1) {
+                sum += values[(y * width + sx) as usize];
+            }
+            rows[(y * width + x) as usize] = sum;
+        }
+    }
+
+    let mut result: Vec<f32> = vec![0.0; values.len()];
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum: f32 = 0.0;
+            for sy in i32::max(y - radius, 0)..=i32::min(y + radius, height - 1) {
+                sum += rows[(sy * width + x) as usize];
+            }
+            result[(y * width + x) as usize] = sum;
+        }
+    }
+    return result;
+}
+
+#[cfg(test)]
+mod tests {
+    use glm::vec2;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use crate::math::vColor::VColor;
+    use super::*;
+
+    const SIZE: usize = 16;
+
+    /* Film of gray pixels with noisy samples around `mean(x)`, over a surface whose albedo is `albedo(x)` */
+    fn noisyFilm(mean: impl Fn(usize) -> f32, albedo: impl Fn(usize) -> f32) -> (Film, Vec<Vec3>) {
+        let mut random: StdRng = StdRng::seed_from_u64(7);
+        let mut film: Film = Film::new(SIZE, SIZE);
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                for _ in 0..8 {
+                    let value: f32 = mean(x) + random.gen_range(-0.2..0.2);
+                    film.addSample(x as i32, y as i32, &vec2(0.5, 0.5), &VColor::new_rgb(value, value, value));
+                }
+                film.addFeatures(x, y, &vec3(albedo(x), albedo(x), albedo(x)), &vec3(0.0, 0.0, -1.0), 2.0);
+            }
+        }
+
+        let mut colors: Vec<Vec3> = Vec::with_capacity(SIZE * SIZE);
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                colors.push(film.resolve(x, y, 0.0).getColorRGB());
+            }
+        }
+        return (film, colors);
+    }
+
+    fn meanSquaredError(colors: &[Vec3], expected: impl Fn(usize) -> f32) -> f32 {
+        let mut sum: f32 = 0.0;
+        for (i, color) in colors.iter().enumerate() {
+            let difference: f32 = color.x - expected(i % SIZE);
+            sum += difference * difference;
+        }
+        return sum / colors.len() as f32;
+    }
+
+    #[test]
+    fn noiseOnAConstantImageIsSmoothed() {
+        let (film, colors) = noisyFilm(|_| 0.5, |_| 0.5);
+        let denoised: Vec<Vec3> = Denoiser::default().denoise(&film, &colors);
+
+        let before: f32 = meanSquaredError(&colors, |_| 0.5);
+        let after: f32 = meanSquaredError(&denoised, |_| 0.5);
+        assert!(after < before * 0.25, "error {} before and {} after", before, after);
+    }
+
+    #[test]
+    fn edgesSurvive() {
+        // Bright on the left half and dark on the right, told apart by the albedo or by the colors alone
+        let mean = |x: usize| if x < SIZE / 2 { 0.8 } else { 0.2 };
+        let albedoEdge = |x: usize| if x < SIZE / 2 { 0.9 } else { 0.1 };
+        for (film, colors) in [noisyFilm(mean, albedoEdge), noisyFilm(mean, |_| 0.5)] {
+            let denoised: Vec<Vec3> = Denoiser::default().denoise(&film, &colors);
+            for y in 0..SIZE {
+                assert!(denoised[y * SIZE + SIZE / 2 - 1].x > 0.7, "{:?}", denoised[y * SIZE + SIZE / 2 - 1]);
+                assert!(denoised[y * SIZE + SIZE / 2].x < 0.3, "{:?}", denoised[y * SIZE + SIZE / 2]);
+            }
+            assert!(meanSquaredError(&denoised, mean) < meanSquaredError(&colors, mean));
+        }
+    }
+}
@@ -0,0 +1,561 @@
+use std::f32::consts::PI;
+use std::rc::Rc;
+use glm::{dot, exp, mix, normalize, Vec2, vec2, Vec3, vec3, vec4};
+use crate::math::distribution::Distribution2D;
+use crate::math::frame::Frame;
+use crate::math::mathUtils::randomVec2;
+use crate::math::ray::Ray;
+use crate::math::spectrum::{luminance, xyzToRGB};
+use crate::math::vColor::VColor;
+use crate::math::warp::{uniformCone, uniformConePdf, uniformSphere, uniformSpherePdf};
+use crate::render::hdrImage::HdrImage;
+use crate::render::light::{EmissionSample, infiniteEmissionOrigin, infiniteEmissionPdf, Light, LightSample};
+
+/* What a ray sees when it leaves the scene */
+pub trait Environment {
+    /* Light arriving from `direction`, `includeDirect` is false for rays whose origin already sampled the parts that
+       `light` hands out, like the sun */
+    fn radiance(&self, direction: &Vec3, includeDirect: bool) -> VColor;
+
+    /* Part of the environment that is worth sampling directly, added to the scene's lights with the environment */
+    fn light(&self) -> Option<Box<dyn Light>> {
+        return None;
+    }
+}
+
+/* The original white to blue backdrop along the y axis */
+pub struct GradientSky {}
+
+impl GradientSky {
+    pub fn new() -> Self {
+        return Self {};
+    }
+}
+
+impl Environment for GradientSky {
+    /* Sampled as a whole by its light, rays that do not take part in MIS leave it to the light */
+    fn radiance(&self, direction: &Vec3, includeDirect: bool) -> VColor {
+        if !includeDirect {
+            return VColor::default();
+        }
+        let unitDirection: Vec3 = normalize(*direction);
+        let t: f32 = 0.5 * (unitDirection.y + 1.0);
+        return VColor::new_sc(1.0) * (1.0 - t) + VColor::new_rgb(0.5, 0.7, 1.0) * t;
+    }
+
+    fn light(&self) -> Option<Box<dyn Light>> {
+        return Some(Box::new(SkyLight::new(Box::new(GradientSky::new()))));
+    }
+}
+
+/* The same light from every direction, a dark one makes a night sky */
+pub struct UniformSky {
+    color: VColor,
+}
+
+impl UniformSky {
+    pub fn new(color: VColor) -> Self {
+        return Self { color };
+    }
+}
+
+impl Environment for UniformSky {
+    fn radiance(&self, _direction: &Vec3, includeDirect: bool) -> VColor {
+        if !includeDirect {
+            return VColor::default();
+        }
+        return self.color.clone();
+    }
+
+    fn light(&self) -> Option<Box<dyn Light>> {
+        return Some(Box::new(SkyLight::new(Box::new(UniformSky::new(self.color.clone())))));
+    }
+}
+
+/* Light for smooth skies, sampled uniformly over the sphere of directions and combined with material sampling
+   through MIS */
+pub struct SkyLight {
+    sky: Box<dyn Environment>,
+}
+
+impl SkyLight {
+    pub fn new(sky: Box<dyn Environment>) -> Self {
+        return Self { sky };
+    }
+}
+
+impl Light for SkyLight {
+    fn illuminate(&self, _point: &Vec3, sample: &mut LightSample) -> bool {
+        sample.direction = uniformSphere(&randomVec2());
+        sample.distance = f32::MAX;
+        sample.pdf = uniformSpherePdf();
+        let value: Vec3 = self.sky.radiance(&sample.direction, true).getColorRGB() / sample.pdf;
+        sample.radiance = VColor::new_vec4(&vec4(value.x, value.y, value.z, 0.0));
+        return true;
+    }
+
+    fn pdf(&self, _point: &Vec3, _direction: &Vec3) -> f32 {
+        return uniformSpherePdf();
+    }
+
+    /* Average of the six axis directions stands in for the integral over the sphere */
+    fn power(&self, sceneRadius: f32) -> f32 {
+        let axes: [Vec3; 6] = [vec3(1.0, 0.0, 0.0), vec3(-1.0, 0.0, 0.0), vec3(0.0, 1.0, 0.0),
+                               vec3(0.0, -1.0, 0.0), vec3(0.0, 0.0, 1.0), vec3(0.0, 0.0, -1.0)];
+        let mut average: f32 = 0.0;
+        for axis in axes.iter() {
+            average += luminance(&self.sky.radiance(axis, true).getColorRGB()) / 6.0;
+        }
+        return PI * sceneRadius * sceneRadius * 4.0 * PI * average;
+    }
+
+    fn sampleEmission(&self, sceneCenter: &Vec3, sceneRadius: f32, sample: &mut EmissionSample) -> bool {
+        let toSky: Vec3 = uniformSphere(&randomVec2());
+        sample.ray = Ray::new(infiniteEmissionOrigin(&(toSky * -1.0), sceneCenter, sceneRadius), toSky * -1.0);
+        sample.normal = sample.ray.direction;
+        sample.radiance = self.sky.radiance(&toSky, true).getColorRGB();
+        sample.pdfPosition = infiniteEmissionPdf(sceneRadius);
+        sample.pdfDirection = uniformSpherePdf();
+        return true;
+    }
+
+    fn emissionPdf(&self, _point: &Vec3, _normal: &Vec3, _direction: &Vec3, sceneRadius: f32) -> (f32, f32) {
+        return (infiniteEmissionPdf(sceneRadius), uniformSpherePdf());
+    }
+}
+
+/* Scene units are kilocandela per square meter, this brings a clear midday zenith to about 0.3 */
+const SKY_EXPOSURE: f32 = 1.0 / 35.0;
+/* Irradiance of the sun straight overhead before the atmosphere, a white diffuse surface facing it ends up near 1 */
+const SUN_IRRADIANCE: f32 = 3.5;
+/* Half of the half degree the sun spans */
+const SUN_ANGULAR_RADIUS: f32 = 0.00465;
+
+/* Perez distribution of luminance over the sky dome for one of Y, x or y */
+struct PerezCoefficients {
+    a: f32,
+    b: f32,
+    c: f32,
+    d: f32,
+    e: f32,
+}
+
+impl PerezCoefficients {
+    fn new(a: f32, b: f32, c: f32, d: f32, e: f32) -> Self {
+        return Self { a, b, c, d, e };
+    }
+
+    /* `cosTheta` is the view direction against the zenith, `gamma` the angle between the view and the sun */
+    fn evaluate(&self, cosTheta: f32, gamma: f32) -> f32 {
+        let cosGamma: f32 = gamma.cos();
+        return (1.0 + self.a * (self.b / cosTheta).exp()) * (1.0 + self.c * (self.d * gamma).exp() + self.e * cosGamma * cosGamma);
+    }
+}
+
+/* Analytic daylight sky by Preetham, Shirley and Smits, "A Practical Analytic Model for Daylight" (1999).
+   The scene's up is -z like the ground of the random scene, the azimuth is measured from +x towards +y */
+pub struct PreethamSky {
+    sunDirection: Vec3,
+    up: Vec3,
+    perezY: PerezCoefficients,
+    perezX: PerezCoefficients,
+    perezYChroma: PerezCoefficients,
+    /* Zenith values divided by the Perez function at the zenith, in Y, x, y */
+    zenith: Vec3,
+    sunRadiance: Vec3,
+}
+
+impl PreethamSky {
+    /* Angles in degrees, turbidity from 2 (very clear) to around 10 (hazy) */
+    pub fn new(elevation: f32, azimuth: f32, turbidity: f32) -> Self {
+        let t: f32 = f32::max(turbidity, 1.7);
+        let elevationRad: f32 = elevation.to_radians();
+        let azimuthRad: f32 = azimuth.to_radians();
+        let sunDirection: Vec3 = normalize(vec3(elevationRad.cos() * azimuthRad.cos(),
+                                                elevationRad.cos() * azimuthRad.sin(),
+                                                -elevationRad.sin()));
+
+        let perezY = PerezCoefficients::new(0.1787 * t - 1.4630, -0.3554 * t + 0.4275, -0.0227 * t + 5.3251, 0.1206 * t - 2.5771, -0.0670 * t + 0.3703);
+        let perezX = PerezCoefficients::new(-0.0193 * t - 0.2592, -0.0665 * t + 0.0008, -0.0004 * t + 0.2125, -0.0641 * t - 0.8989, -0.0033 * t + 0.0452);
+        let perezYChroma = PerezCoefficients::new(-0.0167 * t - 0.2608, -0.0950 * t + 0.0092, -0.0079 * t + 0.2102, -0.0441 * t - 1.6537, -0.0109 * t + 0.0529);
+
+        // The model is fitted for the sun above the horizon
+        let thetaSun: f32 = f32::min(PI * 0.5 - elevationRad, PI * 0.5);
+        let theta2: f32 = thetaSun * thetaSun;
+        let theta3: f32 = theta2 * thetaSun;
+        let t2: f32 = t * t;
+
+        let chi: f32 = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * thetaSun);
+        let zenithLuminance: f32 = f32::max((4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192, 0.0);
+        let zenithX: f32 = t2 * (0.00166 * theta3 - 0.00375 * theta2 + 0.00209 * thetaSun)
+            + t * (-0.02903 * theta3 + 0.06377 * theta2 - 0.03202 * thetaSun + 0.00394)
+            + (0.11693 * theta3 - 0.21196 * theta2 + 0.06052 * thetaSun + 0.25886);
+        let zenithY: f32 = t2 * (0.00275 * theta3 - 0.00610 * theta2 + 0.00317 * thetaSun)
+            + t * (-0.04214 * theta3 + 0.08970 * theta2 - 0.04153 * thetaSun + 0.00516)
+            + (0.15346 * theta3 - 0.26756 * theta2 + 0.06670 * thetaSun + 0.26688);
+
+        let zenith: Vec3 = vec3(zenithLuminance / perezY.evaluate(1.0, thetaSun),
+                                zenithX / perezX.evaluate(1.0, thetaSun),
+                                zenithY / perezYChroma.evaluate(1.0, thetaSun));
+
+        return Self {
+            sunDirection,
+            up: vec3(0.0, 0.0, -1.0),
+            perezY,
+            perezX,
+            perezYChroma,
+            zenith,
+            sunRadiance: Self::sunTransmittance(thetaSun, t) * (SUN_IRRADIANCE / Self::sunSolidAngle()),
+        };
+    }
+
+    fn sunSolidAngle() -> f32 {
+        return 2.0 * PI * (1.0 - SUN_ANGULAR_RADIUS.cos());
+    }
+
+    /* Rough stand-in for the spectral attenuation of the model: Rayleigh scattering takes the blue out and haze takes
+       out all of it, both along the air mass of Kasten and Young */
+    fn sunTransmittance(thetaSun: f32, turbidity: f32) -> Vec3 {
+        let zenithDegrees: f32 = thetaSun.to_degrees();
+        if zenithDegrees >= 93.0 {
+            return vec3(0.0, 0.0, 0.0);
+        }
+
+        let airMass: f32 = 1.0 / (thetaSun.cos() + 0.50572 * (96.07995 - zenithDegrees).powf(-1.6364));
+        let rayleigh: Vec3 = vec3(0.0146, 0.0334, 0.0832);
+        let haze: f32 = 0.035 * (turbidity - 1.0);
+        return exp((rayleigh + vec3(haze, haze, haze)) * -airMass);
+    }
+
+    fn skyRadiance(&self, direction: &Vec3) -> Vec3 {
+        // Below the horizon the dome is continued with its value at the horizon
+        let cosTheta: f32 = f32::max(dot(*direction, self.up), 0.01);
+        let gamma: f32 = dot(*direction, self.sunDirection).clamp(-1.0, 1.0).acos();
+
+        let luminance: f32 = self.zenith.x * self.perezY.evaluate(cosTheta, gamma) * SKY_EXPOSURE;
+        let x: f32 = self.zenith.y * self.perezX.evaluate(cosTheta, gamma);
+        let y: f32 = f32::max(self.zenith.z * self.perezYChroma.evaluate(cosTheta, gamma), 1e-4);
+
+        let xyz: Vec3 = vec3(x / y * luminance, luminance, (1.0 - x - y) / y * luminance);
+        let rgb: Vec3 = xyzToRGB(&xyz);
+        return vec3(f32::max(rgb.x, 0.0), f32::max(rgb.y, 0.0), f32::max(rgb.z, 0.0));
+    }
+}
+
+impl Environment for PreethamSky {
+    fn radiance(&self, direction: &Vec3, includeDirect: bool) -> VColor {
+        let unitDirection: Vec3 = normalize(*direction);
+        let mut value: Vec3 = self.skyRadiance(&unitDirection);
+
+        if includeDirect && dot(unitDirection, self.sunDirection) >= SUN_ANGULAR_RADIUS.cos() {
+            value = value + self.sunRadiance;
+        }
+
+        return VColor::new_vec4(&vec4(value.x, value.y, value.z, 1.0));
+    }
+
+    fn light(&self) -> Option<Box<dyn Light>> {
+        if dot(self.sunDirection, self.up) <= 0.0 {
+            return None;
+        }
+        return Some(Box::new(Sun::new(self.sunDirection, self.sunRadiance)));
+    }
+}
+
+/* The sun disk as a light, sampled over its cone of directions for soft shadows */
+pub struct Sun {
+    frame: Frame,
+    radiance: Vec3,
+}
+
+impl Sun {
+    pub fn new(direction: Vec3, radiance: Vec3) -> Self {
+        return Self {
+            frame: Frame::new(vec3(0.0, 0.0, 0.0), direction),
+            radiance,
+        };
+    }
+}
+
+impl Light for Sun {
+    fn illuminate(&self, _point: &Vec3, sample: &mut LightSample) -> bool {
+        sample.direction = self.frame.vectorToWorld(&uniformCone(&randomVec2(), SUN_ANGULAR_RADIUS.cos()));
+        sample.distance = f32::MAX;
+        // Uniform over the cone, so the radiance is divided by the density of one over its solid angle
+        let irradiance: Vec3 = self.radiance * PreethamSky::sunSolidAngle();
+        sample.radiance = VColor::new_vec4(&vec4(irradiance.x, irradiance.y, irradiance.z, 0.0));
+        return true;
+    }
+
+    fn power(&self, sceneRadius: f32) -> f32 {
+        return PI * sceneRadius * sceneRadius * luminance(&(self.radiance * PreethamSky::sunSolidAngle()));
+    }
+
+    fn sampleEmission(&self, sceneCenter: &Vec3, sceneRadius: f32, sample: &mut EmissionSample) -> bool {
+        let travel: Vec3 = self.frame.vectorToWorld(&uniformCone(&randomVec2(), SUN_ANGULAR_RADIUS.cos())) * -1.0;
+        sample.ray = Ray::new(infiniteEmissionOrigin(&travel, sceneCenter, sceneRadius), travel);
+        sample.normal = travel;
+        sample.radiance = self.radiance;
+        sample.pdfPosition = infiniteEmissionPdf(sceneRadius);
+        sample.pdfDirection = uniformConePdf(SUN_ANGULAR_RADIUS.cos());
+        return true;
+    }
+
+    fn emissionPdf(&self, _point: &Vec3, _normal: &Vec3, direction: &Vec3, sceneRadius: f32) -> (f32, f32) {
+        let cosMax: f32 = SUN_ANGULAR_RADIUS.cos();
+        if dot(normalize(*direction) * -1.0, self.frame.yAxis) < cosMax {
+            return (infiniteEmissionPdf(sceneRadius), 0.0);
+        }
+        return (infiniteEmissionPdf(sceneRadius), uniformConePdf(cosMax));
+    }
+}
+
+/* Equirectangular map shared by the environment and its light. Rows go from the zenith (-z) down to the nadir,
+   columns around the up axis starting at +x plus the rotation */
+struct LatLongMap {
+    image: HdrImage,
+    distribution: Distribution2D,
+    rotation: f32,
+    scale: f32,
+}
+
+impl LatLongMap {
+    fn new(image: HdrImage, rotation: f32, scale: f32) -> Self {
+        let (width, height) = (image.getWidth(), image.getHeight());
+
+        // Luminance weighted by the solid angle of each row, rows near the poles cover less of the sphere
+        let mut function: Vec<f32> = Vec::with_capacity(width * height);
+        for y in 0..height {
+            let sinTheta: f32 = (PI * (y as f32 + 0.5) / height as f32).sin();
+            for x in 0..width {
+                let texel: Vec3 = image.texel(x, y);
+                function.push(luminance(&texel) * sinTheta);
+            }
+        }
+
+        return Self {
+            distribution: Distribution2D::new(&function, width, height),
+            image,
+            rotation,
+            scale,
+        };
+    }
+
+    fn directionToMap(&self, direction: &Vec3) -> Vec2 {
+        let unitDirection: Vec3 = normalize(*direction);
+        // The acos of z would lose the angle near the poles, where the map's density is the highest
+        let horizontal: f32 = (unitDirection.x * unitDirection.x + unitDirection.y * unitDirection.y).sqrt();
+        let theta: f32 = horizontal.atan2(-unitDirection.z);
+        let mut phi: f32 = unitDirection.y.atan2(unitDirection.x) - self.rotation;
+        phi = phi.rem_euclid(2.0 * PI);
+        return vec2(phi / (2.0 * PI), theta / PI);
+    }
+
+    fn mapToDirection(&self, uv: &Vec2) -> Vec3 {
+        let theta: f32 = uv.y * PI;
+        let phi: f32 = uv.x * 2.0 * PI + self.rotation;
+        return vec3(theta.sin() * phi.cos(), theta.sin() * phi.sin(), -theta.cos());
+    }
+
+    /* Bilinear lookup, wrapping around horizontally and clamped at the poles */
+    fn lookup(&self, uv: &Vec2) -> Vec3 {
+        let (width, height) = (self.image.getWidth(), self.image.getHeight());
+        let x: f32 = uv.x * width as f32 - 0.5;
+        let y: f32 = (uv.y * height as f32 - 0.5).clamp(0.0, (height - 1) as f32);
+
+        let x0: f32 = x.floor();
+        let y0: f32 = y.floor();
+        let (fx, fy) = (x - x0, y - y0);
+
+        let column = |offset: f32| -> usize { return ((x0 + offset) as i64).rem_euclid(width as i64) as usize; };
+        let row0: usize = y0 as usize;
+        let row1: usize = usize::min(row0 + 1, height - 1);
+
+        let top: Vec3 = mix(self.image.texel(column(0.0), row0), self.image.texel(column(1.0), row0), vec3(fx, fx, fx));
+        let bottom: Vec3 = mix(self.image.texel(column(0.0), row1), self.image.texel(column(1.0), row1), vec3(fx, fx, fx));
+        return mix(top, bottom, vec3(fy, fy, fy)) * self.scale;
+    }
+
+    fn pdf(&self, direction: &Vec3) -> f32 {
+        let uv: Vec2 = self.directionToMap(direction);
+        let sinTheta: f32 = (uv.y * PI).sin();
+        if sinTheta <= 0.0 {
+            return 0.0;
+        }
+        // From the unit square to the sphere of directions
+        return self.distribution.pdf(&uv) / (2.0 * PI * PI * sinTheta);
+    }
+}
+
+/* Image based lighting from an equirectangular HDR image, importance sampled by its brightness */
+pub struct EnvironmentMap {
+    map: Rc<LatLongMap>,
+}
+
+impl EnvironmentMap {
+    /* `rotation` in degrees around the up axis, `scale` multiplies the image values */
+    pub fn new(image: HdrImage, rotation: f32, scale: f32) -> Self {
+        return Self {
+            map: Rc::new(LatLongMap::new(image, rotation.to_radians(), scale)),
+        };
+    }
+
+    pub fn load(path: &str, rotation: f32, scale: f32) -> Result<Self, String> {
+        return Ok(EnvironmentMap::new(HdrImage::load(path)?, rotation, scale));
+    }
+}
+
+impl Environment for EnvironmentMap {
+    /* Everything is sampled with MIS, rays that do not take part in it leave the map to the light */
+    fn radiance(&self, direction: &Vec3, includeDirect: bool) -> VColor {
+        if !includeDirect {
+            return VColor::default();
+        }
+        let value: Vec3 = self.map.lookup(&self.map.directionToMap(direction));
+        return VColor::new_vec4(&vec4(value.x, value.y, value.z, 1.0));
+    }
+
+    fn light(&self) -> Option<Box<dyn Light>> {
+        return Some(Box::new(EnvironmentLight { map: Rc::clone(&self.map) }));
+    }
+}
+
+pub struct EnvironmentLight {
+    map: Rc<LatLongMap>,
+}
+
+impl Light for EnvironmentLight {
+    fn illuminate(&self, _point: &Vec3, sample: &mut LightSample) -> bool {
+        let (uv, mapPdf) = self.map.distribution.sampleContinuous(&randomVec2());
+        let sinTheta: f32 = (uv.y * PI).sin();
+        if mapPdf <= 0.0 || sinTheta <= 0.0 {
+            return false;
+        }
+
+        sample.direction = self.map.mapToDirection(&uv);
+        sample.distance = f32::MAX;
+        sample.pdf = mapPdf / (2.0 * PI * PI * sinTheta);
+        let value: Vec3 = self.map.lookup(&uv) / sample.pdf;
+        sample.radiance = VColor::new_vec4(&vec4(value.x, value.y, value.z, 0.0));
+        return true;
+    }
+
+    fn pdf(&self, _point: &Vec3, direction: &Vec3) -> f32 {
+        return self.map.pdf(direction);
+    }
+
+    /* Integral of the radiance over the sphere of directions, spread over the scene's cross section */
+    fn power(&self, sceneRadius: f32) -> f32 {
+        return PI * sceneRadius * sceneRadius * 2.0 * PI * PI * self.map.distribution.getIntegral() * self.map.scale;
+    }
+
+    fn sampleEmission(&self, sceneCenter: &Vec3, sceneRadius: f32, sample: &mut EmissionSample) -> bool {
+        let (uv, mapPdf) = self.map.distribution.sampleContinuous(&randomVec2());
+        let sinTheta: f32 = (uv.y * PI).sin();
+        if mapPdf <= 0.0 || sinTheta <= 0.0 {
+            return false;
+        }
+
+        let travel: Vec3 = self.map.mapToDirection(&uv) * -1.0;
+        sample.ray = Ray::new(infiniteEmissionOrigin(&travel, sceneCenter, sceneRadius), travel);
+        sample.normal = travel;
+        sample.radiance = self.map.lookup(&uv);
+        sample.pdfPosition = infiniteEmissionPdf(sceneRadius);
+        sample.pdfDirection = mapPdf / (2.0 * PI * PI * sinTheta);
+        return true;
+    }
+
+    fn emissionPdf(&self, _point: &Vec3, _normal: &Vec3, direction: &Vec3, sceneRadius: f32) -> (f32, f32) {
+        return (infiniteEmissionPdf(sceneRadius), self.map.pdf(&(*direction * -1.0)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assertClose(a: f32, b: f32, tolerance: f32) {
+        assert!((a - b).abs() <= tolerance * f32::max(b.abs(), 1.0), "{} is not {}", a, b);
+    }
+
+    /* Direction at `elevation` degrees above the horizon and `azimuth` degrees from +x, in the sky's frame */
+    fn skyDirection(elevation: f32, azimuth: f32) -> Vec3 {
+        let (e, a) = (elevation.to_radians(), azimuth.to_radians());
+        return vec3(e.cos() * a.cos(), e.cos() * a.sin(), -e.sin());
+    }
+
+    #[test]
+    fn skyIsBlueOverheadAndBrightAroundTheSun() {
+        let sky: PreethamSky = PreethamSky::new(60.0, 0.0, 3.0);
+        let zenith: Vec3 = sky.radiance(&vec3(0.0, 0.0, -1.0), false).getColorRGB();
+        assert!(zenith.z > zenith.x, "zenith {:?} is not blue", zenith);
+
+        let nearSun: Vec3 = sky.radiance(&skyDirection(40.0, 0.0), false).getColorRGB();
+        let awayFromSun: Vec3 = sky.radiance(&skyDirection(40.0, 180.0), false).getColorRGB();
+        assert!(luminance(&nearSun) > luminance(&awayFromSun));
+
+        // Every direction, the ground included, gets a finite non negative value
+        for elevation in (-90..=90).step_by(15) {
+            for azimuth in (0..360).step_by(30) {
+                let value: Vec3 = sky.radiance(&skyDirection(elevation as f32, azimuth as f32), true).getColorRGB();
+                assert!(value.x.is_finite() && value.y.is_finite() && value.z.is_finite());
+                assert!(value.x >= 0.0 && value.y >= 0.0 && value.z >= 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn sunIsSeenOnlyWithDirectLight() {
+        let sky: PreethamSky = PreethamSky::new(45.0, 30.0, 3.0);
+        let direct: Vec3 = sky.radiance(&sky.sunDirection, true).getColorRGB();
+        let indirect: Vec3 = sky.radiance(&sky.sunDirection, false).getColorRGB();
+        assertClose(direct.y - indirect.y, sky.sunRadiance.y, 1e-3);
+
+        // The sun near the horizon crosses more air and loses more blue
+        let low: PreethamSky = PreethamSky::new(5.0, 30.0, 3.0);
+        assert!(low.sunRadiance.x / low.sunRadiance.z > sky.sunRadiance.x / sky.sunRadiance.z);
+        assert!(luminance(&low.sunRadiance) < luminance(&sky.sunRadiance));
+
+        assert!(sky.light().is_some());
+        assert!(PreethamSky::new(-10.0, 30.0, 3.0).light().is_none());
+    }
+
+    #[test]
+    fn sunSamplesStayOnItsDisk() {
+        let sky: PreethamSky = PreethamSky::new(45.0, 30.0, 3.0);
+        let sun: Box<dyn Light> = sky.light().unwrap();
+        let point: Vec3 = vec3(0.0, 0.0, 0.0);
+        for _ in 0..100 {
+            let mut sample: LightSample = LightSample::default();
+            assert!(sun.illuminate(&point, &mut sample));
+            assert!(dot(sample.direction, sky.sunDirection) >= SUN_ANGULAR_RADIUS.cos() - 1e-5);
+            // Material sampling never finds the sun on its own, so it stays out of MIS
+            assert_eq!(sample.pdf, 0.0);
+            assert_eq!(sun.pdf(&point, &sample.direction), 0.0);
+        }
+    }
+
+    #[test]
+    fn environmentPdfsMatchTheirSamples() {
+        let mut bytes: Vec<u8> = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 4 +X 4\n".to_vec();
+        for i in 0..16u8 {
+            bytes.extend_from_slice(&[16 + i * 8, 8 + i * 4, 4 + i * 2, 128]);
+        }
+        let map: EnvironmentMap = EnvironmentMap::new(HdrImage::parse(&bytes).unwrap(), 20.0, 1.0);
+        let lights: Vec<Box<dyn Light>> = vec![
+            map.light().unwrap(),
+            Box::new(SkyLight::new(Box::new(PreethamSky::new(45.0, 30.0, 3.0)))),
+        ];
+
+        let point: Vec3 = vec3(0.0, 0.0, 0.0);
+        for light in lights.iter() {
+            for _ in 0..500 {
+                let mut sample: LightSample = LightSample::default();
+                if !light.illuminate(&point, &mut sample) {
+                    continue;
+                }
+                assertClose(light.pdf(&point, &sample.direction), sample.pdf, 1e-3);
+            }
+        }
+    }
+}
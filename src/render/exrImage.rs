@@ -0,0 +1,132 @@
+use std::fs;
+use crate::render::aov::AovImage;
+
+/* Pixel type of the channels, 32 bit floats */
+const FLOAT_PIXELS: i32 = 2;
+
+/* Writes `layers` into one OpenEXR file: uncompressed scanlines of 32 bit floats, the channels of all layers side by
+   side, which is what compositors read as a multi-layer image */
+pub fn writeExr(path: &str, width: usize, height: usize, layers: &[AovImage]) -> Result<(), String> {
+    // Where each channel's values are: the layer and the channel's position in its pixels
+    let mut channels: Vec<(&str, &AovImage, usize)> = vec![];
+    for layer in layers {
+        if layer.pixels.len() != width * height * layer.channels.len() {
+            return Err(format!("layer '{}' does not match the image size", layer.name));
+        }
+        for (offset, name) in layer.channels.iter().enumerate() {
+            channels.push((name.as_str(), layer, offset));
+        }
+    }
+    // Readers expect the channels sorted by name, in the header and in the pixel data
+    channels.sort_by(|a, b| a.0.cmp(b.0));
+
+    let mut bytes: Vec<u8> = vec![];
+    bytes.extend_from_slice(&20000630i32.to_le_bytes());
+    // Version 2, single part scanline image. Channel names longer than 31 bytes need the long names flag
+    let longNames: bool = channels.iter().any(|(name, _, _)| name.len() > 31);
+    bytes.extend_from_slice(&(2i32 | if longNames { 0x400 } else { 0 }).to_le_bytes());
+
+    let mut channelList: Vec<u8> = vec![];
+    for (name, _, _) in channels.iter() {
+        channelList.extend_from_slice(name.as_bytes());
+        channelList.push(0);
+        channelList.extend_from_slice(&FLOAT_PIXELS.to_le_bytes());
+        // Perceptually linear flag and three reserved bytes, then the x and y sampling
+        channelList.extend_from_slice(&[0, 0, 0, 0]);
+        channelList.extend_from_slice(&1i32.to_le_bytes());
+        channelList.extend_from_slice(&1i32.to_le_bytes());
+    }
+    channelList.push(0);
+
+    let mut window: Vec<u8> = vec![];
+    for value in [0, 0, width as i32 - 1, height as i32 - 1] {
+        window.extend_from_slice(&value.to_le_bytes());
+    }
+
+    writeAttribute(&mut bytes, "channels", "chlist", &channelList);
+    writeAttribute(&mut bytes, "compression", "compression", &[0]);
+    writeAttribute(&mut bytes, "dataWindow", "box2i", &window);
+    writeAttribute(&mut bytes, "displayWindow", "box2i", &window);
+    writeAttribute(&mut bytes, "lineOrder", "lineOrder", &[0]);
+    writeAttribute(&mut bytes, "pixelAspectRatio", "float", &1.0f32.to_le_bytes());
+    writeAttribute(&mut bytes, "screenWindowCenter", "v2f", &[0.0f32.to_le_bytes(), 0.0f32.to_le_bytes()].concat());
+    writeAttribute(&mut bytes, "screenWindowWidth", "float", &1.0f32.to_le_bytes());
+    bytes.push(0);
+
+    // Offsets of the scanlines, each is its y, its size and the values of every channel in turn
+    let scanlineSize: usize = 8 + width * channels.len() * 4;
+    let firstScanline: usize = bytes.len() + height * 8;
+    for y in 0..height {
+        bytes.extend_from_slice(&((firstScanline + y * scanlineSize) as u64).to_le_bytes());
+    }
+    for y in 0..height {
+        bytes.extend_from_slice(&(y as i32).to_le_bytes());
+        bytes.extend_from_slice(&((scanlineSize - 8) as i32).to_le_bytes());
+        for (_, layer, offset) in channels.iter() {
+            let stride: usize = layer.channels.len();
+            for x in 0..width {
+                let value: f32 = layer.pixels[(y * width + x) * stride + offset];
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+    }
+
+    return fs::write(path, bytes).map_err(|e| format!("cannot write '{}': {}", path, e));
+}
+
+fn writeAttribute(bytes: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    bytes.extend_from_slice(name.as_bytes());
+    bytes.push(0);
+    bytes.extend_from_slice(kind.as_bytes());
+    bytes.push(0);
+    bytes.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    bytes.extend_from_slice(value);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use glm::{Vec3, vec3};
+    use super::*;
+
+    fn readI32(bytes: &[u8], position: usize) -> i32 {
+        return i32::from_le_bytes(bytes[position..position + 4].try_into().unwrap());
+    }
+
+    #[test]
+    fn offsetTablePointsAtEachScanline() {
+        let (width, height): (usize, usize) = (3, 4);
+        let colors: Vec<Vec3> = (0..width * height).map(|i| vec3(i as f32, 0.5, -1.0)).collect();
+        let depths: Vec<f32> = (0..width * height).map(|i| i as f32 * 10.0).collect();
+        let layers: Vec<AovImage> = vec![AovImage::new_colors("beauty", &colors), AovImage::new_values("depth", "Z", &depths)];
+
+        let path: String = env::temp_dir().join("exrImageOffsetTable.exr").to_string_lossy().to_string();
+        writeExr(&path, width, height, &layers).unwrap();
+        let bytes: Vec<u8> = fs::read(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(readI32(&bytes, 0), 20000630);
+
+        // Past the attributes, each a name, a type, a size and the value, up to the empty name that ends the header
+        let mut position: usize = 8;
+        while bytes[position] != 0 {
+            for _ in 0..2 {
+                position += bytes[position..].iter().position(|b| *b == 0).unwrap() + 1;
+            }
+            position += 4 + readI32(&bytes, position) as usize;
+        }
+        position += 1;
+
+        // Channels in name order: B, G, R, depth.Z
+        let scanlineSize: usize = 8 + width * 4 * 4;
+        for y in 0..height {
+            let offset: usize = u64::from_le_bytes(bytes[position + y * 8..position + y * 8 + 8].try_into().unwrap()) as usize;
+            assert_eq!(readI32(&bytes, offset), y as i32);
+            assert_eq!(readI32(&bytes, offset + 4) as usize, scanlineSize - 8);
+            let value = |channel: usize, x: usize| -> f32 { return f32::from_le_bytes(bytes[offset + 8 + (channel * width + x) * 4..][..4].try_into().unwrap()); };
+            assert_eq!(value(2, 1), (y * width + 1) as f32);
+            assert_eq!(value(3, 2), (y * width + 2) as f32 * 10.0);
+        }
+        let lastOffset: usize = u64::from_le_bytes(bytes[position + (height - 1) * 8..position + height * 8].try_into().unwrap()) as usize;
+        assert_eq!(lastOffset + scanlineSize, bytes.len());
+    }
+}
@@ -0,0 +1,283 @@
+use std::rc::Rc;
+use glm::{Vec2, vec2, Vec3, vec3, Vec4, vec4};
+use crate::math::spectrum::luminance;
+use crate::math::vColor::VColor;
+use crate::render::filter::{BoxFilter, Filter};
+
+/* Brightness below which the error of a pixel is measured against this floor instead, so that black pixels do not
+   ask for samples forever */
+const ERROR_FLOOR: f32 = 0.01;
+
+/* Accumulates the samples of a render. Camera samples are spread over the pixels around them by the reconstruction
+   filter, splats come from paths that reach the camera from the lights and land in the pixel they hit. The error
+   estimate and the features stay with the pixel a sample was taken for */
+pub struct Film {
+    width: usize,
+    height: usize,
+    /* Where the film sits in the camera's frame when it covers only part of it, in pixels */
+    frameOffsetX: usize,
+    frameOffsetY: usize,
+    frameWidth: usize,
+    frameHeight: usize,
+    filter: Rc<dyn Filter>,
+    /* Sums of the filter weighted camera samples and of their weights */
+    sums: Vec<Vec4>,
+    weightSums: Vec<f32>,
+    sampleCounts: Vec<u32>,
+    /* Running mean of the samples' luminance and the sum of their squared deviations from it, Welford's method */
+    luminanceMeans: Vec<f32>,
+    luminanceDeviations: Vec<f32>,
+    splats: Vec<Vec3>,
+    /* Sums of what the camera rays hit first: the surface's albedo and shading normal and the distance to it. They
+       guide the denoiser */
+    albedoSums: Vec<Vec3>,
+    normalSums: Vec<Vec3>,
+    depthSums: Vec<f32>,
+    featureCounts: Vec<u32>,
+}
+
+impl Film {
+    /* Keeps every camera sample in its own pixel */
+    pub fn new(width: usize, height: usize) -> Self {
+        return Film::new_filtered(width, height, Rc::new(BoxFilter::new(0.5)));
+    }
+
+    pub fn new_filtered(width: usize, height: usize, filter: Rc<dyn Filter>) -> Self {
+        return Self {
+            width,
+            height,
+            frameOffsetX: 0,
+            frameOffsetY: 0,
+            frameWidth: width,
+            frameHeight: height,
+            filter,
+            sums: vec![vec4(0.0, 0.0, 0.0, 0.0); width * height],
+            weightSums: vec![0.0; width * height],
+            sampleCounts: vec![0; width * height],
+            luminanceMeans: vec![0.0; width * height],
+            luminanceDeviations: vec![0.0; width * height],
+            splats: vec![vec3(0.0, 0.0, 0.0); width * height],
+            albedoSums: vec![vec3(0.0, 0.0, 0.0); width * height],
+            normalSums: vec![vec3(0.0, 0.0, 0.0); width * height],
+            depthSums: vec![0.0; width * height],
+            featureCounts: vec![0; width * height],
+        };
+    }
+
+    /* Makes the film the part of a `frameWidth` by `frameHeight` frame that starts at the offset, which is what splats
+       are placed in */
+    pub fn setFrame(&mut self, offsetX: usize, offsetY: usize, frameWidth: usize, frameHeight: usize) {
+        self.frameOffsetX = offsetX;
+        self.frameOffsetY = offsetY;
+        self.frameWidth = frameWidth;
+        self.frameHeight = frameHeight;
+    }
+
+    pub fn getWidth(&self) -> usize {
+        return self.width;
+    }
+
+    pub fn getHeight(&self) -> usize {
+        return self.height;
+    }
+
+    /* A camera sample taken for pixel `x`, `y`, at `offset` in [0, 1)² from the pixel's corner. The pixel may lie
+       outside the film by up to the filter's radius, the sample then only adds to the film's pixels it reaches */
+    pub fn addSample(&mut self, x: i32, y: i32, offset: &Vec2, color: &VColor) {
+        let position: Vec2 = vec2(x as f32 + offset.x, y as f32 + offset.y);
+        let radius: f32 = self.filter.radius();
+        let firstX: i64 = i64::max((position.x - 0.5 - radius).ceil() as i64, 0);
+        let lastX: i64 = i64::min((position.x - 0.5 + radius).floor() as i64, self.width as i64 - 1);
+        let firstY: i64 = i64::max((position.y - 0.5 - radius).ceil() as i64, 0);
+        let lastY: i64 = i64::min((position.y - 0.5 + radius).floor() as i64, self.height as i64 - 1);
+        for pixelY in firstY..=lastY {
+            for pixelX in firstX..=lastX {
+                let weight: f32 = self.filter.evaluate(pixelX as f32 + 0.5 - position.x, pixelY as f32 + 0.5 - position.y);
+                if weight == 0.0 {
+                    continue;
+                }
+                let index: usize = pixelY as usize * self.width + pixelX as usize;
+                self.sums[index] = self.sums[index] + color.color * weight;
+                self.weightSums[index] += weight;
+            }
+        }
+
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return;
+        }
+        let index: usize = y as usize * self.width + x as usize;
+        self.sampleCounts[index] += 1;
+
+        let value: f32 = luminance(&color.getColorRGB());
+        let delta: f32 = value - self.luminanceMeans[index];
+        self.luminanceMeans[index] += delta / self.sampleCounts[index] as f32;
+        self.luminanceDeviations[index] += delta * (value - self.luminanceMeans[index]);
+    }
+
+    pub fn getSampleCount(&self, x: usize, y: usize) -> u32 {
+        return self.sampleCounts[y * self.width + x];
+    }
+
+    /* Estimated variance of the pixel's average luminance. Unbounded until there are two samples */
+    pub fn varianceOfMean(&self, x: usize, y: usize) -> f32 {
+        let index: usize = y * self.width + x;
+        let count: u32 = self.sampleCounts[index];
+        if count < 2 {
+            return f32::MAX;
+        }
+        return self.luminanceDeviations[index] / ((count - 1) * count) as f32;
+    }
+
+    /* Estimated error of the pixel's average, the standard error of its luminance relative to the luminance itself.
+       Unbounded until there are two samples */
+    pub fn relativeError(&self, x: usize, y: usize) -> f32 {
+        let variance: f32 = self.varianceOfMean(x, y);
+        if variance == f32::MAX {
+            return f32::MAX;
+        }
+        return variance.sqrt() / f32::max(self.luminanceMeans[y * self.width + x], ERROR_FLOOR);
+    }
+
+    /* `normal` is zero and `depth` is zero for rays that hit nothing */
+    pub fn addFeatures(&mut self, x: usize, y: usize, albedo: &Vec3, normal: &Vec3, depth: f32) {
+        let index: usize = y * self.width + x;
+        self.albedoSums[index] = self.albedoSums[index] + *albedo;
+        self.normalSums[index] = self.normalSums[index] + *normal;
+        self.depthSums[index] += depth;
+        self.featureCounts[index] += 1;
+    }
+
+    pub fn getAlbedo(&self, x: usize, y: usize) -> Vec3 {
+        let index: usize = y * self.width + x;
+        return self.albedoSums[index] / f32::max(self.featureCounts[index] as f32, 1.0);
+    }
+
+    /* Average of the normals, shorter than one where the pixel covers an edge */
+    pub fn getNormal(&self, x: usize, y: usize) -> Vec3 {
+        let index: usize = y * self.width + x;
+        return self.normalSums[index] / f32::max(self.featureCounts[index] as f32, 1.0);
+    }
+
+    pub fn getDepth(&self, x: usize, y: usize) -> f32 {
+        let index: usize = y * self.width + x;
+        return self.depthSums[index] / f32::max(self.featureCounts[index] as f32, 1.0);
+    }
+
+    /* `u` and `v` are the film coordinates in [0, 1] that the camera maps to the frame */
+    pub fn addSplat(&mut self, u: f32, v: f32, value: &Vec3) {
+        if !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) {
+            return;
+        }
+        let frameX: usize = usize::min((u * self.frameWidth as f32) as usize, self.frameWidth - 1);
+        let frameY: usize = usize::min((v * self.frameHeight as f32) as usize, self.frameHeight - 1);
+        if frameX < self.frameOffsetX || frameY < self.frameOffsetY {
+            return;
+        }
+        let (x, y) = (frameX - self.frameOffsetX, frameY - self.frameOffsetY);
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let index: usize = y * self.width + x;
+        self.splats[index] = self.splats[index] + *value;
+    }
+
+    /* Weighted average of the samples around the pixel plus its splats, which are scaled by `splatScale`, one over
+       the number of light paths traced per pixel */
+    pub fn resolve(&self, x: usize, y: usize, splatScale: f32) -> VColor {
+        let index: usize = y * self.width + x;
+        let mut color: Vec4 = vec4(0.0, 0.0, 0.0, 0.0);
+        // Filters with negative lobes can leave next to nothing, or less, in the sum of weights
+        if self.weightSums[index] > 1e-6 {
+            color = self.sums[index] / self.weightSums[index];
+        }
+        let splat: Vec3 = self.splats[index] * splatScale;
+        return VColor::new_vec4(&vec4(color.x + splat.x, color.y + splat.y, color.z + splat.z, color.w));
+    }
+
+    /* Appends everything the film has accumulated to `bytes`, for `readState` to pick up where the render stopped */
+    pub fn writeState(&self, bytes: &mut Vec<u8>) {
+        writeFloats(bytes, self.sums.iter().flat_map(|v| [v.x, v.y, v.z, v.w]));
+        writeFloats(bytes, self.weightSums.iter().copied());
+        writeFloats(bytes, self.sampleCounts.iter().map(|count| f32::from_bits(*count)));
+        writeFloats(bytes, self.luminanceMeans.iter().copied());
+        writeFloats(bytes, self.luminanceDeviations.iter().copied());
+        writeFloats(bytes, self.splats.iter().flat_map(|v| [v.x, v.y, v.z]));
+        writeFloats(bytes, self.albedoSums.iter().flat_map(|v| [v.x, v.y, v.z]));
+        writeFloats(bytes, self.normalSums.iter().flat_map(|v| [v.x, v.y, v.z]));
+        writeFloats(bytes, self.depthSums.iter().copied());
+        writeFloats(bytes, self.featureCounts.iter().map(|count| f32::from_bits(*count)));
+    }
+
+    /* Replaces what the film has accumulated with what `writeState` wrote for a film of the same size, starting at
+       `position`, which is moved past it */
+    pub fn readState(&mut self, bytes: &[u8], position: &mut usize) -> Result<(), String> {
+        let count: usize = self.width * self.height;
+        let sums: Vec<f32> = readFloats(bytes, position, count * 4)?;
+        self.sums = sums.chunks(4).map(|v| vec4(v[0], v[1], v[2], v[3])).collect();
+        self.weightSums = readFloats(bytes, position, count)?;
+        self.sampleCounts = readFloats(bytes, position, count)?.iter().map(|v| v.to_bits()).collect();
+        self.luminanceMeans = readFloats(bytes, position, count)?;
+        self.luminanceDeviations = readFloats(bytes, position, count)?;
+        self.splats = readFloats(bytes, position, count * 3)?.chunks(3).map(|v| vec3(v[0], v[1], v[2])).collect();
+        self.albedoSums = readFloats(bytes, position, count * 3)?.chunks(3).map(|v| vec3(v[0], v[1], v[2])).collect();
+        self.normalSums = readFloats(bytes, position, count * 3)?.chunks(3).map(|v| vec3(v[0], v[1], v[2])).collect();
+        self.depthSums = readFloats(bytes, position, count)?;
+        self.featureCounts = readFloats(bytes, position, count)?.iter().map(|v| v.to_bits()).collect();
+        return Ok(());
+    }
+}
+
+/* Counts go through as the bits of a float, so that every buffer is a run of little endian 32 bit values */
+fn writeFloats(bytes: &mut Vec<u8>, values: impl Iterator<Item = f32>) {
+    for value in values {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+fn readFloats(bytes: &[u8], position: &mut usize, count: usize) -> Result<Vec<f32>, String> {
+    let end: usize = *position + count * 4;
+    if end > bytes.len() {
+        return Err("truncated film state".to_string());
+    }
+    let values: Vec<f32> = bytes[*position..end].chunks(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect();
+    *position = end;
+    return Ok(values);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::filter::TentFilter;
+
+    #[test]
+    fn stateRoundTrip() {
+        let mut film: Film = Film::new_filtered(4, 3, Rc::new(TentFilter::new(1.0)));
+        for (i, (x, y)) in [(0, 0), (1, 2), (3, 1), (2, 2), (1, 2)].iter().enumerate() {
+            let color: VColor = VColor::new_rgb(0.1 * i as f32, 0.5, 1.0 - 0.2 * i as f32);
+            film.addSample(*x, *y, &vec2(0.3, 0.7), &color);
+        }
+        film.addSplat(0.6, 0.4, &vec3(2.0, 1.0, 0.5));
+        film.addFeatures(1, 2, &vec3(0.8, 0.2, 0.1), &vec3(0.0, 1.0, 0.0), 3.5);
+
+        let mut bytes: Vec<u8> = vec![7];
+        film.writeState(&mut bytes);
+        let mut restored: Film = Film::new_filtered(4, 3, Rc::new(TentFilter::new(1.0)));
+        let mut position: usize = 1;
+        restored.readState(&bytes, &mut position).unwrap();
+        assert_eq!(position, bytes.len());
+
+        for y in 0..3 {
+            for x in 0..4 {
+                assert_eq!(restored.resolve(x, y, 0.5).color, film.resolve(x, y, 0.5).color);
+                assert_eq!(restored.getSampleCount(x, y), film.getSampleCount(x, y));
+                assert_eq!(restored.varianceOfMean(x, y), film.varianceOfMean(x, y));
+                assert_eq!(restored.getAlbedo(x, y), film.getAlbedo(x, y));
+                assert_eq!(restored.getNormal(x, y), film.getNormal(x, y));
+                assert_eq!(restored.getDepth(x, y), film.getDepth(x, y));
+            }
+        }
+
+        let mut truncated: Film = Film::new_filtered(4, 3, Rc::new(TentFilter::new(1.0)));
+        assert!(truncated.readState(&bytes[..bytes.len() - 1], &mut 1).is_err());
+    }
+}
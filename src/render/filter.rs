@@ -0,0 +1,228 @@
+use std::f32::consts::PI;
+use std::rc::Rc;
+
+/* Pixel reconstruction filter. A camera sample adds to every pixel whose center is within the radius of it,
+   weighted by the filter at the offset from the center, and a pixel is the weighted average of what it got */
+pub trait Filter {
+    /* Name `createFilter` knows it by */
+    fn name(&self) -> &'static str;
+
+    /* Half the width of the square the filter covers, in pixels */
+    fn radius(&self) -> f32;
+
+    /* Weight of a sample `x`, `y` pixels away from a pixel's center, zero outside the radius */
+    fn evaluate(&self, x: f32, y: f32) -> f32;
+}
+
+/* Names accepted on the command line */
+pub const FILTER_NAMES: [&str; 5] = ["box", "tent", "gaussian", "mitchell", "lanczos"];
+
+/* Filter by name, with the filter's usual radius unless `radius` is given */
+pub fn createFilter(name: &str, radius: Option<f32>) -> Result<Rc<dyn Filter>, String> {
+    if let Some(r) = radius {
+        if !r.is_finite() || r <= 0.0 {
+            return Err(format!("the radius has to be a positive number of pixels, not {}", r));
+        }
+    }
+
+    let filter: Rc<dyn Filter> = match name {
+        "box" => Rc::new(BoxFilter::new(radius.unwrap_or(0.5))),
+        "tent" => Rc::new(TentFilter::new(radius.unwrap_or(1.0))),
+        "gaussian" => Rc::new(GaussianFilter::new(radius.unwrap_or(1.5))),
+        "mitchell" => Rc::new(MitchellFilter::new(radius.unwrap_or(2.0))),
+        "lanczos" => Rc::new(LanczosFilter::new(radius.unwrap_or(2.0))),
+        _ => return Err(format!("unknown filter '{}', expected one of {}", name, FILTER_NAMES.join(", "))),
+    };
+    return Ok(filter);
+}
+
+/* Equal weights, with a radius of half a pixel every sample stays in its own pixel */
+pub struct BoxFilter {
+    radius: f32,
+}
+
+impl BoxFilter {
+    pub fn new(radius: f32) -> Self {
+        return Self { radius };
+    }
+}
+
+impl Filter for BoxFilter {
+    fn name(&self) -> &'static str {
+        return "box";
+    }
+
+    fn radius(&self) -> f32 {
+        return self.radius;
+    }
+
+    /* Half open, so that a sample on the border between two pixels goes to one of them */
+    fn evaluate(&self, x: f32, y: f32) -> f32 {
+        if x > -self.radius && x <= self.radius && y > -self.radius && y <= self.radius {
+            return 1.0;
+        }
+        return 0.0;
+    }
+}
+
+/* Weights falling off linearly to zero at the radius */
+pub struct TentFilter {
+    radius: f32,
+}
+
+impl TentFilter {
+    pub fn new(radius: f32) -> Self {
+        return Self { radius };
+    }
+}
+
+impl Filter for TentFilter {
+    fn name(&self) -> &'static str {
+        return "tent";
+    }
+
+    fn radius(&self) -> f32 {
+        return self.radius;
+    }
+
+    fn evaluate(&self, x: f32, y: f32) -> f32 {
+        return f32::max(self.radius - x.abs(), 0.0) * f32::max(self.radius - y.abs(), 0.0);
+    }
+}
+
+/* Gaussian with a standard deviation of a third of the radius, shifted down to reach zero at the radius */
+pub struct GaussianFilter {
+    radius: f32,
+    sigma: f32,
+}
+
+impl GaussianFilter {
+    pub fn new(radius: f32) -> Self {
+        return Self { radius, sigma: radius / 3.0 };
+    }
+
+    fn gaussian1D(&self, x: f32) -> f32 {
+        let gaussian = |x: f32| (-x * x / (2.0 * self.sigma * self.sigma)).exp();
+        return f32::max(gaussian(x) - gaussian(self.radius), 0.0);
+    }
+}
+
+impl Filter for GaussianFilter {
+    fn name(&self) -> &'static str {
+        return "gaussian";
+    }
+
+    fn radius(&self) -> f32 {
+        return self.radius;
+    }
+
+    fn evaluate(&self, x: f32, y: f32) -> f32 {
+        return self.gaussian1D(x) * self.gaussian1D(y);
+    }
+}
+
+/* Mitchell and Netravali's cubic with B = C = 1/3, their pick between blurring and ringing. Its negative lobes
+   sharpen edges */
+pub struct MitchellFilter {
+    radius: f32,
+}
+
+impl MitchellFilter {
+    pub fn new(radius: f32) -> Self {
+        return Self { radius };
+    }
+
+    /* The cubic spans [-2, 2], the offset is scaled to it */
+    fn mitchell1D(&self, x: f32) -> f32 {
+        let (b, c): (f32, f32) = (1.0 / 3.0, 1.0 / 3.0);
+        let x: f32 = (2.0 * x / self.radius).abs();
+        if x >= 2.0 {
+            return 0.0;
+        }
+        if x > 1.0 {
+            return ((-b - 6.0 * c) * x * x * x + (6.0 * b + 30.0 * c) * x * x + (-12.0 * b - 48.0 * c) * x + (8.0 * b + 24.0 * c)) / 6.0;
+        }
+        return ((12.0 - 9.0 * b - 6.0 * c) * x * x * x + (-18.0 + 12.0 * b + 6.0 * c) * x * x + (6.0 - 2.0 * b)) / 6.0;
+    }
+}
+
+impl Filter for MitchellFilter {
+    fn name(&self) -> &'static str {
+        return "mitchell";
+    }
+
+    fn radius(&self) -> f32 {
+        return self.radius;
+    }
+
+    fn evaluate(&self, x: f32, y: f32) -> f32 {
+        return self.mitchell1D(x) * self.mitchell1D(y);
+    }
+}
+
+/* Sinc windowed by a wider sinc, three lobes across the radius. The sharpest of the filters, and the one that rings
+   the most */
+pub struct LanczosFilter {
+    radius: f32,
+}
+
+impl LanczosFilter {
+    pub fn new(radius: f32) -> Self {
+        return Self { radius };
+    }
+
+    fn lanczos1D(&self, x: f32) -> f32 {
+        const LOBES: f32 = 3.0;
+        let x: f32 = x.abs() / self.radius * LOBES;
+        if x >= LOBES {
+            return 0.0;
+        }
+        let sinc = |x: f32| if x < 1e-5 { 1.0 } else { (PI * x).sin() / (PI * x) };
+        return sinc(x) * sinc(x / LOBES);
+    }
+}
+
+impl Filter for LanczosFilter {
+    fn name(&self) -> &'static str {
+        return "lanczos";
+    }
+
+    fn radius(&self) -> f32 {
+        return self.radius;
+    }
+
+    fn evaluate(&self, x: f32, y: f32) -> f32 {
+        return self.lanczos1D(x) * self.lanczos1D(y);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filtersVanishAtTheirRadius() {
+        for name in FILTER_NAMES {
+            for radius in [None, Some(0.75), Some(3.0)] {
+                let filter: Rc<dyn Filter> = createFilter(name, radius).unwrap();
+                let r: f32 = filter.radius();
+                assert_eq!(filter.name(), name);
+                assert!(filter.evaluate(0.0, 0.0) > 0.0, "{} is not positive at its center", name);
+
+                // The box is half open, so only its far side is excluded at the radius itself
+                let edge: f32 = if name == "box" { -r } else { r };
+                for (x, y) in [(edge, 0.0), (0.0, edge), (r + 0.01, 0.0), (0.0, -r - 0.01), (r * 1.5, r * 1.5), (-r * 3.0, 0.0)] {
+                    assert_eq!(filter.evaluate(x, y), 0.0, "{} of radius {} is not zero at ({}, {})", name, r, x, y);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn badNamesAndRadiiAreRejected() {
+        assert!(createFilter("sinc", None).is_err());
+        for radius in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+            assert!(createFilter("gaussian", Some(radius)).is_err());
+        }
+    }
+}
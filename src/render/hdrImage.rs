@@ -0,0 +1,186 @@
+use std::fs;
+use glm::{Vec3, vec3};
+
+/* Floating point image read from a Radiance .hdr (RGBE) file */
+pub struct HdrImage {
+    width: usize,
+    height: usize,
+    pixels: Vec<Vec3>,
+}
+
+impl HdrImage {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let bytes: Vec<u8> = fs::read(path).map_err(|e| format!("cannot read '{}': {}", path, e))?;
+        return HdrImage::parse(&bytes).map_err(|e| format!("{}: {}", path, e));
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        let mut position: usize = 0;
+        let readLine = |position: &mut usize| -> Option<String> {
+            if *position >= bytes.len() {
+                return None;
+            }
+            let start: usize = *position;
+            while *position < bytes.len() && bytes[*position] != b'\n' {
+                *position += 1;
+            }
+            let line: String = String::from_utf8_lossy(&bytes[start..*position]).trim_end().to_string();
+            *position += 1;
+            return Some(line);
+        };
+
+        let magic: String = readLine(&mut position).ok_or("empty file")?;
+        if !magic.starts_with("#?") {
+            return Err("not a Radiance HDR file".to_string());
+        }
+
+        // Header lines up to the empty one, only the RGBE format is supported
+        loop {
+            let line: String = readLine(&mut position).ok_or("unexpected end of header")?;
+            if line.is_empty() {
+                break;
+            }
+            if line.starts_with("FORMAT=") && line != "FORMAT=32-bit_rle_rgbe" {
+                return Err(format!("unsupported format '{}'", &line[7..]));
+            }
+        }
+
+        let resolution: String = readLine(&mut position).ok_or("missing resolution")?;
+        let parts: Vec<&str> = resolution.split_whitespace().collect();
+        if parts.len() != 4 || parts[0] != "-Y" || parts[2] != "+X" {
+            return Err(format!("unsupported orientation '{}'", resolution));
+        }
+        let height: usize = parts[1].parse::<usize>().map_err(|_| "bad height")?;
+        let width: usize = parts[3].parse::<usize>().map_err(|_| "bad width")?;
+        if width == 0 || height == 0 {
+            return Err(format!("empty image of {}x{} pixels", width, height));
+        }
+
+        let mut pixels: Vec<Vec3> = Vec::with_capacity(width * height);
+        let mut scanline: Vec<[u8; 4]> = vec![[0; 4]; width];
+        for _ in 0..height {
+            position = HdrImage::readScanline(bytes, position, &mut scanline)?;
+            pixels.extend(scanline.iter().map(HdrImage::rgbeToFloat));
+        }
+
+        return Ok(Self {
+            width,
+            height,
+            pixels,
+        });
+    }
+
+    /* Reads one scanline, either run-length encoded per channel or as flat RGBE quadruples, returns the new position */
+    fn readScanline(bytes: &[u8], start: usize, scanline: &mut [[u8; 4]]) -> Result<usize, String> {
+        let width: usize = scanline.len();
+        let mut position: usize = start;
+        let truncated = || "truncated pixel data".to_string();
+
+        let isRunLength: bool = (8..0x8000).contains(&width) && position + 4 <= bytes.len()
+            && bytes[position] == 2 && bytes[position + 1] == 2 && bytes[position + 2] & 0x80 == 0;
+        if !isRunLength {
+            for pixel in scanline.iter_mut() {
+                if position + 4 > bytes.len() {
+                    return Err(truncated());
+                }
+                pixel.copy_from_slice(&bytes[position..position + 4]);
+                position += 4;
+            }
+            return Ok(position);
+        }
+
+        if ((bytes[position + 2] as usize) << 8 | bytes[position + 3] as usize) != width {
+            return Err("scanline width mismatch".to_string());
+        }
+        position += 4;
+
+        for channel in 0..4 {
+            let mut x: usize = 0;
+            while x < width {
+                let count: usize = *bytes.get(position).ok_or_else(truncated)? as usize;
+                position += 1;
+                if count > 128 {
+                    let run: usize = count - 128;
+                    let value: u8 = *bytes.get(position).ok_or_else(truncated)?;
+                    position += 1;
+                    if run == 0 || x + run > width {
+                        return Err("bad run length".to_string());
+                    }
+                    for pixel in scanline[x..x + run].iter_mut() {
+                        pixel[channel] = value;
+                    }
+                    x += run;
+                } else {
+                    if count == 0 || x + count > width || position + count > bytes.len() {
+                        return Err("bad run length".to_string());
+                    }
+                    for (pixel, byte) in scanline[x..x + count].iter_mut().zip(&bytes[position..position + count]) {
+                        pixel[channel] = *byte;
+                    }
+                    x += count;
+                    position += count;
+                }
+            }
+        }
+
+        return Ok(position);
+    }
+
+    fn rgbeToFloat(rgbe: &[u8; 4]) -> Vec3 {
+        if rgbe[3] == 0 {
+            return vec3(0.0, 0.0, 0.0);
+        }
+        let scale: f32 = 2.0_f32.powi(rgbe[3] as i32 - 136);
+        return vec3(rgbe[0] as f32 * scale, rgbe[1] as f32 * scale, rgbe[2] as f32 * scale);
+    }
+
+    pub fn getWidth(&self) -> usize {
+        return self.width;
+    }
+
+    pub fn getHeight(&self) -> usize {
+        return self.height;
+    }
+
+    pub fn texel(&self, x: usize, y: usize) -> Vec3 {
+        return self.pixels[y * self.width + x];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(resolution: &str, pixels: &[u8]) -> Vec<u8> {
+        let mut bytes: Vec<u8> = format!("#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n{}\n", resolution).into_bytes();
+        bytes.extend_from_slice(pixels);
+        return bytes;
+    }
+
+    #[test]
+    fn flatPixelsAreDecoded() {
+        let image: HdrImage = HdrImage::parse(&file("-Y 1 +X 2", &[128, 64, 0, 129, 0, 0, 0, 0])).unwrap();
+        assert_eq!((image.getWidth(), image.getHeight()), (2, 1));
+        assert_eq!(image.texel(0, 0), vec3(1.0, 0.5, 0.0));
+        assert_eq!(image.texel(1, 0), vec3(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn runLengthScanlinesAreDecoded() {
+        // Red as literal bytes, green as one run, blue as a run then literal bytes, the exponent as one run
+        let pixels: [u8; 24] = [2, 2, 0, 8,
+                                8, 128, 128, 128, 128, 0, 0, 0, 0,
+                                136, 64,
+                                132, 0, 4, 128, 128, 128, 128,
+                                136, 129];
+        let image: HdrImage = HdrImage::parse(&file("-Y 1 +X 8", &pixels)).unwrap();
+        assert_eq!(image.texel(0, 0), vec3(1.0, 0.5, 0.0));
+        assert_eq!(image.texel(7, 0), vec3(0.0, 0.5, 1.0));
+    }
+
+    #[test]
+    fn emptyImagesAreRejected() {
+        assert!(HdrImage::parse(&file("-Y 0 +X 4", &[])).is_err());
+        assert!(HdrImage::parse(&file("-Y 4 +X 0", &[])).is_err());
+    }
+}
@@ -0,0 +1,69 @@
+use glm::{Vec3, vec3, vec4};
+use crate::math::ray::Ray;
+use crate::math::vColor::VColor;
+use crate::render::aov::LightLayers;
+use crate::render::bdpt::BdptIntegrator;
+use crate::render::debugIntegrators::{AlbedoIntegrator, AmbientOcclusionIntegrator, BounceCountIntegrator, DepthIntegrator, NormalIntegrator, ObjectIdIntegrator, UvIntegrator};
+use crate::render::film::Film;
+use crate::render::mltIntegrator::{DEFAULT_BOOTSTRAP_SAMPLES, DEFAULT_CHAIN_COUNT, DEFAULT_LARGE_STEP_PROBABILITY, DEFAULT_MUTATION_SIGMA, MltIntegrator};
+use crate::render::pathIntegrator::PathIntegrator;
+use crate::render::photonIntegrator::{DEFAULT_PHOTON_COUNT, DEFAULT_PHOTON_RADIUS, DEFAULT_RADIUS_REDUCTION, PhotonIntegrator};
+use crate::render::scene::Scene;
+
+/* Computes what a camera ray sees, the renderer only averages the results over the samples of a pixel */
+pub trait Integrator {
+    /* Name `createIntegrator` knows it by */
+    fn name(&self) -> &'static str;
+
+    /* `maxDepth` is the number of times a path may bounce before it is cut off. Light that reaches the camera
+       through other pixels than the ray's is splatted onto `film` */
+    fn radiance(&self, scene: &Scene, ray: &Ray, maxDepth: i16, film: &mut Film) -> VColor;
+
+    /* Called before each pass over the image, a pass takes one sample in every pixel. Integrators that trace light
+       from the lights ahead of the camera do it here */
+    fn beginPass(&mut self, _scene: &Scene, _maxDepth: i16) {}
+
+    /* `radiance` with the light split into `layers` as well. Integrators that cannot tell where their light comes
+       from leave the layers black */
+    fn radianceLayers(&self, scene: &Scene, ray: &Ray, maxDepth: i16, film: &mut Film, _layers: &mut LightLayers) -> VColor {
+        return self.radiance(scene, ray, maxDepth, film);
+    }
+}
+
+/* Names accepted on the command line and by the scene file's `integrator` statement */
+pub const INTEGRATOR_NAMES: [&str; 12] = ["path", "bdpt", "photons", "sppm", "mlt", "normals", "depth", "albedo", "uv", "objectid", "bounces", "ao"];
+
+/* Integrator by name with its default settings */
+pub fn createIntegrator(name: &str) -> Option<Box<dyn Integrator>> {
+    let integrator: Box<dyn Integrator> = match name {
+        "path" => Box::new(PathIntegrator::new()),
+        "bdpt" => Box::new(BdptIntegrator::new()),
+        "photons" => Box::new(PhotonIntegrator::new(DEFAULT_PHOTON_COUNT, DEFAULT_PHOTON_RADIUS)),
+        "sppm" => Box::new(PhotonIntegrator::new_progressive(DEFAULT_PHOTON_COUNT, DEFAULT_PHOTON_RADIUS, DEFAULT_RADIUS_REDUCTION)),
+        "mlt" => Box::new(MltIntegrator::new(DEFAULT_BOOTSTRAP_SAMPLES, DEFAULT_CHAIN_COUNT, DEFAULT_LARGE_STEP_PROBABILITY, DEFAULT_MUTATION_SIGMA)),
+        "normals" => Box::new(NormalIntegrator::new()),
+        "depth" => Box::new(DepthIntegrator::new(50.0)),
+        "albedo" => Box::new(AlbedoIntegrator::new()),
+        "uv" => Box::new(UvIntegrator::new()),
+        "objectid" => Box::new(ObjectIdIntegrator::new()),
+        "bounces" => Box::new(BounceCountIntegrator::new()),
+        "ao" => Box::new(AmbientOcclusionIntegrator::new(16, 1.0)),
+        _ => return None,
+    };
+    return Some(integrator);
+}
+
+/* Maps [0, 1] to blue, cyan, green, yellow and red */
+pub fn heatmap(value: f32) -> VColor {
+    let t: f32 = value.clamp(0.0, 1.0) * 4.0;
+    let rgb: Vec3 = if t < 1.0 {
+        vec3(0.0, t, 1.0)
+    } else if t < 2.0 {
+        vec3(0.0, 1.0, 2.0 - t)
+    } else if t < 3.0 {
+        vec3(t - 2.0, 1.0, 0.0)
+    } else {
+        vec3(1.0, 4.0 - t, 0.0)
+    };
+    return VColor::new_vec4(&vec4(rgb.x, rgb.y, rgb.z, 1.0));
+}
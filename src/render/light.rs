@@ -0,0 +1,503 @@
+use std::f32::consts::PI;
+use glm::{dot, length, normalize, smoothstep, Vec2, Vec3, vec3, vec4};
+use crate::math::aabb::AABB;
+use crate::math::frame::Frame;
+use crate::math::mathUtils::randomVec2;
+use crate::math::ray::Ray;
+use crate::math::spectrum::luminance;
+use crate::math::vColor::VColor;
+use crate::math::warp::{cosineHemisphere, cosineHemispherePdf, uniformCone, uniformConePdf, uniformDisk, uniformSphere, uniformSpherePdf};
+
+/* Light arriving at a shaded point from one light, `direction` points from the point towards the light.
+   `radiance` is already divided by `pdf`, the density the direction was picked with, which stays zero for lights
+   that material sampling can never find and that are counted without MIS */
+pub struct LightSample {
+    pub direction: Vec3,
+    pub distance: f32,
+    pub radiance: VColor,
+    pub pdf: f32,
+    /* Surface normal at the sampled point, zero for lights without a surface */
+    pub normal: Vec3,
+}
+
+/* Ray leaving a light, for tracing paths from the lights. `radiance` is the emitted radiance, or the intensity of
+   point lights. The position density is per area (one for lights sitting at a point) and the direction density per
+   solid angle (one for lights shining in a single direction) */
+pub struct EmissionSample {
+    pub ray: Ray,
+    pub normal: Vec3,
+    pub radiance: Vec3,
+    pub pdfPosition: f32,
+    pub pdfDirection: f32,
+}
+
+/* Light sources sampled directly from every shaded point */
+pub trait Light {
+    fn illuminate(&self, point: &Vec3, sample: &mut LightSample) -> bool;
+
+    /* Density of `illuminate` picking `direction` from `point`, zero for lights that take no part in MIS */
+    fn pdf(&self, _point: &Vec3, _direction: &Vec3) -> f32 {
+        return 0.0;
+    }
+
+    /* Rough estimate of the emitted power, only used to decide how often the light gets picked. Lights at infinity
+       need the radius of the scene to turn their irradiance into power */
+    fn power(&self, sceneRadius: f32) -> f32;
+
+    /* Where the light sits, None for lights at infinity */
+    fn bounds(&self) -> Option<AABB> {
+        return None;
+    }
+
+    /* Starts a light path. Lights at infinity aim at the disk of `sceneRadius` around `sceneCenter` facing them */
+    fn sampleEmission(&self, _sceneCenter: &Vec3, _sceneRadius: f32, _sample: &mut EmissionSample) -> bool {
+        return false;
+    }
+
+    /* Position and direction densities of `sampleEmission` leaving `point` (with `normal`) along `direction` */
+    fn emissionPdf(&self, _point: &Vec3, _normal: &Vec3, _direction: &Vec3, _sceneRadius: f32) -> (f32, f32) {
+        return (0.0, 0.0);
+    }
+
+    /* Light from a single point, nothing can hit it */
+    fn isDeltaPosition(&self) -> bool {
+        return false;
+    }
+
+    /* Light along a single direction, it cannot be connected to */
+    fn isDeltaDirection(&self) -> bool {
+        return false;
+    }
+}
+
+/* Origin of a ray from infinity along `direction`, on the disk that covers the scene as seen from that direction */
+pub fn infiniteEmissionOrigin(direction: &Vec3, sceneCenter: &Vec3, sceneRadius: f32) -> Vec3 {
+    let frame: Frame = Frame::new(*sceneCenter, *direction);
+    let disk: Vec2 = uniformDisk(&randomVec2()) * sceneRadius;
+    return frame.pointToWorld(&vec3(disk.x, -sceneRadius, disk.y));
+}
+
+pub fn infiniteEmissionPdf(sceneRadius: f32) -> f32 {
+    return 1.0 / (PI * sceneRadius * sceneRadius);
+}
+
+/* Light color scaled by the intensity, the alpha is left at zero so sums of light stay out of the coverage */
+fn scaledRadiance(color: &VColor, intensity: f32) -> VColor {
+    let rgb: Vec3 = color.getColorRGB() * intensity;
+    return VColor::new_vec4(&vec4(rgb.x, rgb.y, rgb.z, 0.0));
+}
+
+pub struct PointLight {
+    position: Vec3,
+    color: VColor,
+    intensity: f32,
+}
+
+impl PointLight {
+    pub fn new(position: Vec3, color: VColor, intensity: f32) -> Self {
+        return Self {
+            position,
+            color,
+            intensity,
+        };
+    }
+}
+
+impl Light for PointLight {
+    fn illuminate(&self, point: &Vec3, sample: &mut LightSample) -> bool {
+        let toLight: Vec3 = self.position - *point;
+        let distance: f32 = length(toLight);
+        if distance <= 0.0 {
+            return false;
+        }
+
+        sample.direction = toLight / distance;
+        sample.distance = distance;
+        sample.radiance = scaledRadiance(&self.color, self.intensity / (distance * distance));
+        return true;
+    }
+
+    fn power(&self, _sceneRadius: f32) -> f32 {
+        return 4.0 * PI * self.intensity * luminance(&self.color.getColorRGB());
+    }
+
+    fn bounds(&self) -> Option<AABB> {
+        return Some(AABB::new(self.position, self.position));
+    }
+
+    fn sampleEmission(&self, _sceneCenter: &Vec3, _sceneRadius: f32, sample: &mut EmissionSample) -> bool {
+        sample.ray = Ray::new(self.position, uniformSphere(&randomVec2()));
+        sample.normal = vec3(0.0, 0.0, 0.0);
+        sample.radiance = self.color.getColorRGB() * self.intensity;
+        sample.pdfPosition = 1.0;
+        sample.pdfDirection = uniformSpherePdf();
+        return true;
+    }
+
+    fn emissionPdf(&self, _point: &Vec3, _normal: &Vec3, _direction: &Vec3, _sceneRadius: f32) -> (f32, f32) {
+        return (0.0, uniformSpherePdf());
+    }
+
+    fn isDeltaPosition(&self) -> bool {
+        return true;
+    }
+}
+
+/* Point light restricted to a cone, full strength inside the inner angle and fading out smoothly up to the outer one */
+pub struct SpotLight {
+    position: Vec3,
+    direction: Vec3,
+    color: VColor,
+    intensity: f32,
+    cosInner: f32,
+    cosOuter: f32,
+}
+
+impl SpotLight {
+    /* Angles are half angles of the cones in degrees */
+    pub fn new(position: Vec3, direction: Vec3, color: VColor, intensity: f32, innerAngle: f32, outerAngle: f32) -> Self {
+        let outer: f32 = f32::max(outerAngle, innerAngle);
+        return Self {
+            position,
+            direction: normalize(direction),
+            color,
+            intensity,
+            cosInner: innerAngle.to_radians().cos(),
+            cosOuter: outer.to_radians().cos(),
+        };
+    }
+
+    fn falloff(&self, cosAngle: f32) -> f32 {
+        if cosAngle >= self.cosInner {
+            return 1.0;
+        }
+        if cosAngle <= self.cosOuter {
+            return 0.0;
+        }
+        return smoothstep(self.cosOuter, self.cosInner, cosAngle);
+    }
+}
+
+impl Light for SpotLight {
+    fn illuminate(&self, point: &Vec3, sample: &mut LightSample) -> bool {
+        let toLight: Vec3 = self.position - *point;
+        let distance: f32 = length(toLight);
+        if distance <= 0.0 {
+            return false;
+        }
+
+        sample.direction = toLight / distance;
+        let falloff: f32 = self.falloff(dot(sample.direction * -1.0, self.direction));
+        if falloff <= 0.0 {
+            return false;
+        }
+
+        sample.distance = distance;
+        sample.radiance = scaledRadiance(&self.color, self.intensity * falloff / (distance * distance));
+        return true;
+    }
+
+    fn power(&self, _sceneRadius: f32) -> f32 {
+        return 2.0 * PI * (1.0 - 0.5 * (self.cosInner + self.cosOuter)) * self.intensity * luminance(&self.color.getColorRGB());
+    }
+
+    fn bounds(&self) -> Option<AABB> {
+        return Some(AABB::new(self.position, self.position));
+    }
+
+    /* Uniform over the outer cone, the falloff goes into the radiance */
+    fn sampleEmission(&self, _sceneCenter: &Vec3, _sceneRadius: f32, sample: &mut EmissionSample) -> bool {
+        let frame: Frame = Frame::new(self.position, self.direction);
+        let direction: Vec3 = frame.vectorToWorld(&uniformCone(&randomVec2(), self.cosOuter));
+        let falloff: f32 = self.falloff(dot(direction, self.direction));
+        if falloff <= 0.0 {
+            return false;
+        }
+
+        sample.ray = Ray::new(self.position, direction);
+        sample.normal = vec3(0.0, 0.0, 0.0);
+        sample.radiance = self.color.getColorRGB() * (self.intensity * falloff);
+        sample.pdfPosition = 1.0;
+        sample.pdfDirection = uniformConePdf(self.cosOuter);
+        return true;
+    }
+
+    fn emissionPdf(&self, _point: &Vec3, _normal: &Vec3, direction: &Vec3, _sceneRadius: f32) -> (f32, f32) {
+        if dot(normalize(*direction), self.direction) < self.cosOuter {
+            return (0.0, 0.0);
+        }
+        return (0.0, uniformConePdf(self.cosOuter));
+    }
+
+    fn isDeltaPosition(&self) -> bool {
+        return true;
+    }
+}
+
+/* Light from infinitely far away along one direction, like the sun */
+pub struct DirectionalLight {
+    direction: Vec3,
+    color: VColor,
+    intensity: f32,
+}
+
+impl DirectionalLight {
+    /* The direction is the one the light travels in */
+    pub fn new(direction: Vec3, color: VColor, intensity: f32) -> Self {
+        return Self {
+            direction: normalize(direction),
+            color,
+            intensity,
+        };
+    }
+}
+
+impl Light for DirectionalLight {
+    fn illuminate(&self, _point: &Vec3, sample: &mut LightSample) -> bool {
+        sample.direction = self.direction * -1.0;
+        sample.distance = f32::MAX;
+        sample.radiance = scaledRadiance(&self.color, self.intensity);
+        return true;
+    }
+
+    fn power(&self, sceneRadius: f32) -> f32 {
+        return PI * sceneRadius * sceneRadius * self.intensity * luminance(&self.color.getColorRGB());
+    }
+
+    fn sampleEmission(&self, sceneCenter: &Vec3, sceneRadius: f32, sample: &mut EmissionSample) -> bool {
+        sample.ray = Ray::new(infiniteEmissionOrigin(&self.direction, sceneCenter, sceneRadius), self.direction);
+        sample.normal = self.direction;
+        sample.radiance = self.color.getColorRGB() * self.intensity;
+        sample.pdfPosition = infiniteEmissionPdf(sceneRadius);
+        sample.pdfDirection = 1.0;
+        return true;
+    }
+
+    fn emissionPdf(&self, _point: &Vec3, _normal: &Vec3, _direction: &Vec3, sceneRadius: f32) -> (f32, f32) {
+        return (infiniteEmissionPdf(sceneRadius), 0.0);
+    }
+
+    fn isDeltaDirection(&self) -> bool {
+        return true;
+    }
+}
+
+/* Sphere emitting the same radiance everywhere on its surface, sampled over the cone it covers as seen from the
+   shaded point. The sphere itself has to be in the scene too, with a matching DiffuseLight material */
+pub struct SphereLight {
+    center: Vec3,
+    radius: f32,
+    radiance: Vec3,
+}
+
+impl SphereLight {
+    pub fn new(center: Vec3, radius: f32, radiance: Vec3) -> Self {
+        return Self {
+            center,
+            radius,
+            radiance,
+        };
+    }
+
+    /* Cosine of the half angle of the cone and the density of uniform directions in it, None from inside the sphere */
+    fn cone(&self, point: &Vec3) -> Option<(f32, f32)> {
+        let distanceSquared: f32 = dot(self.center - *point, self.center - *point);
+        let ratio: f32 = self.radius * self.radius / distanceSquared;
+        if ratio.is_nan() || ratio >= 1.0 {
+            return None;
+        }
+        let cosMax: f32 = (1.0 - ratio).sqrt();
+        // One minus the cosine without the cancellation that turns it into zero for small or distant spheres
+        let oneMinusCosMax: f32 = ratio / (1.0 + cosMax);
+        return Some((cosMax, 1.0 / (2.0 * PI * oneMinusCosMax)));
+    }
+}
+
+impl Light for SphereLight {
+    fn illuminate(&self, point: &Vec3, sample: &mut LightSample) -> bool {
+        let (cosMax, pdf) = match self.cone(point) {
+            Some(cone) => cone,
+            None => return false,
+        };
+
+        let frame: Frame = Frame::new(*point, self.center - *point);
+        sample.direction = frame.vectorToWorld(&uniformCone(&randomVec2(), cosMax));
+
+        // Nearest crossing of the sphere along the sampled direction, clamped for directions grazing its silhouette.
+        // The miss distance is measured directly, subtracting squared distances loses the radius far from the sphere
+        let toCenter: Vec3 = self.center - *point;
+        let along: f32 = dot(toCenter, sample.direction);
+        let offAxis: Vec3 = toCenter - sample.direction * along;
+        let discriminant: f32 = f32::max(self.radius * self.radius - dot(offAxis, offAxis), 0.0);
+        sample.distance = along - discriminant.sqrt();
+        sample.normal = normalize(*point + sample.direction * sample.distance - self.center);
+
+        sample.pdf = pdf;
+        let value: Vec3 = self.radiance / sample.pdf;
+        sample.radiance = VColor::new_vec4(&vec4(value.x, value.y, value.z, 0.0));
+        return true;
+    }
+
+    fn pdf(&self, point: &Vec3, direction: &Vec3) -> f32 {
+        let (cosMax, pdf) = match self.cone(point) {
+            Some(cone) => cone,
+            None => return 0.0,
+        };
+        if dot(normalize(*direction), normalize(self.center - *point)) < cosMax {
+            return 0.0;
+        }
+        return pdf;
+    }
+
+    fn power(&self, _sceneRadius: f32) -> f32 {
+        return luminance(&self.radiance) * PI * 4.0 * PI * self.radius * self.radius;
+    }
+
+    fn bounds(&self) -> Option<AABB> {
+        let extent: Vec3 = vec3(self.radius, self.radius, self.radius);
+        return Some(AABB::new(self.center - extent, self.center + extent));
+    }
+
+    /* Uniform point on the surface, leaving it with a cosine weighted direction */
+    fn sampleEmission(&self, _sceneCenter: &Vec3, _sceneRadius: f32, sample: &mut EmissionSample) -> bool {
+        let normal: Vec3 = uniformSphere(&randomVec2());
+        let local: Vec3 = cosineHemisphere(&randomVec2());
+        let frame: Frame = Frame::new(self.center + normal * self.radius, normal);
+
+        sample.ray = Ray::new(frame.origin, frame.vectorToWorld(&local));
+        sample.normal = normal;
+        sample.radiance = self.radiance;
+        sample.pdfPosition = 1.0 / (4.0 * PI * self.radius * self.radius);
+        sample.pdfDirection = cosineHemispherePdf(local.y);
+        return sample.pdfDirection > 0.0;
+    }
+
+    fn emissionPdf(&self, _point: &Vec3, normal: &Vec3, direction: &Vec3, _sceneRadius: f32) -> (f32, f32) {
+        return (1.0 / (4.0 * PI * self.radius * self.radius), cosineHemispherePdf(dot(*normal, normalize(*direction))));
+    }
+}
+
+impl Default for LightSample {
+    fn default() -> Self {
+        return Self {
+            direction: vec3(0.0, 0.0, 0.0),
+            distance: 0.0,
+            radiance: VColor::default(),
+            pdf: 0.0,
+            normal: vec3(0.0, 0.0, 0.0),
+        };
+    }
+}
+
+impl Default for EmissionSample {
+    fn default() -> Self {
+        return Self {
+            ray: Ray::default(),
+            normal: vec3(0.0, 0.0, 0.0),
+            radiance: vec3(0.0, 0.0, 0.0),
+            pdfPosition: 0.0,
+            pdfDirection: 0.0,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glm::vec2;
+    use super::*;
+
+    fn assertClose(a: f32, b: f32, tolerance: f32) {
+        assert!((a - b).abs() <= tolerance * f32::max(b.abs(), 1.0), "{} is not {}", a, b);
+    }
+
+    fn white() -> VColor {
+        return VColor::new_rgb(1.0, 1.0, 1.0);
+    }
+
+    #[test]
+    fn deltaLightsFallOffWithoutAPdf() {
+        let point: PointLight = PointLight::new(vec3(0.0, 0.0, 0.0), white(), 4.0);
+        let mut sample: LightSample = LightSample::default();
+        assert!(point.illuminate(&vec3(0.0, 0.0, 2.0), &mut sample));
+        assertClose(sample.radiance.getColorRGB().x, 1.0, 1e-5);
+        assertClose(sample.direction.z, -1.0, 1e-5);
+        assert_eq!(sample.pdf, 0.0);
+        assert_eq!(point.pdf(&vec3(0.0, 0.0, 2.0), &sample.direction), 0.0);
+
+        let spot: SpotLight = SpotLight::new(vec3(0.0, 0.0, 0.0), vec3(0.0, 0.0, 1.0), white(), 4.0, 10.0, 20.0);
+        let mut sample: LightSample = LightSample::default();
+        assert!(spot.illuminate(&vec3(0.0, 0.0, 2.0), &mut sample));
+        assertClose(sample.radiance.getColorRGB().x, 1.0, 1e-5);
+        assert_eq!(sample.pdf, 0.0);
+        assert!(!spot.illuminate(&vec3(2.0, 0.0, 2.0), &mut LightSample::default()));
+
+        let directional: DirectionalLight = DirectionalLight::new(vec3(0.0, -1.0, 0.0), white(), 2.0);
+        let mut sample: LightSample = LightSample::default();
+        assert!(directional.illuminate(&vec3(5.0, 0.0, 0.0), &mut sample));
+        assertClose(sample.direction.y, 1.0, 1e-5);
+        assert_eq!(sample.pdf, 0.0);
+        assert_eq!(directional.pdf(&vec3(5.0, 0.0, 0.0), &sample.direction), 0.0);
+    }
+
+    #[test]
+    fn spherePdfMatchesItsSamples() {
+        let light: SphereLight = SphereLight::new(vec3(0.0, 0.0, 5.0), 1.0, vec3(2.0, 2.0, 2.0));
+        let point: Vec3 = vec3(0.5, -0.5, 0.0);
+        for _ in 0..1000 {
+            let mut sample: LightSample = LightSample::default();
+            assert!(light.illuminate(&point, &mut sample));
+            assertClose(light.pdf(&point, &sample.direction), sample.pdf, 1e-4);
+            assertClose(length(point + sample.direction * sample.distance - light.center), light.radius, 1e-3);
+            assertClose(sample.radiance.getColorRGB().x * sample.pdf, 2.0, 1e-4);
+        }
+
+        // Inside the sphere there is no cone to sample
+        assert!(!light.illuminate(&vec3(0.0, 0.0, 5.5), &mut LightSample::default()));
+        assert_eq!(light.pdf(&vec3(0.0, 0.0, 5.5), &vec3(0.0, 0.0, 1.0)), 0.0);
+    }
+
+    #[test]
+    fn spherePdfIntegratesToOne() {
+        let light: SphereLight = SphereLight::new(vec3(0.0, 2.0, 0.0), 1.0, vec3(1.0, 1.0, 1.0));
+        let point: Vec3 = vec3(0.0, 0.0, 0.0);
+        let steps: usize = 400;
+        let mut integral: f32 = 0.0;
+        for i in 0..steps {
+            for j in 0..steps {
+                let u: Vec2 = vec2((i as f32 + 0.5) / steps as f32, (j as f32 + 0.5) / steps as f32);
+                integral += light.pdf(&point, &uniformSphere(&u)) / uniformSpherePdf();
+            }
+        }
+        assertClose(integral / (steps * steps) as f32, 1.0, 0.02);
+    }
+
+    #[test]
+    fn emissionPdfsMatchTheirSamples() {
+        let center: Vec3 = vec3(0.0, 0.0, 0.0);
+        let lights: Vec<Box<dyn Light>> = vec![
+            Box::new(PointLight::new(vec3(0.0, 1.0, 0.0), white(), 1.0)),
+            Box::new(SpotLight::new(vec3(0.0, 1.0, 0.0), vec3(0.0, -1.0, 0.0), white(), 1.0, 15.0, 30.0)),
+            Box::new(SphereLight::new(vec3(0.0, 1.0, 0.0), 0.5, vec3(1.0, 1.0, 1.0))),
+        ];
+        for light in lights.iter() {
+            for _ in 0..100 {
+                let mut sample: EmissionSample = EmissionSample::default();
+                if !light.sampleEmission(&center, 10.0, &mut sample) {
+                    continue;
+                }
+                let (pdfPosition, pdfDirection) = light.emissionPdf(&sample.ray.origin, &sample.normal, &sample.ray.direction, 10.0);
+                assertClose(pdfDirection, sample.pdfDirection, 1e-4);
+                if !light.isDeltaPosition() {
+                    assertClose(pdfPosition, sample.pdfPosition, 1e-4);
+                }
+            }
+        }
+
+        let directional: DirectionalLight = DirectionalLight::new(vec3(1.0, -1.0, 0.0), white(), 1.0);
+        let mut sample: EmissionSample = EmissionSample::default();
+        assert!(directional.sampleEmission(&center, 10.0, &mut sample));
+        let (pdfPosition, _) = directional.emissionPdf(&sample.ray.origin, &sample.normal, &sample.ray.direction, 10.0);
+        assertClose(pdfPosition, sample.pdfPosition, 1e-4);
+        assert!(length(sample.ray.origin - center) <= 10.0 * 2.0f32.sqrt() + 1e-3);
+    }
+}
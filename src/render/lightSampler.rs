@@ -0,0 +1,252 @@
+use glm::{dot, Vec3};
+use crate::math::aabb::AABB;
+use crate::math::distribution::Distribution1D;
+use crate::render::light::Light;
+
+/* How the direct lighting step picks the one light it samples at a shaded point */
+pub enum LightSampling {
+    Uniform,
+    /* In proportion to the emitted power, wherever the point is */
+    Power,
+    /* Walks a BVH over the lights, favoring bright lights close to the point */
+    Bvh,
+}
+
+pub trait LightSampler {
+    /* Index of the chosen light and the probability of choosing it, None without lights */
+    fn sample(&self, point: &Vec3, u: f32) -> Option<(usize, f32)>;
+
+    /* Probability that `sample` chooses the light from the point */
+    fn pmf(&self, point: &Vec3, index: usize) -> f32;
+}
+
+pub fn createLightSampler(sampling: &LightSampling, lights: &[Box<dyn Light>], sceneRadius: f32) -> Box<dyn LightSampler> {
+    return match sampling {
+        LightSampling::Uniform => Box::new(UniformLightSampler::new(lights.len())),
+        LightSampling::Power => Box::new(PowerLightSampler::new(lights, sceneRadius)),
+        LightSampling::Bvh => Box::new(LightBvh::new(lights, sceneRadius)),
+    };
+}
+
+pub struct UniformLightSampler {
+    count: usize,
+}
+
+impl UniformLightSampler {
+    pub fn new(count: usize) -> Self {
+        return Self { count };
+    }
+}
+
+impl LightSampler for UniformLightSampler {
+    fn sample(&self, _point: &Vec3, u: f32) -> Option<(usize, f32)> {
+        if self.count == 0 {
+            return None;
+        }
+        let index: usize = usize::min((u * self.count as f32) as usize, self.count - 1);
+        return Some((index, 1.0 / self.count as f32));
+    }
+
+    fn pmf(&self, _point: &Vec3, index: usize) -> f32 {
+        if index >= self.count {
+            return 0.0;
+        }
+        return 1.0 / self.count as f32;
+    }
+}
+
+pub struct PowerLightSampler {
+    distribution: Option<Distribution1D>,
+}
+
+impl PowerLightSampler {
+    pub fn new(lights: &[Box<dyn Light>], sceneRadius: f32) -> Self {
+        if lights.is_empty() {
+            return Self { distribution: None };
+        }
+        let powers: Vec<f32> = lights.iter().map(|light| light.power(sceneRadius)).collect();
+        return Self {
+            distribution: Some(Distribution1D::new(&powers)),
+        };
+    }
+}
+
+impl LightSampler for PowerLightSampler {
+    fn sample(&self, _point: &Vec3, u: f32) -> Option<(usize, f32)> {
+        return self.distribution.as_ref().map(|distribution| distribution.sampleDiscrete(u));
+    }
+
+    fn pmf(&self, _point: &Vec3, index: usize) -> f32 {
+        return match &self.distribution {
+            Some(distribution) if index < distribution.count() => distribution.probability(index),
+            _ => 0.0,
+        };
+    }
+}
+
+enum LightBvhContent {
+    Leaf { light: usize },
+    Interior { left: usize, right: usize },
+}
+
+struct LightBvhNode {
+    bounds: AABB,
+    power: f32,
+    parent: Option<usize>,
+    content: LightBvhContent,
+}
+
+/* Lights with a position go into a BVH whose nodes know the total power below them. Sampling descends towards the
+   child that seems to matter more for the point, lights at infinity get a fixed share next to the whole tree */
+pub struct LightBvh {
+    nodes: Vec<LightBvhNode>,
+    infiniteLights: Vec<usize>,
+    /* Leaf node of every light in the tree */
+    leafOfLight: Vec<Option<usize>>,
+}
+
+impl LightBvh {
+    pub fn new(lights: &[Box<dyn Light>], sceneRadius: f32) -> Self {
+        let mut bvh: Self = Self {
+            nodes: vec![],
+            infiniteLights: vec![],
+            leafOfLight: vec![None; lights.len()],
+        };
+
+        let mut bounded: Vec<(usize, AABB, f32)> = vec![];
+        for (index, light) in lights.iter().enumerate() {
+            match light.bounds() {
+                Some(bounds) => bounded.push((index, bounds, light.power(sceneRadius))),
+                None => bvh.infiniteLights.push(index),
+            }
+        }
+
+        if !bounded.is_empty() {
+            bvh.build(&mut bounded, None);
+        }
+        return bvh;
+    }
+
+    fn build(&mut self, lights: &mut [(usize, AABB, f32)], parent: Option<usize>) -> usize {
+        let nodeIndex: usize = self.nodes.len();
+
+        if lights.len() == 1 {
+            let (light, bounds, power) = (lights[0].0, lights[0].1.clone(), lights[0].2);
+            self.nodes.push(LightBvhNode { bounds, power, parent, content: LightBvhContent::Leaf { light } });
+            self.leafOfLight[light] = Some(nodeIndex);
+            return nodeIndex;
+        }
+
+        // Median split along the longest extent of the centroids
+        let centroids: Vec<Vec3> = lights.iter().map(|light| light.1.centroid()).collect();
+        let extent: Vec3 = AABB::new_from_points(&centroids).extent();
+        let axis: usize = if extent.x > extent.y && extent.x > extent.z { 0 } else if extent.y > extent.z { 1 } else { 2 };
+        lights.sort_by(|a, b| a.1.centroid()[axis].partial_cmp(&b.1.centroid()[axis]).unwrap_or(std::cmp::Ordering::Equal));
+
+        // Placeholder until both children exist
+        self.nodes.push(LightBvhNode {
+            bounds: AABB::empty(),
+            power: 0.0,
+            parent,
+            content: LightBvhContent::Leaf { light: 0 },
+        });
+
+        let middle: usize = lights.len() / 2;
+        let (first, second) = lights.split_at_mut(middle);
+        let left: usize = self.build(first, Some(nodeIndex));
+        let right: usize = self.build(second, Some(nodeIndex));
+
+        self.nodes[nodeIndex].bounds = AABB::surroundingBox(&self.nodes[left].bounds, &self.nodes[right].bounds);
+        self.nodes[nodeIndex].power = self.nodes[left].power + self.nodes[right].power;
+        self.nodes[nodeIndex].content = LightBvhContent::Interior { left, right };
+        return nodeIndex;
+    }
+
+    /* Power over squared distance, never closer than the node's own size so that the point inside a node does not
+       blow it up */
+    fn importance(&self, point: &Vec3, node: usize) -> f32 {
+        let bounds: &AABB = &self.nodes[node].bounds;
+        let toCenter: Vec3 = bounds.centroid() - *point;
+        let halfDiagonal: Vec3 = bounds.extent() * 0.5;
+        let distanceSquared: f32 = f32::max(dot(toCenter, toCenter), dot(halfDiagonal, halfDiagonal));
+        return self.nodes[node].power / f32::max(distanceSquared, 1e-8);
+    }
+
+    /* Probability of descending into `left` rather than `right` */
+    fn leftProbability(&self, point: &Vec3, left: usize, right: usize) -> f32 {
+        let leftImportance: f32 = self.importance(point, left);
+        let rightImportance: f32 = self.importance(point, right);
+        if leftImportance + rightImportance <= 0.0 {
+            return 0.5;
+        }
+        return leftImportance / (leftImportance + rightImportance);
+    }
+
+    fn infiniteProbability(&self) -> f32 {
+        let treeShare: f32 = if self.nodes.is_empty() { 0.0 } else { 1.0 };
+        let count: f32 = self.infiniteLights.len() as f32;
+        if count + treeShare == 0.0 {
+            return 0.0;
+        }
+        return count / (count + treeShare);
+    }
+}
+
+impl LightSampler for LightBvh {
+    fn sample(&self, point: &Vec3, u: f32) -> Option<(usize, f32)> {
+        let infiniteProbability: f32 = self.infiniteProbability();
+        let count: usize = self.infiniteLights.len();
+
+        if u < infiniteProbability || self.nodes.is_empty() {
+            if count == 0 {
+                return None;
+            }
+            let index: usize = usize::min((u / infiniteProbability * count as f32) as usize, count - 1);
+            return Some((self.infiniteLights[index], infiniteProbability / count as f32));
+        }
+
+        // Rescale the rest of u and reuse it for every decision on the way down
+        let mut remaining: f32 = f32::min((u - infiniteProbability) / (1.0 - infiniteProbability), 1.0 - f32::EPSILON);
+        let mut probability: f32 = 1.0 - infiniteProbability;
+        let mut node: usize = 0;
+        loop {
+            match self.nodes[node].content {
+                LightBvhContent::Leaf { light } => return Some((light, probability)),
+                LightBvhContent::Interior { left, right } => {
+                    let leftProbability: f32 = self.leftProbability(point, left, right);
+                    if remaining < leftProbability {
+                        remaining = f32::min(remaining / leftProbability, 1.0 - f32::EPSILON);
+                        probability *= leftProbability;
+                        node = left;
+                    } else {
+                        remaining = f32::min((remaining - leftProbability) / (1.0 - leftProbability), 1.0 - f32::EPSILON);
+                        probability *= 1.0 - leftProbability;
+                        node = right;
+                    }
+                }
+            }
+        }
+    }
+
+    fn pmf(&self, point: &Vec3, index: usize) -> f32 {
+        if self.infiniteLights.contains(&index) {
+            return self.infiniteProbability() / self.infiniteLights.len() as f32;
+        }
+
+        let mut node: usize = match self.leafOfLight.get(index) {
+            Some(Some(leaf)) => *leaf,
+            _ => return 0.0,
+        };
+
+        // Multiply the choices on the way back up to the root
+        let mut probability: f32 = 1.0 - self.infiniteProbability();
+        while let Some(parent) = self.nodes[node].parent {
+            if let LightBvhContent::Interior { left, right } = self.nodes[parent].content {
+                let leftProbability: f32 = self.leftProbability(point, left, right);
+                probability *= if node == left { leftProbability } else { 1.0 - leftProbability };
+            }
+            node = parent;
+        }
+        return probability;
+    }
+}
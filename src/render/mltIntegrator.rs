@@ -0,0 +1,289 @@
+use std::cell::{Cell, RefCell};
+use std::f32::consts::PI;
+use std::rc::Rc;
+use glm::{Vec3, vec4};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use crate::math::distribution::Distribution1D;
+use crate::math::mathUtils::{random01, SampleSource, setSampleSource};
+use crate::math::ray::Ray;
+use crate::math::spectrum::{luminance, sampleWavelength, wavelengthToRGB};
+use crate::math::vColor::VColor;
+use crate::render::film::Film;
+use crate::render::integrator::Integrator;
+use crate::render::pathIntegrator::PathIntegrator;
+use crate::render::scene::Scene;
+
+pub const DEFAULT_BOOTSTRAP_SAMPLES: usize = 100000;
+pub const DEFAULT_CHAIN_COUNT: usize = 1000;
+pub const DEFAULT_LARGE_STEP_PROBABILITY: f32 = 0.3;
+pub const DEFAULT_MUTATION_SIGMA: f32 = 0.01;
+
+/* One number of the primary sample vector, with what it was before the mutation under way */
+struct PrimarySample {
+    value: f32,
+    /* Iteration that last changed the value */
+    lastModification: i64,
+    valueBackup: f32,
+    modifyBackup: i64,
+}
+
+/* Primary sample vector of a Markov chain, after Kelemen et al. The numbers are mutated lazily as the path asks for
+   them: a large step draws them afresh, a small step moves each by a Gaussian offset that grows with the number
+   of iterations it missed */
+struct MltSampler {
+    rng: StdRng,
+    samples: Vec<PrimarySample>,
+    /* Next number handed out in the current iteration */
+    index: usize,
+    iteration: i64,
+    largeStep: bool,
+    lastLargeStepIteration: i64,
+    sigma: f32,
+    largeStepProbability: f32,
+}
+
+impl MltSampler {
+    /* Samplers made with the same seed hand out the same numbers until their first mutation */
+    fn new(seed: u64, sigma: f32, largeStepProbability: f32) -> Self {
+        return Self {
+            rng: StdRng::seed_from_u64(seed),
+            samples: vec![],
+            index: 0,
+            iteration: 0,
+            largeStep: true,
+            lastLargeStepIteration: 0,
+            sigma,
+            largeStepProbability,
+        };
+    }
+
+    fn startIteration(&mut self) {
+        self.iteration += 1;
+        self.largeStep = self.rng.gen_range(0.0..1.0) < self.largeStepProbability;
+        self.index = 0;
+    }
+
+    fn accept(&mut self) {
+        if self.largeStep {
+            self.lastLargeStepIteration = self.iteration;
+        }
+    }
+
+    /* Puts back the numbers the rejected iteration changed */
+    fn reject(&mut self) {
+        for sample in self.samples.iter_mut() {
+            if sample.lastModification == self.iteration {
+                sample.value = sample.valueBackup;
+                sample.lastModification = sample.modifyBackup;
+            }
+        }
+        self.iteration -= 1;
+    }
+
+    /* Brings the number at `index` up to the current iteration */
+    fn ensureReady(&mut self, index: usize) {
+        // Numbers the path never asked for before start out fresh, the rejection loops would never let go of a
+        // small step away from a fixed start
+        while index >= self.samples.len() {
+            let value: f32 = self.rng.gen_range(0.0..1.0);
+            self.samples.push(PrimarySample { value, lastModification: self.iteration, valueBackup: value, modifyBackup: self.iteration });
+        }
+
+        // A large step the number missed replaces it as a whole
+        if self.samples[index].lastModification < self.lastLargeStepIteration {
+            self.samples[index].value = self.rng.gen_range(0.0..1.0);
+            self.samples[index].lastModification = self.lastLargeStepIteration;
+        }
+
+        let sample: &mut PrimarySample = &mut self.samples[index];
+        sample.valueBackup = sample.value;
+        sample.modifyBackup = sample.lastModification;
+
+        if self.largeStep {
+            sample.value = self.rng.gen_range(0.0..1.0);
+        } else {
+            let smallSteps: i64 = self.iteration - sample.lastModification;
+            let sigma: f32 = self.sigma * (smallSteps as f32).sqrt();
+            // Box-Muller, the offset wraps around the unit interval
+            let u1: f32 = 1.0 - self.rng.gen_range(0.0..1.0f32);
+            let u2: f32 = self.rng.gen_range(0.0..1.0f32);
+            let normal: f32 = (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos();
+            sample.value += normal * sigma;
+            sample.value -= sample.value.floor();
+            sample.value = f32::min(sample.value, 1.0 - f32::EPSILON);
+        }
+        sample.lastModification = self.iteration;
+    }
+}
+
+impl SampleSource for MltSampler {
+    fn next(&mut self) -> f32 {
+        let index: usize = self.index;
+        self.ensureReady(index);
+        self.index += 1;
+        return self.samples[index].value;
+    }
+}
+
+/* State of a Markov chain: its sample vector and the path it currently sits on */
+struct Chain {
+    sampler: Rc<RefCell<MltSampler>>,
+    radiance: Vec3,
+    importance: f32,
+    u: f32,
+    v: f32,
+}
+
+/* Primary sample space Metropolis light transport. The path tracer runs on numbers from a sample vector that a
+   Markov chain mutates, so paths are visited in proportion to their brightness and a bright path that is hard to
+   find gets explored once it has been found. Each camera sample of the renderer advances one of the chains by one
+   mutation and splats both the proposal and the current path, weighted by the acceptance probability.
+   The image is scaled by the mean brightness of a bootstrap set of independent paths, which also picks where the
+   chains start */
+pub struct MltIntegrator {
+    path: PathIntegrator,
+    bootstrapSamples: usize,
+    chainCount: usize,
+    largeStepProbability: f32,
+    sigma: f32,
+
+    /* Mean brightness of the bootstrap paths */
+    normalization: Cell<f32>,
+    bootstrapped: Cell<bool>,
+    chains: RefCell<Vec<Chain>>,
+    nextChain: Cell<usize>,
+    /* Numbers for the chains' own decisions, where they start and whether they move, kept apart from the pixel
+       sampler so that they do not line up with the pixels' sample patterns */
+    rng: RefCell<StdRng>,
+}
+
+impl MltIntegrator {
+    pub fn new(bootstrapSamples: usize, chainCount: usize, largeStepProbability: f32, sigma: f32) -> Self {
+        return Self {
+            path: PathIntegrator::new(),
+            bootstrapSamples,
+            chainCount,
+            largeStepProbability,
+            sigma,
+            normalization: Cell::new(0.0),
+            bootstrapped: Cell::new(false),
+            chains: RefCell::new(vec![]),
+            nextChain: Cell::new(0),
+            rng: RefCell::new(StdRng::seed_from_u64(0)),
+        };
+    }
+
+    /* Traces the path that `sampler`'s numbers describe. Gives its color and the film position it lands on */
+    fn evaluate(&self, scene: &Scene, sampler: &Rc<RefCell<MltSampler>>, maxDepth: i16, spectral: bool, film: &mut Film) -> (Vec3, f32, f32) {
+        let previous: Option<Rc<RefCell<dyn SampleSource>>> = setSampleSource(Some(sampler.clone()));
+
+        let u: f32 = random01();
+        let v: f32 = random01();
+        let mut ray: Ray = scene.getCamera().createARay(u, v);
+        if spectral {
+            ray.wavelength = sampleWavelength();
+        }
+        let mut radiance: Vec3 = self.path.radiance(scene, &ray, maxDepth, film).getColorRGB();
+        if spectral {
+            radiance = radiance * wavelengthToRGB(ray.wavelength);
+        }
+
+        setSampleSource(previous);
+        return (radiance, u, v);
+    }
+
+    /* Brightness the chains sample paths by, zero for paths that cannot be used */
+    fn importance(radiance: &Vec3) -> f32 {
+        let value: f32 = luminance(radiance);
+        if !value.is_finite() || value < 0.0 {
+            return 0.0;
+        }
+        return value;
+    }
+
+    fn bootstrap(&self, scene: &Scene, maxDepth: i16, spectral: bool, film: &mut Film) {
+        self.bootstrapped.set(true);
+
+        let mut weights: Vec<f32> = Vec::with_capacity(self.bootstrapSamples);
+        for index in 0..self.bootstrapSamples {
+            let sampler: Rc<RefCell<MltSampler>> = Rc::new(RefCell::new(MltSampler::new(index as u64, self.sigma, self.largeStepProbability)));
+            let (radiance, _, _) = self.evaluate(scene, &sampler, maxDepth, spectral, film);
+            weights.push(MltIntegrator::importance(&radiance));
+        }
+        if weights.is_empty() {
+            return;
+        }
+
+        let distribution: Distribution1D = Distribution1D::new(&weights);
+        self.normalization.set(weights.iter().sum::<f32>() / weights.len() as f32);
+        if self.normalization.get() <= 0.0 {
+            return;
+        }
+
+        // A sampler seeded like a bootstrap path replays it, which starts the chain in proportion to its brightness
+        let mut chains: Vec<Chain> = Vec::with_capacity(self.chainCount);
+        for _ in 0..self.chainCount {
+            let (index, _) = distribution.sampleDiscrete(self.rng.borrow_mut().gen_range(0.0..1.0));
+            let sampler: Rc<RefCell<MltSampler>> = Rc::new(RefCell::new(MltSampler::new(index as u64, self.sigma, self.largeStepProbability)));
+            let (radiance, u, v) = self.evaluate(scene, &sampler, maxDepth, spectral, film);
+            chains.push(Chain {
+                sampler,
+                importance: MltIntegrator::importance(&radiance),
+                radiance,
+                u,
+                v,
+            });
+        }
+        *self.chains.borrow_mut() = chains;
+    }
+}
+
+impl Integrator for MltIntegrator {
+    fn name(&self) -> &'static str {
+        return "mlt";
+    }
+
+    /* The camera ray only tells whether the render is spectral, the chains pick their own film positions */
+    fn radiance(&self, scene: &Scene, ray: &Ray, maxDepth: i16, film: &mut Film) -> VColor {
+        let spectral: bool = ray.wavelength > 0.0;
+        if !self.bootstrapped.get() {
+            self.bootstrap(scene, maxDepth, spectral, film);
+        }
+
+        let mut chains = self.chains.borrow_mut();
+        if chains.is_empty() {
+            return VColor::new_vec4(&vec4(0.0, 0.0, 0.0, 1.0));
+        }
+        let index: usize = self.nextChain.get();
+        self.nextChain.set((index + 1) % chains.len());
+        let chain: &mut Chain = &mut chains[index];
+
+        chain.sampler.borrow_mut().startIteration();
+        let (radiance, u, v) = self.evaluate(scene, &chain.sampler, maxDepth, spectral, film);
+        let importance: f32 = MltIntegrator::importance(&radiance);
+
+        let acceptance: f32 = if chain.importance > 0.0 { f32::min(1.0, importance / chain.importance) } else { 1.0 };
+
+        // Both paths are splatted by how likely the chain is to end up on them
+        let normalization: f32 = self.normalization.get();
+        if importance > 0.0 {
+            film.addSplat(u, v, &(radiance * (acceptance * normalization / importance)));
+        }
+        if chain.importance > 0.0 {
+            film.addSplat(chain.u, chain.v, &(chain.radiance * ((1.0 - acceptance) * normalization / chain.importance)));
+        }
+
+        if self.rng.borrow_mut().gen_range(0.0..1.0f32) < acceptance {
+            chain.radiance = radiance;
+            chain.importance = importance;
+            chain.u = u;
+            chain.v = v;
+            chain.sampler.borrow_mut().accept();
+        } else {
+            chain.sampler.borrow_mut().reject();
+        }
+
+        return VColor::new_vec4(&vec4(0.0, 0.0, 0.0, 1.0));
+    }
+}
@@ -1,3 +1,21 @@
 ﻿pub mod scene;
 pub mod camera;
-pub mod renderer;
\ No newline at end of file
+pub mod renderer;
+pub mod sceneLoader;
+pub mod light;
+pub mod environment;
+pub mod hdrImage;
+pub mod lightSampler;
+pub mod integrator;
+pub mod pathIntegrator;
+pub mod debugIntegrators;
+pub mod film;
+pub mod bdpt;
+pub mod photonMap;
+pub mod photonIntegrator;
+pub mod mltIntegrator;
+pub mod sampler;
+pub mod denoiser;
+pub mod aov;
+pub mod exrImage;
+pub mod filter;
\ No newline at end of file
@@ -0,0 +1,171 @@
+use glm::{normalize, Vec3};
+use crate::math::mathUtils::{powerHeuristic, random01};
+use crate::math::ray::Ray;
+use crate::math::vColor::VColor;
+use crate::model::hitRecord::HitRecord;
+use crate::render::aov::LightLayers;
+use crate::render::film::Film;
+use crate::render::integrator::Integrator;
+use crate::render::light::LightSample;
+use crate::render::scene::Scene;
+
+/* Unidirectional path tracer with direct light sampling and MIS against material sampling */
+pub struct PathIntegrator {}
+
+impl PathIntegrator {
+    pub fn new() -> Self {
+        return Self {};
+    }
+
+    /* Light reaching the hit point straight from one of the scene's lights, picked by the scene's light sampler */
+    pub fn directLight(scene: &Scene, ray: &Ray, hitRecord: &HitRecord) -> VColor {
+        let (index, selectionProbability) = match scene.getLightSampler().sample(&hitRecord.point, random01()) {
+            Some(choice) => choice,
+            None => return VColor::default(),
+        };
+
+        let mut sample: LightSample = LightSample::default();
+        if selectionProbability <= 0.0 || !scene.getLights()[index].illuminate(&hitRecord.point, &mut sample) {
+            return VColor::default();
+        }
+
+        let value: VColor = hitRecord.material.eval(ray, hitRecord, &sample.direction);
+        let rgb: Vec3 = value.getColorRGB();
+        if rgb.x <= 0.0 && rgb.y <= 0.0 && rgb.z <= 0.0 {
+            return VColor::default();
+        }
+
+        // Lights that material sampling can find as well share the sample with it
+        let mut weight: f32 = 1.0;
+        if sample.pdf > 0.0 {
+            let scatterPdf: f32 = hitRecord.material.pdf(ray, hitRecord, &sample.direction);
+            if scatterPdf > 0.0 {
+                weight = powerHeuristic(selectionProbability * sample.pdf, scatterPdf);
+            }
+        }
+
+        if !scene.isVisible(&hitRecord.point, &sample.direction, sample.distance) {
+            return VColor::default();
+        }
+        return value * sample.radiance * (weight / selectionProbability);
+    }
+
+    /* Weight of light that a scattered ray found on a source direct lighting samples as well */
+    pub fn emissionWeight(scene: &Scene, ray: &Ray, light: Option<usize>, specularBounce: bool, scatterPdf: f32) -> f32 {
+        let light: usize = match light {
+            Some(light) if !specularBounce => light,
+            _ => return 1.0,
+        };
+        // Direct lighting took all of it if the material cannot tell its density
+        if scatterPdf <= 0.0 {
+            return 0.0;
+        }
+        return powerHeuristic(scatterPdf, scene.lightPdf(&ray.origin, &normalize(ray.direction), light));
+    }
+
+    /* `specularBounce` tells whether the ray left a camera or a mirror-like lobe, only then it may count the lights
+       that direct lighting samples. Otherwise `scatterPdf` is the density the ray was picked with, used for MIS */
+    fn colorRay(&self, scene: &Scene, ray: &Ray, depth: i16, specularBounce: bool, scatterPdf: f32) -> VColor {
+        let (emitted, scattered) = self.colorRayParts(scene, ray, depth, specularBounce, scatterPdf);
+        return emitted + scattered;
+    }
+
+    /* Light along the ray split into what the surface it hits or the environment emits, and what the surface
+       scatters towards the ray's origin */
+    fn colorRayParts(&self, scene: &Scene, ray: &Ray, depth: i16, specularBounce: bool, scatterPdf: f32) -> (VColor, VColor) {
+        if depth as i32 <= 0 { return (VColor::new_sc(0.0), VColor::default()); };
+
+        let mut hitRecord: HitRecord = HitRecord::default();
+
+        if scene.Hit(ray, &mut hitRecord) {
+            let direct: VColor = PathIntegrator::directLight(scene, ray, &hitRecord);
+
+            let mut emission: VColor = VColor::default();
+            let emitted: VColor = hitRecord.material.emitted(ray, &hitRecord);
+            let emittedRGB: Vec3 = emitted.getColorRGB();
+            if emittedRGB.x > 0.0 || emittedRGB.y > 0.0 || emittedRGB.z > 0.0 {
+                let light: Option<usize> = scene.getLightOfObject(hitRecord.objectId);
+                emission = emitted * PathIntegrator::emissionWeight(scene, ray, light, specularBounce, scatterPdf);
+            }
+
+            let mut scattered: Ray = Ray::default();
+            let mut attenuation: VColor = VColor::default();
+            if hitRecord.material.scatter(ray, &hitRecord, &mut attenuation, &mut scattered) {
+                scattered.wavelength = ray.wavelength;
+                let specular: bool = hitRecord.material.isSpecular(ray, &hitRecord, &scattered);
+                let pdf: f32 = hitRecord.material.pdf(ray, &hitRecord, &normalize(scattered.direction));
+                return (emission, direct + attenuation * self.colorRay(scene, &scattered, depth - 1, specular, pdf));
+            }
+            return (emission, direct);
+        }
+
+        return (PathIntegrator::environmentRadiance(scene, ray, specularBounce, scatterPdf), VColor::default());
+    }
+
+    /* Light from the environment along a ray that left the scene, weighted against direct lighting like emission */
+    pub fn environmentRadiance(scene: &Scene, ray: &Ray, specularBounce: bool, scatterPdf: f32) -> VColor {
+        let environment = scene.getEnvironment();
+        if !specularBounce && scatterPdf > 0.0 {
+            if let Some(light) = scene.getEnvironmentLight() {
+                let lightPdf: f32 = scene.lightPdf(&ray.origin, &normalize(ray.direction), light);
+                if lightPdf > 0.0 {
+                    return environment.radiance(&ray.direction, true) * powerHeuristic(scatterPdf, lightPdf);
+                }
+            }
+        }
+        return environment.radiance(&ray.direction, specularBounce);
+    }
+}
+
+impl Integrator for PathIntegrator {
+    fn name(&self) -> &'static str {
+        return "path";
+    }
+
+    fn radiance(&self, scene: &Scene, ray: &Ray, maxDepth: i16, _film: &mut Film) -> VColor {
+        return self.colorRay(scene, ray, maxDepth, true, 0.0);
+    }
+
+    /* The first bounce of `colorRay` taken apart. Light sampled straight from the lights at the first hit counts as
+       diffuse, mirror-like lobes are left to the scattered ray */
+    fn radianceLayers(&self, scene: &Scene, ray: &Ray, maxDepth: i16, _film: &mut Film, layers: &mut LightLayers) -> VColor {
+        if maxDepth <= 0 {
+            return VColor::new_sc(0.0);
+        }
+
+        let mut hitRecord: HitRecord = HitRecord::default();
+        if !scene.Hit(ray, &mut hitRecord) {
+            let environment: VColor = PathIntegrator::environmentRadiance(scene, ray, true, 0.0);
+            layers.emission = environment.getColorRGB();
+            return environment;
+        }
+
+        // Camera rays count emitters in full, like after a specular bounce
+        let direct: VColor = PathIntegrator::directLight(scene, ray, &hitRecord);
+        let emitted: VColor = hitRecord.material.emitted(ray, &hitRecord);
+        layers.emission = emitted.getColorRGB();
+        layers.direct = direct.getColorRGB();
+        layers.diffuse = direct.getColorRGB();
+        let mut result: VColor = direct + emitted;
+
+        let mut scattered: Ray = Ray::default();
+        let mut attenuation: VColor = VColor::default();
+        if hitRecord.material.scatter(ray, &hitRecord, &mut attenuation, &mut scattered) {
+            scattered.wavelength = ray.wavelength;
+            let specular: bool = hitRecord.material.isSpecular(ray, &hitRecord, &scattered);
+            let pdf: f32 = hitRecord.material.pdf(ray, &hitRecord, &normalize(scattered.direction));
+            let (arriving, beyond) = self.colorRayParts(scene, &scattered, maxDepth - 1, specular, pdf);
+
+            let reflected: VColor = attenuation.clone() * (arriving.clone() + beyond.clone());
+            layers.direct = layers.direct + (attenuation.clone() * arriving).getColorRGB();
+            layers.indirect = (attenuation * beyond).getColorRGB();
+            if specular {
+                layers.specular = reflected.getColorRGB();
+            } else {
+                layers.diffuse = layers.diffuse + reflected.getColorRGB();
+            }
+            result += reflected;
+        }
+        return result;
+    }
+}
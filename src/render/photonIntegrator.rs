@@ -0,0 +1,190 @@
+use std::f32::consts::PI;
+use glm::{dot, normalize, Vec3, vec3, vec4};
+use crate::math::mathUtils::random01;
+use crate::math::ray::Ray;
+use crate::math::vColor::VColor;
+use crate::model::hitRecord::HitRecord;
+use crate::render::film::Film;
+use crate::render::integrator::Integrator;
+use crate::render::light::EmissionSample;
+use crate::render::pathIntegrator::PathIntegrator;
+use crate::render::photonMap::{Photon, PhotonMap};
+use crate::render::scene::Scene;
+
+pub const DEFAULT_PHOTON_COUNT: usize = 100000;
+pub const DEFAULT_PHOTON_RADIUS: f32 = 0.1;
+pub const DEFAULT_RADIUS_REDUCTION: f32 = 0.7;
+
+/* Photon mapping: light paths deposit photons on the surfaces they bounce off, and the indirect light at the first
+   diffuse surface a camera path reaches is estimated from the photons around it. Direct light is sampled like in the
+   path tracer, mirror-like surfaces are followed. The estimate blurs the light over the gather radius, which is
+   what lets caustics through glass converge.
+
+   The progressive variant traces a new photon map before every pass and shrinks the radius each time, after Knaus
+   and Zwicker's probabilistic formulation of progressive photon mapping. The average over the passes loses the
+   blur as the number of passes grows */
+pub struct PhotonIntegrator {
+    /* Photons emitted per map */
+    photonCount: usize,
+    radius: f32,
+    /* Fraction of the new photons kept by each pass of the progressive variant, none keeps the first map */
+    reduction: Option<f32>,
+    passes: i32,
+    map: PhotonMap,
+}
+
+impl PhotonIntegrator {
+    pub fn new(photonCount: usize, radius: f32) -> Self {
+        return Self {
+            photonCount,
+            radius,
+            reduction: None,
+            passes: 0,
+            map: PhotonMap::default(),
+        };
+    }
+
+    /* `reduction` in (0, 1) sets how fast the radius shrinks, lower values lose the blur faster but stay noisier */
+    pub fn new_progressive(photonCount: usize, radius: f32, reduction: f32) -> Self {
+        return Self {
+            reduction: Some(reduction),
+            ..PhotonIntegrator::new(photonCount, radius)
+        };
+    }
+
+    /* Follows `photonCount` light paths from the scene's lights. Photons that arrive straight from a light are left
+       out, the camera samples direct light itself */
+    fn tracePhotons(&self, scene: &Scene, maxDepth: i16) -> Vec<Photon> {
+        let mut photons: Vec<Photon> = Vec::with_capacity(self.photonCount);
+        let sceneCenter: Vec3 = scene.getSceneCenter();
+        let sceneRadius: f32 = scene.getSceneRadius();
+
+        for _ in 0..self.photonCount {
+            let (index, pmf) = match scene.getEmissionSampler().sample(&sceneCenter, random01()) {
+                Some(choice) => choice,
+                None => return photons,
+            };
+            let mut sample: EmissionSample = EmissionSample::default();
+            if pmf <= 0.0 || !scene.getLights()[index].sampleEmission(&sceneCenter, sceneRadius, &mut sample) || sample.pdfPosition <= 0.0 || sample.pdfDirection <= 0.0 {
+                continue;
+            }
+
+            let mut ray: Ray = Ray::new(sample.ray.origin, normalize(sample.ray.direction));
+            let hasNormal: bool = sample.normal.x != 0.0 || sample.normal.y != 0.0 || sample.normal.z != 0.0;
+            let cosTheta: f32 = if hasNormal { f32::abs(dot(sample.normal, ray.direction)) } else { 1.0 };
+            let mut power: Vec3 = sample.radiance * (cosTheta / (pmf * sample.pdfPosition * sample.pdfDirection * self.photonCount as f32));
+
+            for depth in 0..maxDepth {
+                let mut hitRecord: HitRecord = HitRecord::default();
+                if !scene.Hit(&ray, &mut hitRecord) {
+                    break;
+                }
+                if depth > 0 {
+                    photons.push(Photon {
+                        point: hitRecord.point,
+                        direction: normalize(ray.direction) * -1.0,
+                        power,
+                    });
+                }
+
+                let mut scattered: Ray = Ray::default();
+                let mut attenuation: VColor = VColor::default();
+                if !hitRecord.material.scatter(&ray, &hitRecord, &mut attenuation, &mut scattered) {
+                    break;
+                }
+                power = power * attenuation.getColorRGB();
+                if power.x <= 0.0 && power.y <= 0.0 && power.z <= 0.0 {
+                    break;
+                }
+                ray = scattered;
+            }
+        }
+        return photons;
+    }
+
+    /* Light reflected towards the ray's origin by the photons around the hit point */
+    fn estimate(&self, ray: &Ray, hitRecord: &HitRecord) -> Vec3 {
+        let mut sum: Vec3 = vec3(0.0, 0.0, 0.0);
+        self.map.gather(&hitRecord.point, self.radius, &mut |photon: &Photon| {
+            // The material's value holds the cosine at the surface, the photon's power already paid for it
+            let cosTheta: f32 = f32::abs(dot(hitRecord.shadingNormal, photon.direction));
+            if cosTheta < 1e-4 {
+                return;
+            }
+            let value: Vec3 = hitRecord.material.eval(ray, hitRecord, &photon.direction).getColorRGB();
+            sum = sum + value * photon.power / cosTheta;
+        });
+        return sum / (PI * self.radius * self.radius);
+    }
+
+    /* Light that a ray leaving a diffuse surface picks up from emitters and the environment right away, the part of
+       the direct light that material sampling finds */
+    fn emissionAlong(scene: &Scene, ray: &Ray, scatterPdf: f32) -> Vec3 {
+        let mut hitRecord: HitRecord = HitRecord::default();
+        if !scene.Hit(ray, &mut hitRecord) {
+            return PathIntegrator::environmentRadiance(scene, ray, false, scatterPdf).getColorRGB();
+        }
+        let light: Option<usize> = scene.getLightOfObject(hitRecord.objectId);
+        let weight: f32 = PathIntegrator::emissionWeight(scene, ray, light, false, scatterPdf);
+        return hitRecord.material.emitted(ray, &hitRecord).getColorRGB() * weight;
+    }
+}
+
+impl Integrator for PhotonIntegrator {
+    fn name(&self) -> &'static str {
+        return if self.reduction.is_some() { "sppm" } else { "photons" };
+    }
+
+    fn radiance(&self, scene: &Scene, ray: &Ray, maxDepth: i16, _film: &mut Film) -> VColor {
+        let mut radiance: Vec3 = vec3(0.0, 0.0, 0.0);
+        let mut beta: Vec3 = vec3(1.0, 1.0, 1.0);
+        let mut ray: Ray = ray.clone();
+
+        for _ in 0..maxDepth {
+            let mut hitRecord: HitRecord = HitRecord::default();
+            if !scene.Hit(&ray, &mut hitRecord) {
+                radiance = radiance + beta * PathIntegrator::environmentRadiance(scene, &ray, true, 0.0).getColorRGB();
+                break;
+            }
+
+            // Every ray so far left the camera or a mirror-like lobe, so emitters count in full
+            radiance = radiance + beta * hitRecord.material.emitted(&ray, &hitRecord).getColorRGB();
+            radiance = radiance + beta * PathIntegrator::directLight(scene, &ray, &hitRecord).getColorRGB();
+            radiance = radiance + beta * self.estimate(&ray, &hitRecord);
+
+            let mut scattered: Ray = Ray::default();
+            let mut attenuation: VColor = VColor::default();
+            if !hitRecord.material.scatter(&ray, &hitRecord, &mut attenuation, &mut scattered) {
+                break;
+            }
+            scattered.wavelength = ray.wavelength;
+            beta = beta * attenuation.getColorRGB();
+
+            // The photons stand in for everything a diffuse bounce would gather, except what direct lighting
+            // leaves to material sampling
+            if !hitRecord.material.isSpecular(&ray, &hitRecord, &scattered) {
+                let scatterPdf: f32 = hitRecord.material.pdf(&ray, &hitRecord, &normalize(scattered.direction));
+                radiance = radiance + beta * PhotonIntegrator::emissionAlong(scene, &scattered, scatterPdf);
+                break;
+            }
+            ray = scattered;
+        }
+
+        return VColor::new_vec4(&vec4(radiance.x, radiance.y, radiance.z, 1.0));
+    }
+
+    fn beginPass(&mut self, scene: &Scene, maxDepth: i16) {
+        match self.reduction {
+            None if self.passes > 0 => return,
+            // Shrinks the area of the radius so that each pass keeps `reduction` of the photons it would gather
+            Some(reduction) if self.passes > 0 => {
+                let passes: f32 = self.passes as f32;
+                self.radius *= ((passes + reduction) / (passes + 1.0)).sqrt();
+            }
+            _ => {}
+        }
+
+        self.map = PhotonMap::new(self.tracePhotons(scene, maxDepth));
+        self.passes += 1;
+    }
+}
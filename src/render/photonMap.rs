@@ -0,0 +1,140 @@
+use glm::{dot, Vec3, vec3};
+
+/* Light carried by a photon into a surface point */
+pub struct Photon {
+    pub point: Vec3,
+    /* Unit direction back towards where the photon came from */
+    pub direction: Vec3,
+    pub power: Vec3,
+}
+
+/* Balanced kd-tree over photons, stored in place: the photon in the middle of every range splits it, the ranges
+   before and after it are its subtrees */
+pub struct PhotonMap {
+    photons: Vec<Photon>,
+    /* Split axis of the photon at the same index */
+    axes: Vec<u8>,
+}
+
+impl PhotonMap {
+    pub fn new(photons: Vec<Photon>) -> Self {
+        let mut map: PhotonMap = PhotonMap {
+            axes: vec![0; photons.len()],
+            photons,
+        };
+        let count: usize = map.photons.len();
+        map.build(0, count);
+        return map;
+    }
+
+    /* Sorts photons[start..end] into a subtree, splitting at the median along the widest axis */
+    fn build(&mut self, start: usize, end: usize) {
+        if end - start <= 1 {
+            return;
+        }
+
+        let mut minimum: Vec3 = vec3(f32::MAX, f32::MAX, f32::MAX);
+        let mut maximum: Vec3 = vec3(f32::MIN, f32::MIN, f32::MIN);
+        for photon in self.photons[start..end].iter() {
+            for axis in 0..3 {
+                minimum[axis] = f32::min(minimum[axis], photon.point[axis]);
+                maximum[axis] = f32::max(maximum[axis], photon.point[axis]);
+            }
+        }
+        let extent: Vec3 = maximum - minimum;
+        let axis: usize = if extent.x >= extent.y && extent.x >= extent.z { 0 } else if extent.y >= extent.z { 1 } else { 2 };
+
+        let middle: usize = (start + end) / 2;
+        self.photons[start..end].select_nth_unstable_by(middle - start, |a, b| {
+            return a.point[axis].partial_cmp(&b.point[axis]).unwrap_or(std::cmp::Ordering::Equal);
+        });
+        self.axes[middle] = axis as u8;
+
+        self.build(start, middle);
+        self.build(middle + 1, end);
+    }
+
+    /* Calls `visit` for every photon closer than `radius` to `point` */
+    pub fn gather<F: FnMut(&Photon)>(&self, point: &Vec3, radius: f32, visit: &mut F) {
+        self.gatherRange(0, self.photons.len(), point, radius * radius, visit);
+    }
+
+    fn gatherRange<F: FnMut(&Photon)>(&self, start: usize, end: usize, point: &Vec3, radiusSquared: f32, visit: &mut F) {
+        if start >= end {
+            return;
+        }
+
+        let middle: usize = (start + end) / 2;
+        let photon: &Photon = &self.photons[middle];
+        let offset: Vec3 = photon.point - *point;
+        if dot(offset, offset) < radiusSquared {
+            visit(photon);
+        }
+        if end - start == 1 {
+            return;
+        }
+
+        // Only the side the point lies on can be skipped, and only when the splitting plane is out of reach
+        let axis: usize = self.axes[middle] as usize;
+        let distance: f32 = point[axis] - photon.point[axis];
+        if distance < 0.0 || distance * distance < radiusSquared {
+            self.gatherRange(start, middle, point, radiusSquared, visit);
+        }
+        if distance >= 0.0 || distance * distance < radiusSquared {
+            self.gatherRange(middle + 1, end, point, radiusSquared, visit);
+        }
+    }
+}
+
+impl Default for PhotonMap {
+    fn default() -> Self {
+        return Self {
+            photons: vec![],
+            axes: vec![],
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+    use super::*;
+
+    #[test]
+    fn gatherMatchesABruteForceSearch() {
+        let mut random: StdRng = StdRng::seed_from_u64(7);
+        let mut photons: Vec<Photon> = vec![];
+        let mut points: Vec<Vec3> = vec![];
+        for index in 0..3000 {
+            let mut point: Vec3 = vec3(random.gen(), random.gen(), random.gen());
+            // Every other photon on a coarse grid, so that many share their coordinate with the split
+            if index % 2 == 0 {
+                point = vec3((point.x * 10.0).round() / 10.0, (point.y * 10.0).round() / 10.0, (point.z * 10.0).round() / 10.0);
+            }
+            // The power is the photon's index, to tell which ones were found
+            photons.push(Photon { point, direction: vec3(0.0, 0.0, 1.0), power: vec3(index as f32, 0.0, 0.0) });
+            points.push(point);
+        }
+        let map: PhotonMap = PhotonMap::new(photons);
+        assert_eq!(map.photons.len(), points.len());
+
+        for query in 0..300 {
+            let mut center: Vec3 = vec3(random.gen_range(-0.2..1.2), random.gen_range(-0.2..1.2), random.gen_range(-0.2..1.2));
+            if query % 3 == 0 {
+                center = points[random.gen_range(0..points.len())];
+            }
+            let radius: f32 = random.gen_range(0.01..0.3);
+
+            let mut found: Vec<usize> = vec![];
+            map.gather(&center, radius, &mut |photon: &Photon| found.push(photon.power.x as usize));
+            found.sort();
+
+            let expected: Vec<usize> = (0..points.len()).filter(|i| {
+                let offset: Vec3 = points[*i] - center;
+                return dot(offset, offset) < radius * radius;
+            }).collect();
+            assert_eq!(found, expected);
+        }
+    }
+}
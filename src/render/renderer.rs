@@ -1,73 +1,406 @@
-﻿use std::thread;
-use glm::{IVec2, IVec4, ivec4, normalize, Vec3};
-use rand::{Rng, thread_rng};
+﻿use std::cell::RefCell;
+use std::rc::Rc;
+use std::fs;
+use std::thread;
+use glm::{IVec2, IVec4, ivec4, length, Vec2, Vec3, vec3, vec4};
 use sfml::graphics::Image;
 use crate::Camera;
-use crate::math::mathUtils::randomInHemisphere;
+use crate::math::mathUtils::{randomVec2, SampleSource, setSampleSource};
 use crate::math::ray::Ray;
+use crate::math::spectrum::{sampleWavelength, wavelengthToRGB};
 use crate::math::vColor::VColor;
 use crate::model::hitRecord::HitRecord;
+use crate::render::aov::{AovBuffers, AovImage, LightLayers};
+use crate::render::debugIntegrators::AlbedoIntegrator;
+use crate::render::denoiser::Denoiser;
+use crate::render::exrImage::writeExr;
+use crate::render::film::Film;
+use crate::render::filter::{BoxFilter, Filter};
+use crate::render::integrator::{heatmap, Integrator};
+use crate::render::pathIntegrator::PathIntegrator;
+use crate::render::sampler::{RandomSampler, Sampler};
 use crate::render::scene::Scene;
 
+/* Start of the files written by `saveState` */
+const STATE_MAGIC: &[u8; 8] = b"RTSTATE1";
+
 pub struct Renderer {
     sampling: i32,
+    /* Most samples per pixel and the relative error threshold of adaptive sampling: pixels whose estimated error
+       stays above the threshold after `sampling` samples keep being sampled. None samples every pixel alike */
+    adaptive: Option<(i32, f32)>,
     rayRecursionDepth: i16,
+    /* Each camera path carries a single sampled wavelength, converted to RGB when accumulated */
+    spectral: bool,
 
     scene: Scene,
+    integrator: Box<dyn Integrator>,
+    /* Source of every number drawn while a pixel sample is traced */
+    sampler: Rc<RefCell<dyn Sampler>>,
     pub imageBuffer: Image,
+    /* Samples of the last render */
+    film: Film,
+    /* How the samples are spread over the pixels */
+    filter: Rc<dyn Filter>,
+    /* Applied to the image once all samples are in */
+    denoiser: Option<Denoiser>,
+    /* Names of the layers `saveLayers` writes, from AOV_NAMES */
+    layerNames: Vec<String>,
+    aovs: Option<AovBuffers>,
+    /* The last render's image before it is turned into 8 bit colors */
+    beauty: Vec<Vec3>,
+    /* Passes and camera samples that went into `film`, which a resumed render continues from */
+    completedPasses: i32,
+    sampleCount: u64,
+    /* Samples per pixel the sampler lays its sets out over, the sampling of the render's first session */
+    samplerSampling: i32,
+    resumed: bool,
+    /* Size of the camera's frame */
+    resolution: IVec2,
+    /* Part of the frame that is rendered: offset in x and y, then width and height */
     viewPort: IVec4,
 }
 
 impl Renderer {
     pub fn new(sc: Scene, res: &IVec2, img: Image) -> Renderer {
-        let renderer: Renderer = Renderer {
-            scene: sc,
-            viewPort: ivec4(0, 0, res.x.clone(), res.y.clone()),
+        let mut scene: Scene = sc;
+        scene.prepareLights();
+
+        let mut renderer: Renderer = Renderer {
+            resolution: *res,
+            viewPort: ivec4(0, 0, res.x, res.y),
             imageBuffer: img,
             ..Default::default()
         };
 
+        // A scene file may ask for an integrator, the caller can still replace it
+        if let Some(integrator) = scene.takeIntegrator() {
+            renderer.integrator = integrator;
+        }
+        if let Some(sampler) = scene.takeSampler() {
+            renderer.sampler = sampler;
+        }
+        renderer.scene = scene;
+
         return renderer;
     }
 
-    pub fn colorRay(&self, x: i32, y: i32, ray: &Ray, depth: i16) -> VColor {
-        if depth as i32 <= 0 { return VColor::new_sc(0.0); };
+    /* Samples per pixel, the total over all sessions of a resumed render */
+    pub fn setSampling(&mut self, sampling: i32) {
+        self.sampling = sampling;
+    }
+
+    /* Renders only the `width` by `height` pixels from `x`, `y` on, seen as they are in the full frame. The rest of
+       the image stays as it is */
+    pub fn setCropWindow(&mut self, x: i32, y: i32, width: i32, height: i32) {
+        let x: i32 = i32::min(i32::max(x, 0), self.resolution.x - 1);
+        let y: i32 = i32::min(i32::max(y, 0), self.resolution.y - 1);
+        self.viewPort = ivec4(x, y, i32::max(i32::min(width, self.resolution.x - x), 1), i32::max(i32::min(height, self.resolution.y - y), 1));
+    }
+
+    pub fn setSpectral(&mut self, spectral: bool) {
+        self.spectral = spectral;
+    }
+
+    pub fn setIntegrator(&mut self, integrator: Box<dyn Integrator>) {
+        self.integrator = integrator;
+    }
+
+    pub fn setSampler(&mut self, sampler: Rc<RefCell<dyn Sampler>>) {
+        self.sampler = sampler;
+    }
 
-        let mut hitRecord: HitRecord = HitRecord::default();
+    /* Keeps sampling the pixels whose relative error is above `errorThreshold`, up to `maxSampling` samples */
+    pub fn setAdaptiveSampling(&mut self, maxSampling: i32, errorThreshold: f32) {
+        self.adaptive = Some((maxSampling, errorThreshold));
+    }
+
+    pub fn setFilter(&mut self, filter: Rc<dyn Filter>) {
+        self.filter = filter;
+    }
 
-        if self.scene.Hit(ray, &mut hitRecord) {
-            let mut scattered: Ray = Ray::default();
-            let mut attenuation: VColor = VColor::default();
-            if hitRecord.material.scatter(ray, &hitRecord, &mut attenuation, &mut scattered) {
-                let target: Vec3 = hitRecord.point + randomInHemisphere(&hitRecord.normal);
-                return attenuation * self.colorRay(x, y, &scattered, depth - 1);
+    pub fn setDenoiser(&mut self, denoiser: Option<Denoiser>) {
+        self.denoiser = denoiser;
+    }
+
+    /* Layers to keep track of while rendering, the light layers cost the integrator some bookkeeping */
+    pub fn setLayers(&mut self, names: Vec<String>) {
+        self.layerNames = names;
+    }
+
+    /* One of the layers of the last render, none if it was not asked for with `setLayers` */
+    pub fn layer(&self, name: &str) -> Option<AovImage> {
+        let (width, height) = (self.film.getWidth(), self.film.getHeight());
+        let pixels = || (0..height).flat_map(move |y| (0..width).map(move |x| (x, y)));
+        return match name {
+            "beauty" => Some(AovImage::new_colors(name, &self.beauty)),
+            "albedo" => Some(AovImage::new_colors(name, &pixels().map(|(x, y)| self.film.getAlbedo(x, y)).collect::<Vec<Vec3>>())),
+            "normal" => Some(AovImage::new_colors(name, &pixels().map(|(x, y)| self.film.getNormal(x, y)).collect::<Vec<Vec3>>())),
+            "depth" => Some(AovImage::new_values(name, "Z", &pixels().map(|(x, y)| self.film.getDepth(x, y)).collect::<Vec<f32>>())),
+            _ => self.aovs.as_ref().and_then(|aovs| aovs.layer(name)),
+        };
+    }
+
+    /* Saves the layers of the last render. An .exr path gets all of them in one file at full precision, any other
+       path one 8 bit image per layer, named after the path with the layer's name appended */
+    pub fn saveLayers(&self, path: &str) -> Result<(), String> {
+        let mut images: Vec<AovImage> = vec![];
+        for name in self.layerNames.iter() {
+            images.push(self.layer(name).ok_or(format!("unknown layer '{}'", name))?);
+        }
+
+        let (width, height) = (self.film.getWidth(), self.film.getHeight());
+        if path.to_lowercase().ends_with(".exr") {
+            return writeExr(path, width, height, &images);
+        }
+
+        let (stem, extension) = path.rsplit_once('.').unwrap_or((path, "png"));
+        for image in images.iter() {
+            let file: String = format!("{}_{}.{}", stem, image.name, extension);
+            if !image.toDisplayImage(width, height).save_to_file(&file) {
+                return Err(format!("cannot write '{}'", file));
             }
-            return VColor::default();
         }
+        return Ok(());
+    }
 
-        let unitDirection: Vec3 = normalize(ray.direction);
-        let t: f32 = 0.5 * (unitDirection.y + 1.0);
-        return VColor::new_sc(1.0) * (1.0 - t) + VColor::new_rgb(0.5, 0.7, 1.0) * t;
+    /* Samples each pixel got in the last render, from blue for none to red for the most a pixel may take */
+    pub fn sampleCountHeatmap(&self) -> Image {
+        let mut image: Image = Image::new(self.viewPort.z as u32, self.viewPort.w as u32).unwrap();
+        let maxSampling: f32 = self.maxSampling() as f32;
+        for y in 0..self.film.getHeight() {
+            for x in 0..self.film.getWidth() {
+                let color: VColor = heatmap(self.film.getSampleCount(x, y) as f32 / maxSampling);
+                image.set_pixel(x as u32, y as u32, &color.asSFColor());
+            }
+        }
+        return image;
+    }
+
+    /* Writes what the last render accumulated, so that `resumeFrom` can add more samples to it later. The light and
+       id layers are not part of it, nor is what integrators keep between passes */
+    pub fn saveState(&self, path: &str) -> Result<(), String> {
+        let mut bytes: Vec<u8> = STATE_MAGIC.to_vec();
+        for value in [self.resolution.x, self.resolution.y, self.viewPort.x, self.viewPort.y, self.viewPort.z, self.viewPort.w, self.completedPasses, self.samplerSampling] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend_from_slice(&self.sampleCount.to_le_bytes());
+        bytes.push(self.spectral as u8);
+        bytes.extend_from_slice(&self.filter.radius().to_le_bytes());
+        for name in [self.filter.name(), self.sampler.borrow().name(), self.integrator.name()] {
+            bytes.extend_from_slice(&(name.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(name.as_bytes());
+        }
+        self.film.writeState(&mut bytes);
+        return fs::write(path, bytes).map_err(|e| format!("cannot write '{}': {}", path, e));
+    }
+
+    /* Makes the next render continue a render saved by `saveState`. The resolution, crop window, filter, sampler,
+       integrator and spectral mode have to be the same. It takes the passes that are still missing to reach the
+       sampling, the sampler goes on over the samples per pixel of the first session so that no sample repeats.
+       Integrators that keep state between passes, like sppm's shrinking radius, start it over */
+    pub fn resumeFrom(&mut self, path: &str) -> Result<(), String> {
+        let bytes: Vec<u8> = fs::read(path).map_err(|e| format!("cannot read '{}': {}", path, e))?;
+        let notSaved = || format!("'{}' is not a saved render", path);
+        let mut position: usize = STATE_MAGIC.len();
+        if bytes.len() < position || bytes[..position] != STATE_MAGIC[..] {
+            return Err(notSaved());
+        }
+        let mut take = |count: usize| -> Result<&[u8], String> {
+            if position + count > bytes.len() {
+                return Err(notSaved());
+            }
+            position += count;
+            return Ok(&bytes[position - count..position]);
+        };
+
+        let mut header: Vec<i32> = vec![];
+        for _ in 0..8 {
+            header.push(i32::from_le_bytes(take(4)?.try_into().unwrap()));
+        }
+        let sampleCount: u64 = u64::from_le_bytes(take(8)?.try_into().unwrap());
+        let spectral: bool = take(1)?[0] != 0;
+        let filterRadius: f32 = f32::from_le_bytes(take(4)?.try_into().unwrap());
+        let mut names: Vec<String> = vec![];
+        for _ in 0..3 {
+            let length: usize = u32::from_le_bytes(take(4)?.try_into().unwrap()) as usize;
+            names.push(String::from_utf8_lossy(take(length)?).to_string());
+        }
+        let headerSize: usize = position;
+        if header[6] < 0 || header[7] < 1 {
+            return Err(format!("'{}' is corrupt, it has {} passes over {} samples per pixel", path, header[6], header[7]));
+        }
+
+        if header[0] != self.resolution.x || header[1] != self.resolution.y || header[2..6] != [self.viewPort.x, self.viewPort.y, self.viewPort.z, self.viewPort.w] {
+            return Err(format!("'{}' was rendered at {}x{} with the crop window {} {} {} {}", path, header[0], header[1], header[2], header[3], header[4], header[5]));
+        }
+        if names[0] != self.filter.name() || filterRadius != self.filter.radius() {
+            return Err(format!("'{}' was rendered with the {} filter of radius {}", path, names[0], filterRadius));
+        }
+        if names[1] != self.sampler.borrow().name() {
+            return Err(format!("'{}' was rendered with the {} sampler", path, names[1]));
+        }
+        if names[2] != self.integrator.name() || spectral != self.spectral {
+            return Err(format!("'{}' was rendered with the {} integrator{}", path, names[2], if spectral { " in spectral mode" } else { "" }));
+        }
+
+        let mut film: Film = self.createFilm();
+        let mut position: usize = headerSize;
+        film.readState(&bytes, &mut position)?;
+
+        self.film = film;
+        self.completedPasses = header[6];
+        self.samplerSampling = header[7];
+        self.sampleCount = sampleCount;
+        self.resumed = true;
+        return Ok(());
+    }
+
+    fn createFilm(&self) -> Film {
+        let mut film: Film = Film::new_filtered(self.viewPort.z as usize, self.viewPort.w as usize, self.filter.clone());
+        film.setFrame(self.viewPort.x as usize, self.viewPort.y as usize, self.resolution.x as usize, self.resolution.y as usize);
+        return film;
+    }
+
+    /* Albedo, shading normal and distance of the first surface the camera ray hits */
+    fn firstHitFeatures(&self, ray: &Ray, hitRecord: Option<&HitRecord>) -> (Vec3, Vec3, f32) {
+        let hitRecord: &HitRecord = match hitRecord {
+            Some(hitRecord) => hitRecord,
+            None => {
+                let environment: Vec3 = self.scene.getEnvironment().radiance(&ray.direction, true).getColorRGB();
+                return (environment, vec3(0.0, 0.0, 0.0), 0.0);
+            }
+        };
+        let albedo: Vec3 = AlbedoIntegrator::surfaceAlbedo(ray, hitRecord);
+        return (albedo, hitRecord.shadingNormal, hitRecord.t * length(ray.direction));
+    }
+
+    /* Most samples a pixel may take */
+    fn maxSampling(&self) -> i32 {
+        return match self.adaptive {
+            Some((maxSampling, _)) => i32::max(maxSampling, self.sampling),
+            None => self.sampling,
+        };
+    }
+
+    /* Whether the pixel still takes samples in pass `pass` */
+    fn isPixelActive(&self, x: i32, y: i32, pass: i32) -> bool {
+        if pass < self.sampling {
+            return true;
+        }
+        return match self.adaptive {
+            Some((_, errorThreshold)) => self.film.relativeError(x as usize, y as usize) > errorThreshold,
+            None => false,
+        };
     }
 
     pub fn render(&mut self) {
-        for y in 0..self.viewPort.w {
-            for x in 0..self.viewPort.z {
-                let mut num = VColor::default();
+        if !self.resumed {
+            self.film = self.createFilm();
+            self.completedPasses = 0;
+            self.sampleCount = 0;
+            self.samplerSampling = self.sampling;
+        }
+        self.resumed = false;
+        self.aovs = if self.layerNames.is_empty() { None } else { Some(AovBuffers::new(self.viewPort.z as usize, self.viewPort.w as usize)) };
+        let maxSampling: i32 = self.maxSampling();
 
+        // A crop window samples the pixels around it as far as the filter reaches, so that its border pixels get the
+        // same samples as in a render of the full frame
+        let margin: i32 = f32::max((self.filter.radius() - 0.5).ceil(), 0.0) as i32;
+        let (left, top) = (i32::min(margin, self.viewPort.x), i32::min(margin, self.viewPort.y));
+        let right: i32 = i32::min(margin, self.resolution.x - self.viewPort.x - self.viewPort.z);
+        let bottom: i32 = i32::min(margin, self.resolution.y - self.viewPort.y - self.viewPort.w);
 
-                for s in 0..self.sampling {
+        for pass in self.completedPasses..maxSampling {
+            if pass >= self.sampling && !(0..self.viewPort.w).any(|y| (0..self.viewPort.z).any(|x| self.isPixelActive(x, y, pass))) {
+                break;
+            }
+            self.integrator.beginPass(&self.scene, self.rayRecursionDepth);
+
+            // Light paths traced ahead of the pass keep to the thread's generator
+            let source: Rc<RefCell<dyn SampleSource>> = self.sampler.clone();
+            setSampleSource(Some(source));
+            for y in -top..self.viewPort.w + bottom {
+                for x in -left..self.viewPort.z + right {
+                    // Pixels around the crop window only take the base samples, their error is not tracked
+                    let inside: bool = x >= 0 && y >= 0 && x < self.viewPort.z && y < self.viewPort.w;
+                    if (inside && !self.isPixelActive(x, y, pass)) || (!inside && pass >= self.sampling) {
+                        continue;
+                    }
+                    // Samplers and the camera see the pixel's place in the full frame
+                    let (frameX, frameY) = (self.viewPort.x + x, self.viewPort.y + y);
+                    // The base samples are one stratified set, the extra samples of adaptive sampling and of resumed
+                    // renders further rounds
+                    self.sampler.borrow_mut().startPixelSample(frameX, frameY, pass, self.samplerSampling);
+                    let jitter: Vec2 = randomVec2();
                     //let ray: Ray = self.scene.getCamera().createARay(x as f32 + thread_rng().gen_range(0.0..1.0),y as f32 + thread_rng().gen_range(0.0..1.0));
-                    let ray: Ray = self.scene.getCamera().createARay((x as f32 + thread_rng().gen_range(0.0..1.0) as f32)  / (self.viewPort.z - 1) as f32
-                                                                     , (y as f32 + thread_rng().gen_range(0.0..1.0) as f32) / (self.viewPort.w - 1) as f32);
+                    let mut ray: Ray = self.scene.getCamera().createARay((frameX as f32 + jitter.x)  / self.resolution.x as f32
+                                                                     , (frameY as f32 + jitter.y) / self.resolution.y as f32);
 
-                    num += self.colorRay(x, y, &ray, self.rayRecursionDepth);
-                }
+                    let mut weight: Option<Vec3> = None;
+                    if self.spectral {
+                        ray.wavelength = sampleWavelength();
+                        weight = Some(wavelengthToRGB(ray.wavelength));
+                    }
 
-                let result: VColor = num / self.sampling;
-                self.imageBuffer.set_pixel(x as u32, y as u32, &result.asSFColor());
+                    let mut lightLayers: LightLayers = LightLayers::default();
+                    let mut sample: VColor = if self.aovs.is_some() {
+                        self.integrator.radianceLayers(&self.scene, &ray, self.rayRecursionDepth, &mut self.film, &mut lightLayers)
+                    } else {
+                        self.integrator.radiance(&self.scene, &ray, self.rayRecursionDepth, &mut self.film)
+                    };
+                    if let Some(weight) = weight {
+                        sample = sample * VColor::new_vec4(&vec4(weight.x, weight.y, weight.z, 1.0));
+                        lightLayers = lightLayers.scaled(&weight);
+                    }
+                    self.film.addSample(x, y, &jitter, &sample);
+                    self.sampleCount += 1;
+                    if !inside {
+                        continue;
+                    }
+
+                    // After the integrator, so that the pixel's first sample dimensions go to the path
+                    let mut hitRecord: HitRecord = HitRecord::default();
+                    let hit: Option<&HitRecord> = if self.scene.Hit(&ray, &mut hitRecord) { Some(&hitRecord) } else { None };
+                    let (albedo, normal, depth) = self.firstHitFeatures(&ray, hit);
+                    self.film.addFeatures(x as usize, y as usize, &albedo, &normal, depth);
+                    if let Some(aovs) = &mut self.aovs {
+                        aovs.addSample(x as usize, y as usize, &lightLayers);
+                        aovs.addFirstHit(x as usize, y as usize, hit);
+                    }
+                }
             }
+            setSampleSource(None);
+            self.completedPasses = pass + 1;
         };
+
+        // Integrators that trace from the lights splat one light path per camera sample over the whole frame, which
+        // makes the splats an average over the mean number of samples per pixel of the frame
+        // Without camera samples nothing was splatted either
+        let splatScale: f32 = if self.sampleCount == 0 { 0.0 } else { (self.resolution.x * self.resolution.y) as f32 / self.sampleCount as f32 };
+        let mut results: Vec<VColor> = Vec::with_capacity((self.viewPort.z * self.viewPort.w) as usize);
+        for y in 0..self.viewPort.w {
+            for x in 0..self.viewPort.z {
+                results.push(self.film.resolve(x as usize, y as usize, splatScale));
+            }
+        }
+
+        if let Some(denoiser) = &self.denoiser {
+            let colors: Vec<Vec3> = results.iter().map(|result| result.getColorRGB()).collect();
+            let denoised: Vec<Vec3> = denoiser.denoise(&self.film, &colors);
+            for (result, color) in results.iter_mut().zip(denoised.iter()) {
+                *result = VColor::new_vec4(&vec4(color.x, color.y, color.z, result.color.w));
+            }
+        }
+
+        self.beauty = results.iter().map(|result| result.getColorRGB()).collect();
+        for y in 0..self.viewPort.w {
+            for x in 0..self.viewPort.z {
+                let result: &VColor = &results[(y * self.viewPort.z + x) as usize];
+                self.imageBuffer.set_pixel((self.viewPort.x + x) as u32, (self.viewPort.y + y) as u32, &result.asSFColor());
+            }
+        }
     }
 }
 
@@ -75,9 +408,24 @@ impl Default for Renderer {
     fn default() -> Self {
         return Self {
             scene: Scene::new(Camera::default(), vec![]),
+            integrator: Box::new(PathIntegrator::new()),
+            sampler: Rc::new(RefCell::new(RandomSampler::new())),
             sampling: 32,
+            adaptive: None,
             imageBuffer: Image::new(800, 600).unwrap(),
+            film: Film::new(800, 600),
+            filter: Rc::new(BoxFilter::new(0.5)),
+            denoiser: None,
+            layerNames: vec![],
+            aovs: None,
+            beauty: vec![],
+            completedPasses: 0,
+            sampleCount: 0,
+            samplerSampling: 32,
+            resumed: false,
+            resolution: IVec2 { x: 800, y: 600 },
             rayRecursionDepth: 16,
+            spectral: false,
             viewPort: ivec4(0, 0, 800, 600),
         };
     }
@@ -0,0 +1,368 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use glm::{Vec2, vec2};
+use rand::{Rng, thread_rng};
+use crate::math::lowDiscrepancy::{fixedToFloat, hashPixel, mixBits, ONE_MINUS_EPSILON, owenScramble, owenScrambledRadicalInverse, permutationElement, PRIMES, sobol2D};
+use crate::math::mathUtils::SampleSource;
+
+/* Hands out the numbers of one pixel sample after the other. The renderer routes `random01` to it while it traces
+   the sample, so the dimensions go to the pixel jitter first, then the lens and then whatever the path asks for */
+pub trait Sampler: SampleSource {
+    /* Name `createSampler` knows it by */
+    fn name(&self) -> &'static str;
+
+    /* Moves to sample `index` of the pixel at (x, y), out of `samplesPerPixel`, starting again at the first
+       dimension. Indices past `samplesPerPixel` go on in rounds of `samplesPerPixel` that are laid out like the first
+       one, which is what adaptive sampling takes its extra samples from */
+    fn startPixelSample(&mut self, x: i32, y: i32, index: i32, samplesPerPixel: i32);
+}
+
+/* Names accepted on the command line and by the scene file's `sampler` statement */
+pub const SAMPLER_NAMES: [&str; 5] = ["random", "stratified", "halton", "sobol", "bluenoise"];
+
+/* Sampler by name */
+pub fn createSampler(name: &str) -> Option<Rc<RefCell<dyn Sampler>>> {
+    let sampler: Rc<RefCell<dyn Sampler>> = match name {
+        "random" => Rc::new(RefCell::new(RandomSampler::new())),
+        "stratified" => Rc::new(RefCell::new(StratifiedSampler::new())),
+        "halton" => Rc::new(RefCell::new(HaltonSampler::new())),
+        "sobol" => Rc::new(RefCell::new(SobolSampler::new())),
+        "bluenoise" => Rc::new(RefCell::new(BlueNoiseSampler::new())),
+        _ => return None,
+    };
+    return Some(sampler);
+}
+
+fn uniform() -> f32 {
+    return thread_rng().gen_range(0.0..1.0);
+}
+
+/* Where a sampler is: which pixel, which of its samples and which dimension comes next */
+struct PixelSample {
+    x: i32,
+    y: i32,
+    index: u32,
+    samplesPerPixel: u32,
+    dimension: u32,
+}
+
+impl PixelSample {
+    fn new() -> Self {
+        return Self { x: 0, y: 0, index: 0, samplesPerPixel: 1, dimension: 0 };
+    }
+
+    fn start(&mut self, x: i32, y: i32, index: i32, samplesPerPixel: i32) {
+        self.x = x;
+        self.y = y;
+        self.index = index as u32;
+        self.samplesPerPixel = i32::max(samplesPerPixel, 1) as u32;
+        self.dimension = 0;
+    }
+
+    /* Which round of `samplesPerPixel` samples the sample is in, and its place in the round */
+    fn round(&self) -> (u32, u32) {
+        return (self.index / self.samplesPerPixel, self.index % self.samplesPerPixel);
+    }
+
+    /* Hash of the pixel and the dimension about to be taken, which is skipped over */
+    fn takeDimension(&mut self, count: u32, seed: u64) -> u64 {
+        let hash: u64 = hashPixel(self.x, self.y, self.dimension, seed);
+        self.dimension += count;
+        return hash;
+    }
+}
+
+/* Independent uniform numbers, what the renderer did before there were samplers */
+pub struct RandomSampler {}
+
+impl RandomSampler {
+    pub fn new() -> Self {
+        return Self {};
+    }
+}
+
+impl SampleSource for RandomSampler {
+    fn next(&mut self) -> f32 {
+        return uniform();
+    }
+}
+
+impl Sampler for RandomSampler {
+    fn name(&self) -> &'static str {
+        return "random";
+    }
+
+    fn startPixelSample(&mut self, _x: i32, _y: i32, _index: i32, _samplesPerPixel: i32) {}
+}
+
+/* Jittered strata: every dimension is cut into one stratum per sample and pairs into a grid, each sample of a pixel
+   takes its own stratum. Which one is shuffled per pixel, dimension and round, so the dimensions do not line up */
+pub struct StratifiedSampler {
+    state: PixelSample,
+}
+
+impl StratifiedSampler {
+    pub fn new() -> Self {
+        return Self { state: PixelSample::new() };
+    }
+}
+
+impl SampleSource for StratifiedSampler {
+    fn next(&mut self) -> f32 {
+        let hash: u64 = self.state.takeDimension(1, 0);
+        let (round, index) = self.state.round();
+        let stratum: u32 = permutationElement(index, self.state.samplesPerPixel, (hash ^ mixBits(round as u64)) as u32);
+        return f32::min((stratum as f32 + uniform()) / self.state.samplesPerPixel as f32, ONE_MINUS_EPSILON);
+    }
+
+    fn next2D(&mut self) -> Vec2 {
+        // A grid of at least as many cells as samples, counts that are no square leave some cells out
+        let columns: u32 = (self.state.samplesPerPixel as f32).sqrt().ceil() as u32;
+        let rows: u32 = self.state.samplesPerPixel.div_ceil(columns);
+        let hash: u64 = self.state.takeDimension(2, 0);
+        let (round, index) = self.state.round();
+        let cell: u32 = permutationElement(index, columns * rows, (hash ^ mixBits(round as u64)) as u32);
+        return vec2(f32::min(((cell % columns) as f32 + uniform()) / columns as f32, ONE_MINUS_EPSILON),
+                    f32::min(((cell / columns) as f32 + uniform()) / rows as f32, ONE_MINUS_EPSILON));
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn name(&self) -> &'static str {
+        return "stratified";
+    }
+
+    fn startPixelSample(&mut self, x: i32, y: i32, index: i32, samplesPerPixel: i32) {
+        self.state.start(x, y, index, samplesPerPixel);
+    }
+}
+
+/* The Halton sequence, one prime base per dimension, with the digits Owen scrambled per pixel. Dimensions past the
+   table of primes fall back to independent numbers */
+pub struct HaltonSampler {
+    state: PixelSample,
+}
+
+impl HaltonSampler {
+    pub fn new() -> Self {
+        return Self { state: PixelSample::new() };
+    }
+}
+
+impl SampleSource for HaltonSampler {
+    fn next(&mut self) -> f32 {
+        let dimension: usize = self.state.dimension as usize;
+        let hash: u64 = self.state.takeDimension(1, 1);
+        if dimension >= PRIMES.len() {
+            return uniform();
+        }
+        return owenScrambledRadicalInverse(PRIMES[dimension], self.state.index as u64, hash);
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn name(&self) -> &'static str {
+        return "halton";
+    }
+
+    fn startPixelSample(&mut self, x: i32, y: i32, index: i32, samplesPerPixel: i32) {
+        self.state.start(x, y, index, samplesPerPixel);
+    }
+}
+
+/* Owen scrambled Sobol points, padded: every dimension or pair takes the first one or two Sobol dimensions, which
+   are the best distributed, with the order of the samples shuffled and the bits scrambled per pixel and dimension.
+   Works best with a power of two samples per pixel */
+pub struct SobolSampler {
+    state: PixelSample,
+}
+
+impl SobolSampler {
+    pub fn new() -> Self {
+        return Self { state: PixelSample::new() };
+    }
+
+    /* Later rounds take the next block of Sobol points, for a power of two samples per pixel every block is as well
+       distributed as the first and so are all blocks together */
+    fn shuffledIndex(&self, hash: u64) -> u32 {
+        let (round, index) = self.state.round();
+        return round * self.state.samplesPerPixel + permutationElement(index, self.state.samplesPerPixel, hash as u32);
+    }
+}
+
+impl SampleSource for SobolSampler {
+    fn next(&mut self) -> f32 {
+        let hash: u64 = self.state.takeDimension(1, 2);
+        let (first, _) = sobol2D(self.shuffledIndex(hash));
+        return fixedToFloat(owenScramble(first, (hash >> 32) as u32));
+    }
+
+    fn next2D(&mut self) -> Vec2 {
+        let hash: u64 = self.state.takeDimension(2, 2);
+        let (first, second) = sobol2D(self.shuffledIndex(hash));
+        let scrambleSeed: u64 = hash.rotate_left(17);
+        return vec2(fixedToFloat(owenScramble(first, (hash >> 32) as u32)), fixedToFloat(owenScramble(second, scrambleSeed as u32)));
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn name(&self) -> &'static str {
+        return "sobol";
+    }
+
+    fn startPixelSample(&mut self, x: i32, y: i32, index: i32, samplesPerPixel: i32) {
+        self.state.start(x, y, index, samplesPerPixel);
+    }
+}
+
+const BLUE_NOISE_SIZE: usize = 64;
+
+/* Blue noise dithered sampling after Georgiev and Fajardo: every pixel runs through the same Sobol points, shifted
+   around the unit interval by a blue noise mask. Neighbouring pixels get shifts that are far apart, which leaves the
+   error as high frequency noise that looks finer at low sample counts. Each dimension reads the mask at its own
+   offset so that the dimensions are not shifted alike */
+pub struct BlueNoiseSampler {
+    state: PixelSample,
+    mask: Vec<f32>,
+}
+
+impl BlueNoiseSampler {
+    pub fn new() -> Self {
+        return Self {
+            state: PixelSample::new(),
+            mask: blueNoiseMask(BLUE_NOISE_SIZE),
+        };
+    }
+
+    /* Mask value of the current pixel, the tile moved by an offset taken from `hash` */
+    fn shift(&self, hash: u64) -> f32 {
+        let size: i32 = BLUE_NOISE_SIZE as i32;
+        let x: i32 = (self.state.x + (hash % BLUE_NOISE_SIZE as u64) as i32).rem_euclid(size);
+        let y: i32 = (self.state.y + ((hash >> 16) % BLUE_NOISE_SIZE as u64) as i32).rem_euclid(size);
+        return self.mask[(y * size + x) as usize];
+    }
+
+    fn shifted(value: f32, shift: f32) -> f32 {
+        let sum: f32 = value + shift;
+        return f32::min(sum - sum.floor(), ONE_MINUS_EPSILON);
+    }
+}
+
+impl SampleSource for BlueNoiseSampler {
+    fn next(&mut self) -> f32 {
+        // The pixel's own hash would make the mask pointless, the offsets only depend on the dimension
+        let dimension: u32 = self.state.dimension;
+        self.state.dimension += 1;
+        let hash: u64 = hashPixel(0, 0, dimension, 3);
+        let (first, _) = sobol2D(self.state.index);
+        return BlueNoiseSampler::shifted(fixedToFloat(owenScramble(first, (hash >> 32) as u32)), self.shift(hash));
+    }
+
+    fn next2D(&mut self) -> Vec2 {
+        let dimension: u32 = self.state.dimension;
+        self.state.dimension += 2;
+        let hash: u64 = hashPixel(0, 0, dimension, 3);
+        let otherHash: u64 = hashPixel(0, 0, dimension + 1, 3);
+        let (first, second) = sobol2D(self.state.index);
+        return vec2(BlueNoiseSampler::shifted(fixedToFloat(owenScramble(first, (hash >> 32) as u32)), self.shift(hash)),
+                    BlueNoiseSampler::shifted(fixedToFloat(owenScramble(second, (otherHash >> 32) as u32)), self.shift(otherHash)));
+    }
+}
+
+impl Sampler for BlueNoiseSampler {
+    fn name(&self) -> &'static str {
+        return "bluenoise";
+    }
+
+    fn startPixelSample(&mut self, x: i32, y: i32, index: i32, samplesPerPixel: i32) {
+        self.state.start(x, y, index, samplesPerPixel);
+    }
+}
+
+/* Tileable `size`² blue noise mask with values in [0, 1), built with Ulichney's void and cluster method: pixels
+   are ranked by adding them one by one where the pattern so far leaves the largest gap */
+fn blueNoiseMask(size: usize) -> Vec<f32> {
+    let count: usize = size * size;
+    let sigma: f32 = 1.5;
+
+    // Gaussian falloff by toroidal offset, so that the mask tiles
+    let mut kernel: Vec<f32> = vec![0.0; count];
+    for dy in 0..size {
+        for dx in 0..size {
+            let x: f32 = usize::min(dx, size - dx) as f32;
+            let y: f32 = usize::min(dy, size - dy) as f32;
+            kernel[dy * size + dx] = (-(x * x + y * y) / (2.0 * sigma * sigma)).exp();
+        }
+    }
+
+    let mut energy: Vec<f32> = vec![0.0; count];
+    let mut set: Vec<bool> = vec![false; count];
+    let update = |energy: &mut Vec<f32>, index: usize, sign: f32| {
+        let (px, py) = (index % size, index / size);
+        for y in 0..size {
+            for x in 0..size {
+                let dx: usize = (x + size - px) % size;
+                let dy: usize = (y + size - py) % size;
+                energy[y * size + x] += sign * kernel[dy * size + dx];
+            }
+        }
+    };
+    let extreme = |energy: &Vec<f32>, set: &Vec<bool>, wanted: bool, largest: bool| -> usize {
+        let mut best: usize = 0;
+        let mut bestEnergy: f32 = if largest { f32::MIN } else { f32::MAX };
+        for index in 0..count {
+            if set[index] == wanted && ((largest && energy[index] > bestEnergy) || (!largest && energy[index] < bestEnergy)) {
+                best = index;
+                bestEnergy = energy[index];
+            }
+        }
+        return best;
+    };
+
+    // A tenth of the pixels at random, then moved from the tightest cluster into the largest void until it settles
+    let initialCount: usize = count / 10;
+    let mut placed: usize = 0;
+    while placed < initialCount {
+        let index: usize = thread_rng().gen_range(0..count);
+        if !set[index] {
+            set[index] = true;
+            update(&mut energy, index, 1.0);
+            placed += 1;
+        }
+    }
+    for _ in 0..count {
+        let cluster: usize = extreme(&energy, &set, true, true);
+        set[cluster] = false;
+        update(&mut energy, cluster, -1.0);
+        let void: usize = extreme(&energy, &set, false, false);
+        set[void] = true;
+        update(&mut energy, void, 1.0);
+        if void == cluster {
+            break;
+        }
+    }
+
+    let mut ranks: Vec<usize> = vec![0; count];
+
+    // The initial pattern ranks downwards by taking out its tightest clusters first
+    let initial: Vec<bool> = set.clone();
+    let initialEnergy: Vec<f32> = energy.clone();
+    for rank in (0..initialCount).rev() {
+        let cluster: usize = extreme(&energy, &set, true, true);
+        set[cluster] = false;
+        update(&mut energy, cluster, -1.0);
+        ranks[cluster] = rank;
+    }
+
+    // Every other pixel ranks upwards by filling the largest void
+    set = initial;
+    energy = initialEnergy;
+    for rank in initialCount..count {
+        let void: usize = extreme(&energy, &set, false, false);
+        set[void] = true;
+        update(&mut energy, void, 1.0);
+        ranks[void] = rank;
+    }
+
+    return ranks.iter().map(|rank| (*rank as f32 + 0.5) / count as f32).collect();
+}
@@ -1,18 +1,43 @@
-﻿use std::ops::Deref;
+﻿use std::cell::RefCell;
+use std::ops::Deref;
 use std::ptr::null;
 use std::rc::Rc;
 use glm::{length, Mat4, mat4, Vec3, vec3, vec4};
+use crate::math::aabb::AABB;
 use rand::{Rng, thread_rng};
 use crate::{Dielectric, Lambertian, Metal, Sphere, VColor};
 use crate::math::ray::Ray;
+use crate::model::diffuseLight::DiffuseLight;
+use crate::model::heightfield::{GridAxes, Heightfield};
 use crate::model::hitRecord::HitRecord;
 use crate::model::hittableObject::HittableObject;
 use crate::model::material::Material;
 use crate::render::camera::Camera;
+use crate::render::environment::{Environment, GradientSky};
+use crate::render::integrator::Integrator;
+use crate::render::light::{Light, SphereLight};
+use crate::render::lightSampler::{createLightSampler, LightSampler, LightSampling, PowerLightSampler, UniformLightSampler};
+use crate::render::sampler::Sampler;
 
 pub struct Scene {
     cam: Camera,
     hittableObjects: Vec<Box<dyn HittableObject>>,
+    lights: Vec<Box<dyn Light>>,
+    /* Light that samples each object, for the emitters among them */
+    lightOfObject: Vec<Option<usize>>,
+    environment: Box<dyn Environment>,
+    environmentLight: Option<usize>,
+    lightSampling: LightSampling,
+    lightSampler: Box<dyn LightSampler>,
+    /* Picks the lights that paths start from, in proportion to their power */
+    emissionSampler: Box<dyn LightSampler>,
+    /* Sphere around all bounded objects, lights at infinity shine onto its cross section */
+    sceneCenter: Vec3,
+    sceneRadius: f32,
+    /* Integrator asked for by the scene file, handed over to the renderer */
+    integrator: Option<Box<dyn Integrator>>,
+    /* Sampler asked for by the scene file, handed over like the integrator */
+    sampler: Option<Rc<RefCell<dyn Sampler>>>,
     tMax: f32,
     tMin: f32,
 }
@@ -21,6 +46,7 @@ impl Scene {
     pub fn new(camera: Camera, objects: Vec<Box<dyn HittableObject>>) -> Self {
         return Self {
             cam: camera,
+            lightOfObject: vec![None; objects.len()],
             hittableObjects: objects,
             ..Default::default()
         };
@@ -39,30 +65,181 @@ impl Scene {
         let mut hitAnything: bool = false;
         let mut closestSoFar: f32 = self.tMax;
 
-        for object in self.hittableObjects.iter() {
+        for (objectId, object) in self.hittableObjects.iter().enumerate() {
             if object.testIntersection(ray, self.tMin, closestSoFar, hitRecord) {
                 hitAnything = true;
                 closestSoFar = hitRecord.t;
+                hitRecord.objectId = objectId;
             }
         }
 
         return hitAnything;
     }
 
+    /* Shadow ray test, `direction` has to be normalized so that `distance` is measured along it */
+    pub fn isVisible(&self, point: &Vec3, direction: &Vec3, distance: f32) -> bool {
+        let mut hitRecord: HitRecord = HitRecord::default();
+        if !self.Hit(&Ray::new(*point, *direction), &mut hitRecord) {
+            return true;
+        }
+        return hitRecord.t >= distance * (1.0 - 1e-4);
+    }
+
     pub fn getCamera(&self) -> &Camera {
         return &self.cam;
     }
 
     pub fn addObject(&mut self, object: Box<dyn HittableObject>) {
         self.hittableObjects.push(object);
+        self.lightOfObject.push(None);
+    }
+
+    /* Emissive sphere that is both visible and sampled as a light */
+    pub fn addSphereLight(&mut self, center: Vec3, radius: f32, color: VColor, intensity: f32) {
+        let material: DiffuseLight = DiffuseLight::new(color, intensity);
+        self.lights.push(Box::new(SphereLight::new(center, radius, material.getRadiance())));
+        self.hittableObjects.push(Box::new(Sphere::new(radius, center, Rc::new(material))));
+        self.lightOfObject.push(Some(self.lights.len() - 1));
+    }
+
+    pub fn addLight(&mut self, light: Box<dyn Light>) {
+        self.lights.push(light);
+    }
+
+    pub fn getLights(&self) -> &Vec<Box<dyn Light>> {
+        return &self.lights;
+    }
+
+    /* Also adds the environment's light to the scene's, so it should only be set once */
+    pub fn setEnvironment(&mut self, environment: Box<dyn Environment>) {
+        if let Some(light) = environment.light() {
+            self.lights.push(light);
+            self.environmentLight = Some(self.lights.len() - 1);
+        }
+        self.environment = environment;
+    }
+
+    pub fn getEnvironmentLight(&self) -> Option<usize> {
+        return self.environmentLight;
+    }
+
+    pub fn getLightOfObject(&self, objectId: usize) -> Option<usize> {
+        return match self.lightOfObject.get(objectId) {
+            Some(light) => *light,
+            None => None,
+        };
+    }
+
+    pub fn setIntegrator(&mut self, integrator: Box<dyn Integrator>) {
+        self.integrator = Some(integrator);
+    }
+
+    pub fn takeIntegrator(&mut self) -> Option<Box<dyn Integrator>> {
+        return self.integrator.take();
+    }
+
+    pub fn setSampler(&mut self, sampler: Rc<RefCell<dyn Sampler>>) {
+        self.sampler = Some(sampler);
+    }
+
+    pub fn takeSampler(&mut self) -> Option<Rc<RefCell<dyn Sampler>>> {
+        return self.sampler.take();
+    }
+
+    pub fn setLightSampling(&mut self, sampling: LightSampling) {
+        self.lightSampling = sampling;
+    }
+
+    /* Builds the light sampler over the lights added so far, has to run before rendering. The default sky gets its
+       light here if no environment was set */
+    pub fn prepareLights(&mut self) {
+        if self.environmentLight.is_none() {
+            if let Some(light) = self.environment.light() {
+                self.lights.push(light);
+                self.environmentLight = Some(self.lights.len() - 1);
+            }
+        }
+
+        let mut bounds: AABB = AABB::empty();
+        for object in self.hittableObjects.iter() {
+            let mut objectBox: AABB = AABB::default();
+            if object.boundingBox(&mut objectBox) {
+                bounds = AABB::surroundingBox(&bounds, &objectBox);
+            }
+        }
+
+        let extent: Vec3 = bounds.extent();
+        let bounded: bool = extent.x >= 0.0 && extent.x < f32::MAX;
+        self.sceneRadius = if bounded { f32::max(length(extent) * 0.5, 1.0) } else { 10.0 };
+        self.sceneCenter = if bounded { bounds.centroid() } else { vec3(0.0, 0.0, 0.0) };
+        self.lightSampler = createLightSampler(&self.lightSampling, &self.lights, self.sceneRadius);
+        self.emissionSampler = Box::new(PowerLightSampler::new(&self.lights, self.sceneRadius));
+    }
+
+    pub fn getSceneCenter(&self) -> Vec3 {
+        return self.sceneCenter;
+    }
+
+    pub fn getSceneRadius(&self) -> f32 {
+        return self.sceneRadius;
+    }
+
+    /* Sampler for the light a light path starts from, the same from everywhere */
+    pub fn getEmissionSampler(&self) -> &dyn LightSampler {
+        return self.emissionSampler.as_ref();
+    }
+
+    pub fn getLightSampler(&self) -> &dyn LightSampler {
+        return self.lightSampler.as_ref();
+    }
+
+    /* Density of direct lighting at `point` picking `direction` through the light, the choice of the light included */
+    pub fn lightPdf(&self, point: &Vec3, direction: &Vec3, light: usize) -> f32 {
+        return self.lightSampler.pmf(point, light) * self.lights[light].pdf(point, direction);
+    }
+
+    pub fn getEnvironment(&self) -> &dyn Environment {
+        return self.environment.as_ref();
     }
 
     pub fn randomScene() -> Self {
-        let mut scene: Self = Self::default();
+        let groundMaterial: Lambertian = Lambertian::new(VColor::new_sc(0.5));
+
+        return Scene::randomSceneOn(Box::new(Sphere::new(1000.0, vec3(0.0, 0.0, 1000.0), Rc::new(groundMaterial))));
+    }
 
+    /* The random scene standing on rolling noise terrain instead of the giant ground sphere */
+    pub fn randomSceneOnTerrain() -> Self {
         let groundMaterial: Lambertian = Lambertian::new(VColor::new_sc(0.5));
 
-        scene.addObject(Box::new(Sphere::new(1000.0, vec3(0.0, 0.0, 1000.0), Rc::new(groundMaterial))));
+        let axes: GridAxes = GridAxes {
+            origin: vec3(-30.0, -30.0, 0.25),
+            uAxis: vec3(60.0, 0.0, 0.0),
+            vAxis: vec3(0.0, 60.0, 0.0),
+            heightAxis: vec3(0.0, 0.0, -0.6),
+        };
+        let terrain: Heightfield = Heightfield::new_from_noise(256, 256, 6.0, 4, axes, Rc::new(groundMaterial));
+
+        return Scene::randomSceneOn(Box::new(terrain));
+    }
+
+    /* Center of a sphere resting on the ground below (x, y), up is -z */
+    fn restingOn(ground: &dyn HittableObject, x: f32, y: f32, radius: f32) -> Vec3 {
+        let mut hitRecord: HitRecord = HitRecord::default();
+        let groundZ: f32 = if ground.testIntersection(&Ray::new(vec3(x, y, -100.0), vec3(0.0, 0.0, 1.0)), 0.0, f32::MAX, &mut hitRecord) {
+            hitRecord.point.z
+        } else {
+            0.0
+        };
+        return vec3(x, y, groundZ - radius);
+    }
+
+    fn randomSceneOn(ground: Box<dyn HittableObject>) -> Self {
+        let mut scene: Self = Self::default();
+
+        scene.addObject(ground);
+        let ground: &dyn HittableObject = scene.hittableObjects[0].as_ref();
+        let mut spheres: Vec<Box<dyn HittableObject>> = vec![];
 
         for a in -11..11 {
             for b in -11..11 {
@@ -84,20 +261,23 @@ impl Scene {
                         sphereMat = Rc::new(Dielectric::new(1.5));
                     }
 
-                    scene.addObject(Box::new(Sphere::new(0.2, center, sphereMat)));
+                    spheres.push(Box::new(Sphere::new(0.2, Scene::restingOn(ground, center.x, center.y, 0.2), sphereMat)));
                 }
             }
         }
 
         let mat1: Rc<Dielectric> = Rc::new(Dielectric::new(1.5));
-        scene.addObject(Box::new(Sphere::new(1.0, vec3(0.0, 0.0, -1.0), mat1)));
+        spheres.push(Box::new(Sphere::new(1.0, Scene::restingOn(ground, 0.0, 0.0, 1.0), mat1)));
 
         let mat2: Rc<Lambertian> = Rc::new(Lambertian::new(VColor::new_rgb(0.4, 0.2, 0.1)));
-        scene.addObject(Box::new(Sphere::new(1.0, vec3(-4.0, 0.0, -1.0), mat2)));
+        spheres.push(Box::new(Sphere::new(1.0, Scene::restingOn(ground, -4.0, 0.0, 1.0), mat2)));
 
         let mat3: Rc<Metal> = Rc::new(Metal::new(VColor::new_rgb(0.7, 0.6, 0.5), 0.0));
-        scene.addObject(Box::new(Sphere::new(1.0, vec3(4.0, 0.0, -1.0), mat3)));
+        spheres.push(Box::new(Sphere::new(1.0, Scene::restingOn(ground, 4.0, 0.0, 1.0), mat3)));
 
+        for sphere in spheres {
+            scene.addObject(sphere);
+        }
 
         return scene;
     }
@@ -108,6 +288,17 @@ impl Default for Scene {
         return Self {
             cam: Camera::default(),
             hittableObjects: vec![],
+            lights: vec![],
+            lightOfObject: vec![],
+            environment: Box::new(GradientSky::new()),
+            environmentLight: None,
+            lightSampling: LightSampling::Bvh,
+            lightSampler: Box::new(UniformLightSampler::new(0)),
+            emissionSampler: Box::new(UniformLightSampler::new(0)),
+            sceneCenter: vec3(0.0, 0.0, 0.0),
+            sceneRadius: 10.0,
+            integrator: None,
+            sampler: None,
             tMax: f32::MAX,
             tMin: 0.001,
         };
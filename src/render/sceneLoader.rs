@@ -0,0 +1,558 @@
+use std::collections::HashMap;
+use std::fs;
+use std::rc::Rc;
+use glm::{IVec2, Vec3, vec3};
+use crate::math::aabb::AABB;
+use crate::math::vColor::VColor;
+use crate::model::coated::Coated;
+use crate::model::cone::Cone;
+use crate::model::csg::{Csg, CsgOperation};
+use crate::model::curve::{Curve, CurveType};
+use crate::model::cylinder::Cylinder;
+use crate::model::dielectric::{Dielectric, Dispersion};
+use crate::model::diffuseLight::DiffuseLight;
+use crate::model::disk::Disk;
+use crate::model::heightfield::{GridAxes, Heightfield};
+use crate::model::hittableObject::HittableObject;
+use crate::model::kajiyaKay::KajiyaKay;
+use crate::model::lambertian::Lambertian;
+use crate::model::masked::{MaskChannel, Masked, MaskTest};
+use crate::model::material::Material;
+use crate::model::mesh::Mesh;
+use crate::model::metal::Metal;
+use crate::model::normalMapped::NormalMapped;
+use crate::model::plane::Plane;
+use crate::model::sdf::{Mandelbulb, SdfBlend, SdfBox, SdfCapsule, SdfIntersection, SdfPlane, SdfRepeat, SdfRound, SdfSmoothSubtraction, SdfSmoothUnion,
+                        SdfSphere, SdfSubtraction, SdfTorus, SdfUnion, SignedDistance};
+use crate::model::sdfObject::SdfObject;
+use crate::model::sphere::Sphere;
+use crate::model::subdivision::SubdivisionScheme;
+use crate::model::texture::{ImageTexture, NoiseTexture, SolidColor, Texture};
+use crate::model::torus::Torus;
+use crate::render::camera::Camera;
+use crate::render::debugIntegrators::{AmbientOcclusionIntegrator, DepthIntegrator};
+use crate::render::environment::{EnvironmentMap, GradientSky, PreethamSky, UniformSky};
+use crate::render::integrator::{createIntegrator, INTEGRATOR_NAMES};
+use crate::render::light::{DirectionalLight, PointLight, SpotLight};
+use crate::render::lightSampler::LightSampling;
+use crate::render::mltIntegrator::MltIntegrator;
+use crate::render::photonIntegrator::PhotonIntegrator;
+use crate::render::sampler::{createSampler, SAMPLER_NAMES};
+use crate::render::scene::Scene;
+
+/* Reads a scene from a plain text file with one statement per line, see the README for the format */
+pub struct SceneLoader {
+    resolution: IVec2,
+    materials: HashMap<String, Rc<dyn Material>>,
+}
+
+/* Cursor over the whitespace separated tokens of one line */
+struct Tokens<'a> {
+    tokens: Vec<&'a str>,
+    position: usize,
+    lineNumber: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(line: &'a str, lineNumber: usize) -> Self {
+        return Self {
+            tokens: line.split_whitespace().collect(),
+            position: 0,
+            lineNumber,
+        };
+    }
+
+    fn error(&self, message: &str) -> String {
+        return format!("line {}: {}", self.lineNumber, message);
+    }
+
+    fn hasMore(&self) -> bool {
+        return self.position < self.tokens.len();
+    }
+
+    fn word(&mut self) -> Result<&'a str, String> {
+        if !self.hasMore() {
+            return Err(self.error("unexpected end of line"));
+        }
+        self.position += 1;
+        return Ok(self.tokens[self.position - 1]);
+    }
+
+    fn float(&mut self) -> Result<f32, String> {
+        let word: &str = self.word()?;
+        return word.parse::<f32>().map_err(|_| self.error(&format!("expected a number, found '{}'", word)));
+    }
+
+    fn vec3(&mut self) -> Result<Vec3, String> {
+        return Ok(vec3(self.float()?, self.float()?, self.float()?));
+    }
+
+    fn color(&mut self) -> Result<VColor, String> {
+        return Ok(VColor::new_rgb(self.float()?, self.float()?, self.float()?));
+    }
+
+    /* Consumes the keyword if it is the next token */
+    fn keyword(&mut self, keyword: &str) -> bool {
+        if self.hasMore() && self.tokens[self.position] == keyword {
+            self.position += 1;
+            return true;
+        }
+        return false;
+    }
+
+    fn finish(&self) -> Result<(), String> {
+        if self.hasMore() {
+            return Err(self.error(&format!("unexpected '{}'", self.tokens[self.position])));
+        }
+        return Ok(());
+    }
+}
+
+impl SceneLoader {
+    pub fn new(resolution: &IVec2) -> Self {
+        return Self {
+            resolution: *resolution,
+            materials: HashMap::new(),
+        };
+    }
+
+    pub fn load(path: &str, resolution: &IVec2) -> Result<Scene, String> {
+        let source: String = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        return SceneLoader::new(resolution).parse(&source).map_err(|e| format!("{}: {}", path, e));
+    }
+
+    pub fn parse(&mut self, source: &str) -> Result<Scene, String> {
+        let mut scene: Scene = Scene::default();
+        scene.setCam(Camera::new(vec3(13.0, -2.0, -2.0), vec3(0.0, 0.0, 0.0), self.resolution.x, self.resolution.y, 0.1, 10.0, 20.0));
+
+        for (index, rawLine) in source.lines().enumerate() {
+            let line: &str = rawLine.split('#').next().unwrap_or("");
+            let mut tokens: Tokens = Tokens::new(line, index + 1);
+            if !tokens.hasMore() {
+                continue;
+            }
+
+            let statement: &str = tokens.word()?;
+            self.parseStatement(statement, &mut tokens, &mut scene)?;
+            tokens.finish()?;
+        }
+
+        return Ok(scene);
+    }
+
+    fn material(&self, tokens: &mut Tokens) -> Result<Rc<dyn Material>, String> {
+        let name: &str = tokens.word()?;
+        return match self.materials.get(name) {
+            Some(material) => Ok(Rc::clone(material)),
+            None => Err(tokens.error(&format!("unknown material '{}'", name))),
+        };
+    }
+
+    /* `image <path>`, `noise <scale> <octaves>` or `color <r g b>` */
+    fn texture(&self, tokens: &mut Tokens) -> Result<Rc<dyn Texture>, String> {
+        let kind: &str = tokens.word()?;
+        let texture: Rc<dyn Texture> = match kind {
+            "image" => {
+                let path: &str = tokens.word()?;
+                match ImageTexture::new_from_file(path) {
+                    Some(image) => Rc::new(image),
+                    None => return Err(tokens.error(&format!("cannot load image '{}'", path))),
+                }
+            }
+            "noise" => {
+                let scale: f32 = tokens.float()?;
+                Rc::new(NoiseTexture::new(scale, tokens.float()? as i32))
+            }
+            "color" => Rc::new(SolidColor::new(tokens.color()?)),
+            _ => return Err(tokens.error(&format!("unknown texture '{}'", kind))),
+        };
+        return Ok(texture);
+    }
+
+    fn parseMaterial(&self, tokens: &mut Tokens) -> Result<Rc<dyn Material>, String> {
+        let kind: &str = tokens.word()?;
+        let material: Rc<dyn Material> = match kind {
+            "lambertian" => Rc::new(Lambertian::new(tokens.color()?)),
+            "metal" => Rc::new(Metal::new(tokens.color()?, tokens.float()?)),
+            "dielectric" => {
+                let index: f32 = tokens.float()?;
+                if tokens.keyword("absorption") {
+                    Rc::new(Dielectric::new_absorbing(index, tokens.vec3()?))
+                } else {
+                    Rc::new(Dielectric::new(index))
+                }
+            }
+            "emissive" => Rc::new(DiffuseLight::new(tokens.color()?, tokens.float()?)),
+            "kajiya-kay" => Rc::new(KajiyaKay::new(tokens.color()?, tokens.color()?, tokens.float()?)),
+            "crown-glass" => Rc::new(Dielectric::new_crown_glass()),
+            "diamond" => Rc::new(Dielectric::new_diamond()),
+            "cauchy" => {
+                let a: f32 = tokens.float()?;
+                Rc::new(Dielectric::new_dispersive(Dispersion::Cauchy { a, b: tokens.float()? }))
+            }
+            "coated" => {
+                let base: Rc<dyn Material> = self.material(tokens)?;
+                let index: f32 = tokens.float()?;
+                if tokens.keyword("film") {
+                    Rc::new(Coated::new_thin_film(base, index, tokens.float()?, tokens.float()?))
+                } else {
+                    Rc::new(Coated::new(base, index))
+                }
+            }
+            "normalmap" => {
+                let base: Rc<dyn Material> = self.material(tokens)?;
+                let texture: Rc<dyn Texture> = self.texture(tokens)?;
+                Rc::new(NormalMapped::new_normal_map(base, texture, tokens.float()?))
+            }
+            "bump" => {
+                let base: Rc<dyn Material> = self.material(tokens)?;
+                let texture: Rc<dyn Texture> = self.texture(tokens)?;
+                Rc::new(NormalMapped::new_bump(base, texture, tokens.float()?))
+            }
+            _ => return Err(tokens.error(&format!("unknown material type '{}'", kind))),
+        };
+
+        return Ok(material);
+    }
+
+    fn parseStatement(&mut self, statement: &str, tokens: &mut Tokens, scene: &mut Scene) -> Result<(), String> {
+        match statement {
+            "camera" => {
+                let position: Vec3 = tokens.vec3()?;
+                let lookAt: Vec3 = tokens.vec3()?;
+                let aperture: f32 = tokens.float()?;
+                let focusDist: f32 = tokens.float()?;
+                let fov: f32 = tokens.float()?;
+                scene.setCam(Camera::new(position, lookAt, self.resolution.x, self.resolution.y, aperture, focusDist, fov));
+            }
+            "material" => {
+                let name: String = tokens.word()?.to_string();
+                let material: Rc<dyn Material> = self.parseMaterial(tokens)?;
+                self.materials.insert(name, material);
+            }
+            "sky" => {
+                let model: &str = tokens.word()?;
+                match model {
+                    "gradient" => scene.setEnvironment(Box::new(GradientSky::new())),
+                    "color" => scene.setEnvironment(Box::new(UniformSky::new(tokens.color()?))),
+                    "hdr" => {
+                        let path: &str = tokens.word()?;
+                        let (rotation, scale) = (tokens.float()?, tokens.float()?);
+                        let map: EnvironmentMap = EnvironmentMap::load(path, rotation, scale).map_err(|e| tokens.error(&e))?;
+                        scene.setEnvironment(Box::new(map));
+                    }
+                    "preetham" => scene.setEnvironment(Box::new(PreethamSky::new(tokens.float()?, tokens.float()?, tokens.float()?))),
+                    _ => return Err(tokens.error(&format!("unknown sky '{}'", model))),
+                }
+            }
+            "pointlight" => {
+                scene.addLight(Box::new(PointLight::new(tokens.vec3()?, tokens.color()?, tokens.float()?)));
+            }
+            "spotlight" => {
+                let (position, direction) = (tokens.vec3()?, tokens.vec3()?);
+                let (color, intensity) = (tokens.color()?, tokens.float()?);
+                scene.addLight(Box::new(SpotLight::new(position, direction, color, intensity, tokens.float()?, tokens.float()?)));
+            }
+            "directionallight" => {
+                scene.addLight(Box::new(DirectionalLight::new(tokens.vec3()?, tokens.color()?, tokens.float()?)));
+            }
+            "spherelight" => {
+                let (center, radius) = (tokens.vec3()?, tokens.float()?);
+                scene.addSphereLight(center, radius, tokens.color()?, tokens.float()?);
+            }
+            "integrator" => {
+                let name: &str = tokens.word()?;
+                match name {
+                    "depth" if tokens.hasMore() => scene.setIntegrator(Box::new(DepthIntegrator::new(tokens.float()?))),
+                    "ao" if tokens.hasMore() => {
+                        let samples: i32 = tokens.float()? as i32;
+                        scene.setIntegrator(Box::new(AmbientOcclusionIntegrator::new(samples, tokens.float()?)));
+                    }
+                    "photons" if tokens.hasMore() => {
+                        let photonCount: usize = tokens.float()? as usize;
+                        scene.setIntegrator(Box::new(PhotonIntegrator::new(photonCount, tokens.float()?)));
+                    }
+                    "sppm" if tokens.hasMore() => {
+                        let photonCount: usize = tokens.float()? as usize;
+                        let radius: f32 = tokens.float()?;
+                        scene.setIntegrator(Box::new(PhotonIntegrator::new_progressive(photonCount, radius, tokens.float()?)));
+                    }
+                    "mlt" if tokens.hasMore() => {
+                        let bootstrapSamples: usize = tokens.float()? as usize;
+                        let chainCount: usize = tokens.float()? as usize;
+                        let largeStepProbability: f32 = tokens.float()?;
+                        scene.setIntegrator(Box::new(MltIntegrator::new(bootstrapSamples, chainCount, largeStepProbability, tokens.float()?)));
+                    }
+                    _ => match createIntegrator(name) {
+                        Some(integrator) => scene.setIntegrator(integrator),
+                        None => return Err(tokens.error(&format!("unknown integrator '{}', expected one of {}", name, INTEGRATOR_NAMES.join(", ")))),
+                    },
+                }
+            }
+            "sampler" => {
+                let name: &str = tokens.word()?;
+                match createSampler(name) {
+                    Some(sampler) => scene.setSampler(sampler),
+                    None => return Err(tokens.error(&format!("unknown sampler '{}', expected one of {}", name, SAMPLER_NAMES.join(", ")))),
+                }
+            }
+            "lightsampling" => {
+                let strategy: &str = tokens.word()?;
+                scene.setLightSampling(match strategy {
+                    "uniform" => LightSampling::Uniform,
+                    "power" => LightSampling::Power,
+                    "bvh" => LightSampling::Bvh,
+                    _ => return Err(tokens.error(&format!("unknown light sampling '{}'", strategy))),
+                });
+            }
+            _ => match self.parseObject(statement, tokens)? {
+                Some(object) => scene.addObject(object),
+                None => return Err(tokens.error(&format!("unknown statement '{}'", statement))),
+            },
+        }
+
+        return Ok(());
+    }
+
+    /* An object statement, which is how wrappers like `masked` take the object they wrap */
+    fn object(&self, tokens: &mut Tokens) -> Result<Box<dyn HittableObject>, String> {
+        let kind: &str = tokens.word()?;
+        return match self.parseObject(kind, tokens)? {
+            Some(object) => Ok(object),
+            None => Err(tokens.error(&format!("unknown object '{}'", kind))),
+        };
+    }
+
+    /* None if `kind` does not name an object */
+    fn parseObject(&self, kind: &str, tokens: &mut Tokens) -> Result<Option<Box<dyn HittableObject>>, String> {
+        let object: Box<dyn HittableObject> = match kind {
+            "sphere" => {
+                let material: Rc<dyn Material> = self.material(tokens)?;
+                let center: Vec3 = tokens.vec3()?;
+                Box::new(Sphere::new(tokens.float()?, center, material))
+            }
+            "plane" => {
+                let material: Rc<dyn Material> = self.material(tokens)?;
+                Box::new(Plane::new(tokens.vec3()?, tokens.vec3()?, material))
+            }
+            "disk" => {
+                let material: Rc<dyn Material> = self.material(tokens)?;
+                Box::new(Disk::new(tokens.vec3()?, tokens.vec3()?, tokens.float()?, material))
+            }
+            "cylinder" => {
+                let material: Rc<dyn Material> = self.material(tokens)?;
+                Box::new(Cylinder::new(tokens.vec3()?, tokens.vec3()?, tokens.float()?, tokens.float()?, material))
+            }
+            "cone" => {
+                let material: Rc<dyn Material> = self.material(tokens)?;
+                Box::new(Cone::new(tokens.vec3()?, tokens.vec3()?, tokens.float()?, tokens.float()?, material))
+            }
+            "torus" => {
+                let material: Rc<dyn Material> = self.material(tokens)?;
+                Box::new(Torus::new(tokens.vec3()?, tokens.vec3()?, tokens.float()?, tokens.float()?, material))
+            }
+            "curve" => {
+                let material: Rc<dyn Material> = self.material(tokens)?;
+                let controlPoints: [Vec3; 4] = [tokens.vec3()?, tokens.vec3()?, tokens.vec3()?, tokens.vec3()?];
+                let (width0, width1) = (tokens.float()?, tokens.float()?);
+                let curveType: CurveType = if tokens.keyword("cylinder") {
+                    CurveType::Cylinder
+                } else {
+                    tokens.keyword("ribbon");
+                    CurveType::Ribbon
+                };
+                Box::new(Curve::new(controlPoints, width0, width1, curveType, material))
+            }
+            "heightfield" => {
+                let material: Rc<dyn Material> = self.material(tokens)?;
+                let source: &str = tokens.word()?;
+                let heightfield: Heightfield = match source {
+                    "image" => {
+                        let path: &str = tokens.word()?;
+                        match Heightfield::new_from_image(path, SceneLoader::gridAxes(tokens)?, material) {
+                            Some(heightfield) => heightfield,
+                            None => return Err(tokens.error(&format!("cannot load height map '{}'", path))),
+                        }
+                    }
+                    "noise" => {
+                        let columns: usize = tokens.float()? as usize;
+                        let rows: usize = tokens.float()? as usize;
+                        if columns < 2 || rows < 2 {
+                            return Err(tokens.error("a heightfield needs at least 2x2 samples"));
+                        }
+                        let frequency: f32 = tokens.float()?;
+                        let octaves: i32 = tokens.float()? as i32;
+                        Heightfield::new_from_noise(columns, rows, frequency, octaves, SceneLoader::gridAxes(tokens)?, material)
+                    }
+                    _ => return Err(tokens.error(&format!("unknown heightfield source '{}'", source))),
+                };
+                Box::new(heightfield)
+            }
+            "mesh" => {
+                let material: Rc<dyn Material> = self.material(tokens)?;
+                let path: &str = tokens.word()?;
+                let schemeName: &str = tokens.word()?;
+                let scheme: SubdivisionScheme = match schemeName {
+                    "catmull-clark" => SubdivisionScheme::CatmullClark,
+                    "loop" => SubdivisionScheme::Loop,
+                    _ => return Err(tokens.error(&format!("unknown subdivision scheme '{}'", schemeName))),
+                };
+                let levels: i32 = tokens.float()? as i32;
+                Box::new(Mesh::load_obj(path, scheme, levels, material).map_err(|e| tokens.error(&e))?)
+            }
+            "masked" => {
+                let mask: Rc<dyn Texture> = self.texture(tokens)?;
+                let channel: MaskChannel = if tokens.keyword("alpha") { MaskChannel::Alpha } else { MaskChannel::Luminance };
+                let test: MaskTest = if tokens.keyword("stochastic") {
+                    MaskTest::Stochastic
+                } else if tokens.keyword("threshold") {
+                    MaskTest::Threshold(tokens.float()?)
+                } else {
+                    return Err(tokens.error("expected 'threshold <opacity>' or 'stochastic'"));
+                };
+                Box::new(Masked::new(self.object(tokens)?, mask, channel, test))
+            }
+            "csg" => {
+                let operationName: &str = tokens.word()?;
+                let operation: CsgOperation = match operationName {
+                    "union" => CsgOperation::Union,
+                    "intersection" => CsgOperation::Intersection,
+                    "difference" => CsgOperation::Difference,
+                    _ => return Err(tokens.error(&format!("unknown csg operation '{}'", operationName))),
+                };
+                Box::new(Csg::new(operation, self.object(tokens)?, self.object(tokens)?))
+            }
+            "sdf" => {
+                let material: Rc<dyn Material> = self.material(tokens)?;
+                let bounds: AABB = AABB::new(tokens.vec3()?, tokens.vec3()?);
+                let mut sdfObject: SdfObject = SdfObject::new(self.distanceField(tokens)?, bounds, material);
+                if tokens.keyword("march") {
+                    let maxSteps: i32 = tokens.float()? as i32;
+                    sdfObject.setMarching(maxSteps, tokens.float()?, tokens.float()?);
+                }
+                Box::new(sdfObject)
+            }
+            _ => return Ok(None),
+        };
+
+        return Ok(Some(object));
+    }
+
+    /* Origin, u axis, v axis and height axis of a heightfield */
+    fn gridAxes(tokens: &mut Tokens) -> Result<GridAxes, String> {
+        return Ok(GridAxes {
+            origin: tokens.vec3()?,
+            uAxis: tokens.vec3()?,
+            vAxis: tokens.vec3()?,
+            heightAxis: tokens.vec3()?,
+        });
+    }
+
+    /* A distance field in prefix notation, operators are followed by the shapes they combine */
+    fn distanceField(&self, tokens: &mut Tokens) -> Result<Box<dyn SignedDistance>, String> {
+        let kind: &str = tokens.word()?;
+        let field: Box<dyn SignedDistance> = match kind {
+            "sphere" => Box::new(SdfSphere::new(tokens.vec3()?, tokens.float()?)),
+            "box" => Box::new(SdfBox::new(tokens.vec3()?, tokens.vec3()?, tokens.float()?)),
+            "torus" => Box::new(SdfTorus::new(tokens.vec3()?, tokens.float()?, tokens.float()?)),
+            "capsule" => Box::new(SdfCapsule::new(tokens.vec3()?, tokens.vec3()?, tokens.float()?)),
+            "plane" => Box::new(SdfPlane::new(tokens.vec3()?, tokens.float()?)),
+            "mandelbulb" => {
+                let (center, scale, power) = (tokens.vec3()?, tokens.float()?, tokens.float()?);
+                Box::new(Mandelbulb::new(center, scale, power, tokens.float()? as i32))
+            }
+            "union" => Box::new(SdfUnion::new(self.distanceField(tokens)?, self.distanceField(tokens)?)),
+            "intersection" => Box::new(SdfIntersection::new(self.distanceField(tokens)?, self.distanceField(tokens)?)),
+            "subtraction" => Box::new(SdfSubtraction::new(self.distanceField(tokens)?, self.distanceField(tokens)?)),
+            "smoothunion" => {
+                let k: f32 = tokens.float()?;
+                Box::new(SdfSmoothUnion::new(self.distanceField(tokens)?, self.distanceField(tokens)?, k))
+            }
+            "smoothsubtraction" => {
+                let k: f32 = tokens.float()?;
+                Box::new(SdfSmoothSubtraction::new(self.distanceField(tokens)?, self.distanceField(tokens)?, k))
+            }
+            "blend" => {
+                let t: f32 = tokens.float()?;
+                Box::new(SdfBlend::new(self.distanceField(tokens)?, self.distanceField(tokens)?, t))
+            }
+            "round" => {
+                let radius: f32 = tokens.float()?;
+                Box::new(SdfRound::new(self.distanceField(tokens)?, radius))
+            }
+            "repeat" => {
+                let period: Vec3 = tokens.vec3()?;
+                Box::new(SdfRepeat::new(self.distanceField(tokens)?, period))
+            }
+            _ => return Err(tokens.error(&format!("unknown distance field '{}'", kind))),
+        };
+        return Ok(field);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glm::ivec2;
+    use crate::math::ray::Ray;
+    use crate::model::hitRecord::HitRecord;
+    use super::*;
+
+    fn firstHit(scene: &Scene, origin: Vec3, direction: Vec3) -> Option<HitRecord> {
+        let mut hitRecord: HitRecord = HitRecord::default();
+        if !scene.Hit(&Ray::new(origin, direction), &mut hitRecord) {
+            return None;
+        }
+        return Some(hitRecord);
+    }
+
+    #[test]
+    fn maskedWrapsAnObject() {
+        let source: &str = "material white lambertian 1 1 1\n\
+                            masked color 0 0 0 threshold 0.5 sphere white 0 0 0 1\n\
+                            masked color 1 1 1 alpha stochastic disk white 0 0 3 0 0 1 1\n";
+        let scene: Scene = SceneLoader::new(&ivec2(8, 8)).parse(source).unwrap();
+        let hit: HitRecord = firstHit(&scene, vec3(0.0, 0.0, -5.0), vec3(0.0, 0.0, 1.0)).unwrap();
+        assert_eq!(hit.objectId, 1);
+
+        let error: String = SceneLoader::new(&ivec2(8, 8)).parse("masked color 1 1 1 threshold 0.5 box").err().unwrap();
+        assert_eq!(error, "line 1: unknown object 'box'");
+    }
+
+    #[test]
+    fn normalMapsWrapAMaterial() {
+        let source: &str = "material light emissive 1 0.5 0.25 2\n\
+                            material bumpy bump light noise 4 2 0.1\n\
+                            material mapped normalmap bumpy color 0.5 0.5 1 1\n\
+                            sphere mapped 0 0 0 1\n";
+        let scene: Scene = SceneLoader::new(&ivec2(8, 8)).parse(source).unwrap();
+        let ray: Ray = Ray::new(vec3(0.0, 0.0, -5.0), vec3(0.0, 0.0, 1.0));
+        let hit: HitRecord = firstHit(&scene, ray.origin, ray.direction).unwrap();
+        assert_eq!(hit.material.emitted(&ray, &hit).getColorRGB(), vec3(2.0, 1.0, 0.5));
+
+        let error: String = SceneLoader::new(&ivec2(8, 8)).parse("material m lambertian 1 1 1\nmaterial n bump m wood 1").err().unwrap();
+        assert_eq!(error, "line 2: unknown texture 'wood'");
+    }
+
+    #[test]
+    fn csgNests() {
+        let source: &str = "material white lambertian 1 1 1\n\
+                            csg difference csg union sphere white 0 0 0 1 sphere white 1 0 0 1 sphere white 2 0 0 0.5\n";
+        let scene: Scene = SceneLoader::new(&ivec2(8, 8)).parse(source).unwrap();
+        let hit: HitRecord = firstHit(&scene, vec3(5.0, 0.0, 0.0), vec3(-1.0, 0.0, 0.0)).unwrap();
+        assert!((hit.t - 3.5).abs() < 1e-3);
+
+        let error: String = SceneLoader::new(&ivec2(8, 8)).parse("csg xor").err().unwrap();
+        assert_eq!(error, "line 1: unknown csg operation 'xor'");
+    }
+
+    #[test]
+    fn sdfTakesAFieldTree() {
+        let source: &str = "material white lambertian 1 1 1\n\
+                            sdf white -2 -2 -2 2 2 2 subtraction sphere 0 0 0 1 box 1 0 0 0.5 0.5 0.5 0 march 128 0.0001 1\n";
+        let scene: Scene = SceneLoader::new(&ivec2(8, 8)).parse(source).unwrap();
+        let hit: HitRecord = firstHit(&scene, vec3(-5.0, 0.0, 0.0), vec3(1.0, 0.0, 0.0)).unwrap();
+        assert!((hit.t - 4.0).abs() < 1e-3);
+        let hit: HitRecord = firstHit(&scene, vec3(5.0, 0.0, 0.0), vec3(-1.0, 0.0, 0.0)).unwrap();
+        assert!((hit.t - 4.5).abs() < 1e-3);
+    }
+}